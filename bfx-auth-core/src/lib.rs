@@ -4,8 +4,8 @@ use bfx_proto::auth::{
     ChangePasswordReply, ChangePasswordRequest, CreateUserReply, CreateUserRequest,
     GetUserByEmailReply, GetUserByEmailRequest, GetUserByTokenReply, GetUserByTokenRequest,
     GetUsersByIdsReply, GetUsersByIdsRequest, LoginEmailReply, LoginEmailRequest,
-    LoginExternalReply, LoginExternalRequest, SendVerificationEmailReply,
-    SendVerificationEmailRequest, VerifyEmailReply, VerifyEmailRequest,
+    LoginExternalReply, LoginExternalRequest, LogoutReply, LogoutRequest,
+    SendVerificationEmailReply, SendVerificationEmailRequest, VerifyEmailReply, VerifyEmailRequest,
 };
 use tonic::transport::Channel;
 use tonic::{Request, Response, Status};
@@ -86,4 +86,11 @@ impl AuthCore for AuthCoreService {
     ) -> Result<Response<LoginExternalReply>, Status> {
         self.login_external(request).await
     }
+
+    async fn logout(
+        &self,
+        request: Request<LogoutRequest>,
+    ) -> Result<Response<LogoutReply>, Status> {
+        self.logout(request).await
+    }
 }
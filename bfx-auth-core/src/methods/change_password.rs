@@ -2,7 +2,10 @@ use crate::AuthCoreService;
 use crate::models::user::RawUser;
 use bfx_core::log_if_error::LogIfErrorExt;
 use bfx_core::status::{ErrorCode, StatusExt};
-use bfx_proto::auth::{ChangePasswordReply, ChangePasswordRequest};
+use bfx_proto::auth::password_recovery_client::PasswordRecoveryClient;
+use bfx_proto::auth::{
+    ChangePasswordReply, ChangePasswordRequest, InvalidatePasswordResetRequestsRequest,
+};
 use bfx_proto::notification::notification_client::NotificationClient;
 use bfx_proto::param_map;
 use chrono::Utc;
@@ -16,6 +19,7 @@ impl AuthCoreService {
     /// - If the user is not found
     /// - If `old_password` is provided but doesn't match the current password
     /// - If the new password is too weak
+    /// - If pending password reset requests can't be invalidated
     /// - Miscellaneous internal errors
     pub async fn change_password(
         &self,
@@ -82,19 +86,32 @@ impl AuthCoreService {
             .map_err(Status::db)?;
         }
 
+        // reset links for the old password shouldn't work anymore.
+        // the password is only changed if this succeeds, a leftover link could undo the change
+        PasswordRecoveryClient::new(self.router.clone())
+            .invalidate_password_reset_requests(InvalidatePasswordResetRequestsRequest {
+                user_id: user.id,
+            })
+            .await?;
+
         tx.commit().await.map_err(Status::db)?;
 
+        if request.terminate_all_sessions {
+            self.backchannel_logout(user.id).await;
+        }
+
         // send notifying email
         let mut notification = NotificationClient::new(self.router.clone());
         notification
             .send_notification(bfx_proto::notification::SendNotificationRequest {
                 user_id: user.id,
                 user_override: Some(user.into()),
-                definition: include_str!("../../notifications/password_change.yml").to_string(),
+                definition_id: "password_change".to_string(),
                 params: param_map! {
                     "audit_time" => Utc::now().to_rfc3339(),
                     "audit_ip" => user_context.ip,
                 },
+                send_at: None,
             })
             .await
             .log_if_error("sending password change notification");
@@ -102,3 +119,48 @@ impl AuthCoreService {
         Ok(Response::new(ChangePasswordReply {}))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bfx_core::service::database::Db;
+    use bfx_proto::UserContext;
+    use tonic::transport::Channel;
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn keep_password_if_reset_requests_stay_valid(db: Db) {
+        // nothing listens here, so invalidating the reset requests fails
+        let service = AuthCoreService {
+            db,
+            router: Channel::from_static("http://localhost:1").connect_lazy(),
+            frontend_root: "https://bfx.example.com".into(),
+        };
+
+        let user_id = sqlx::query_scalar::<_, i64>(
+            "insert into auth_core.users (email, password) values ('a@example.com', 'old hash')
+             returning id",
+        )
+        .fetch_one(&service.db)
+        .await
+        .unwrap();
+
+        let result = service
+            .change_password(Request::new(ChangePasswordRequest {
+                user_id,
+                old_password: None,
+                new_password: "correct horse battery staple".into(),
+                user_context: Some(UserContext::default()),
+                terminate_all_sessions: false,
+            }))
+            .await;
+        assert!(result.is_err());
+
+        let password =
+            sqlx::query_scalar::<_, String>("select password from auth_core.users where id = $1")
+                .bind(user_id)
+                .fetch_one(&service.db)
+                .await
+                .unwrap();
+        assert_eq!(password, "old hash");
+    }
+}
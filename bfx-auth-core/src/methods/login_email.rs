@@ -207,10 +207,11 @@ impl AuthCoreService {
             .send_notification(SendNotificationRequest {
                 user_id: user.id,
                 user_override: Some(user.into()),
-                definition: include_str!("../../notifications/account_login.yml").to_string(),
+                definition_id: "account_login".to_string(),
                 params: param_map! {
-                    "audit_ip" => user_context.ip.to_string(),
+                    "audit_ip" => user_context.ip.clone(),
                 },
+                send_at: None,
             })
             .await
             .log_if_error("sending login notification");
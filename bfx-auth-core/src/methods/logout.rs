@@ -0,0 +1,47 @@
+use crate::AuthCoreService;
+use bfx_core::log_if_error::LogIfErrorExt;
+use bfx_core::status::StatusExt;
+use bfx_proto::auth::auth_o_auth_provider_client::AuthOAuthProviderClient;
+use bfx_proto::auth::{BackchannelLogoutRequest, LogoutReply, LogoutRequest};
+use tonic::{Request, Response, Status};
+
+impl AuthCoreService {
+    /// End a session
+    ///
+    /// OAuth clients that support back-channel logout are notified.
+    /// Ending an already ended session is not an error.
+    ///
+    /// # Errors
+    ///
+    /// - Miscellaneous internal errors
+    pub async fn logout(
+        &self,
+        request: Request<LogoutRequest>,
+    ) -> Result<Response<LogoutReply>, Status> {
+        let request = request.into_inner();
+
+        let user_id = sqlx::query_scalar!(
+            "update auth_core.sessions set expires_at = now()
+             where id = $1 and expires_at > now()
+             returning user_id",
+            request.session_id,
+        )
+        .fetch_optional(&self.db)
+        .await
+        .map_err(Status::db)?;
+
+        if let Some(user_id) = user_id {
+            self.backchannel_logout(user_id).await;
+        }
+
+        Ok(Response::new(LogoutReply {}))
+    }
+
+    /// Notify OAuth clients that the user's sessions have ended
+    pub(crate) async fn backchannel_logout(&self, user_id: i64) {
+        AuthOAuthProviderClient::new(self.router.clone())
+            .backchannel_logout(BackchannelLogoutRequest { user_id })
+            .await
+            .log_if_error("sending back-channel logout");
+    }
+}
@@ -5,5 +5,6 @@ mod get_user_by_token;
 mod get_users_by_ids;
 mod login_email;
 mod login_external;
+mod logout;
 mod send_verification_email;
 mod verify_email;
@@ -78,13 +78,14 @@ impl AuthCoreService {
             .send_notification(SendNotificationRequest {
                 user_id: user.id,
                 user_override: Some(user.into()),
-                definition: include_str!("../../notifications/email_verification.yml").to_string(),
+                definition_id: "email_verification".to_string(),
                 params: param_map! {
                     "verify_url" => format!(
                         "{}/auth/verify-email?token={}",
                         self.frontend_root, email_verification_code
                     )
                 },
+                send_at: None,
             })
             .await?;
 
@@ -0,0 +1,142 @@
+use crate::AuthOAuthProviderService;
+use crate::models::client_info::ClientInfo;
+use crate::signing_keys::KeyAlgorithm;
+use bfx_core::service::id_encryption::IdType;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use nanoid::nanoid;
+use serde::{Deserialize, Serialize};
+use sqlx::types::chrono::Utc;
+use std::time::Duration;
+use tonic::Status;
+
+const OAUTH_ACCESS_TOKEN_LIFETIME: Duration = Duration::from_hours(1);
+// JWT access tokens can't be revoked before they expire (resource servers validate them offline)
+const JWT_ACCESS_TOKEN_LIFETIME: Duration = Duration::from_mins(5);
+
+const JWT_ACCESS_TOKEN_TYPE: &str = "at+jwt";
+
+/// Claims of a JWT access token (RFC 9068)
+#[derive(Serialize)]
+struct JwtAccessTokenClaims<'a> {
+    iss: &'a str,
+    exp: i64,
+    aud: &'a str,
+    sub: String,
+    client_id: &'a str,
+    iat: i64,
+    // same format as opaque access tokens, so the flow can be found from it
+    jti: &'a str,
+    scope: String,
+    // RFC 9449 section 6.1
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cnf: Option<Confirmation>,
+}
+
+/// Key that a token is bound to (RFC 7800 `cnf` claim)
+#[derive(Serialize)]
+pub struct Confirmation {
+    /// Thumbprint of the `DPoP` key
+    pub jkt: String,
+}
+
+#[derive(Deserialize)]
+struct UnverifiedJwtAccessTokenClaims {
+    jti: String,
+}
+
+impl AuthOAuthProviderService {
+    /// Make a new access token for a flow
+    ///
+    /// Clients with `jwt_access_tokens` get a signed RFC 9068 JWT, other clients get an opaque token.
+    /// Either way, the token must be stored in the flow, so it can be looked up and revoked.
+    /// JWTs of `DPoP`-bound tokens carry the key thumbprint as `cnf.jkt`.
+    ///
+    /// Returns the token and its lifetime.
+    ///
+    /// # Errors
+    ///
+    /// - If the JWT can't be signed
+    pub fn make_access_token(
+        &self,
+        client: &ClientInfo,
+        flow_id: i64,
+        user_id: Option<i64>,
+        scopes: &[String],
+        dpop_jkt: Option<&str>,
+    ) -> Result<(String, Duration), Status> {
+        let opaque_token = format!(
+            "BF/A/{}/{}",
+            self.id_encryptor.encrypt_id(IdType::OAuthFlow, flow_id),
+            nanoid!(32)
+        );
+
+        if !client.jwt_access_tokens {
+            return Ok((opaque_token, OAUTH_ACCESS_TOKEN_LIFETIME));
+        }
+
+        let now = Utc::now();
+        let claims = JwtAccessTokenClaims {
+            iss: self.issuer.as_str(),
+            exp: (now + JWT_ACCESS_TOKEN_LIFETIME).timestamp(),
+            aud: self.issuer.as_str(),
+            // client-only tokens act as the client itself
+            sub: user_id.map_or_else(
+                || client.client_id.clone(),
+                |user_id| self.get_subject(client, user_id),
+            ),
+            client_id: &client.client_id,
+            iat: now.timestamp(),
+            jti: &opaque_token,
+            scope: scopes.join(" "),
+            cnf: dpop_jkt.map(|jkt| Confirmation { jkt: jkt.into() }),
+        };
+
+        // RS256 is the only algorithm that resource servers are required to support
+        let token = self
+            .get_active_signing_key(KeyAlgorithm::Rs256)?
+            .sign_jwt(JWT_ACCESS_TOKEN_TYPE, &claims)?;
+
+        Ok((token, JWT_ACCESS_TOKEN_LIFETIME))
+    }
+
+    /// Get the type (`A`, `R`, ...) and the flow ID of a token issued by the provider
+    ///
+    /// The token is not validated, it must still be compared against the one stored in the flow.
+    #[must_use]
+    pub fn parse_token(&self, token: &str) -> Option<(String, i64)> {
+        // JWT access tokens carry the opaque token as `jti`
+        let jti;
+        let token = if token.starts_with("BF/") {
+            token
+        } else {
+            let mut validation = Validation::new(Algorithm::RS256);
+            validation.insecure_disable_signature_validation();
+            validation.validate_aud = false;
+            validation.validate_exp = false;
+            validation.set_required_spec_claims(&["jti"]);
+
+            jti = jsonwebtoken::decode::<UnverifiedJwtAccessTokenClaims>(
+                token,
+                &DecodingKey::from_secret(&[]),
+                &validation,
+            )
+            .ok()?
+            .claims
+            .jti;
+            &jti
+        };
+
+        let mut parts = token.split('/');
+        let (Some("BF"), Some(token_type), Some(flow_id)) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            return None;
+        };
+        let flow_id = self
+            .id_encryptor
+            .decrypt_id(IdType::OAuthFlow, flow_id)
+            .ok()?;
+
+        Some((token_type.to_string(), flow_id))
+    }
+}
@@ -0,0 +1,542 @@
+use crate::AuthOAuthProviderService;
+use crate::models::client_info::ClientInfo;
+use bfx_core::status::{ErrorCode, StatusExt};
+use bfx_proto::auth::BasicAuthorization;
+use jsonwebtoken::jwk::{AlgorithmParameters, JwkSet};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use nanoid::nanoid;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use sqlx::types::chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
+use strum::{Display, EnumString};
+use subtle::ConstantTimeEq;
+use tonic::{Code, Status};
+
+pub const JWT_BEARER_ASSERTION_TYPE: &str =
+    "urn:ietf:params:oauth:client-assertion-type:jwt-bearer";
+
+const ALLOWED_ASSERTION_ALGORITHMS: &[Algorithm] = &[
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::PS384,
+    Algorithm::PS512,
+    Algorithm::ES256,
+    Algorithm::ES384,
+    Algorithm::EdDSA,
+];
+
+// `jti`s are stored until the assertion expires, so don't allow them to live forever
+const MAX_ASSERTION_LIFETIME: Duration = Duration::from_mins(10);
+
+const MAX_JWKS_LENGTH: usize = 16 * 1024;
+
+/// Reason why a client failed to authenticate
+///
+/// This is returned to the client as `error_description`.
+pub type ClientAuthError = String;
+
+/// How a client authenticates at the back-channel endpoints
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Display, EnumString)]
+#[strum(serialize_all = "snake_case")]
+pub enum TokenEndpointAuthMethod {
+    /// The client secret, either as `client_secret_basic` or `client_secret_post`
+    ClientSecretBasic,
+    /// An assertion signed with a key of the client's registered JWKS
+    PrivateKeyJwt,
+    /// Only the `client_id`, for public clients that can't keep a secret
+    ///
+    /// These have to use PKCE and `DPoP` instead.
+    None,
+}
+
+#[derive(Deserialize)]
+struct ClientAssertionClaims {
+    jti: String,
+    exp: i64,
+}
+
+#[derive(Deserialize)]
+struct UnverifiedClientAssertionClaims {
+    iss: String,
+}
+
+/// Generate a new client secret and its hash
+pub fn generate_client_secret() -> (String, Vec<u8>) {
+    let client_secret = nanoid!(48);
+    let hash = hash_client_secret(&client_secret);
+    (client_secret, hash)
+}
+
+fn hash_client_secret(client_secret: &str) -> Vec<u8> {
+    Sha256::digest(client_secret.as_bytes()).to_vec()
+}
+
+/// Check a client secret against its hash in constant time
+fn verify_client_secret(client_secret: &str, hash: &[u8]) -> bool {
+    hash_client_secret(client_secret).ct_eq(hash).into()
+}
+
+/// Check that a client-registered JWKS can be used for `private_key_jwt`
+///
+/// # Errors
+///
+/// - If the JWKS is too long, can't be parsed, is empty or contains symmetric keys
+pub fn check_client_jwks(jwks: &str) -> Result<(), Status> {
+    let invalid = |details: &str| {
+        Status::coded(Code::InvalidArgument, ErrorCode::InvalidParameter).with_details(details)
+    };
+
+    if jwks.len() > MAX_JWKS_LENGTH {
+        return Err(invalid("JWKS is too long"));
+    }
+    let jwks: JwkSet = serde_json::from_str(jwks).map_err(|_| invalid("invalid JWKS"))?;
+    if jwks.keys.is_empty() {
+        return Err(invalid("JWKS has no keys"));
+    }
+    if jwks
+        .keys
+        .iter()
+        .any(|key| matches!(key.algorithm, AlgorithmParameters::OctetKey(_)))
+    {
+        return Err(invalid("JWKS must not contain symmetric keys"));
+    }
+
+    Ok(())
+}
+
+impl ClientInfo {
+    /// Get the effective authentication method of the client
+    ///
+    /// Clients with a registered JWKS always use `private_key_jwt`.
+    #[must_use]
+    pub fn token_endpoint_auth_method(&self) -> TokenEndpointAuthMethod {
+        if self.jwks.is_some() {
+            return TokenEndpointAuthMethod::PrivateKeyJwt;
+        }
+
+        match TokenEndpointAuthMethod::from_str(&self.token_endpoint_auth_method) {
+            Ok(TokenEndpointAuthMethod::None) => TokenEndpointAuthMethod::None,
+            _ => TokenEndpointAuthMethod::ClientSecretBasic,
+        }
+    }
+
+    /// Check if the client is a public client (`token_endpoint_auth_method` `none`)
+    #[must_use]
+    pub fn is_public(&self) -> bool {
+        self.token_endpoint_auth_method() == TokenEndpointAuthMethod::None
+    }
+}
+
+impl AuthOAuthProviderService {
+    /// Authenticate the client calling a back-channel endpoint
+    ///
+    /// Supports `client_secret_basic`, `client_secret_post`, `private_key_jwt` and `none`.
+    /// Clients can only use the method they are registered with.
+    /// The used parameters are removed from `params`.
+    ///
+    /// # Errors
+    ///
+    /// - Returns `Ok(Err(_))` if the client failed to authenticate
+    /// - Miscellaneous internal errors
+    pub async fn authenticate_client(
+        &self,
+        params: &mut HashMap<String, String>,
+        authorization: Option<BasicAuthorization>,
+    ) -> Result<Result<ClientInfo, ClientAuthError>, Status> {
+        let client_assertion_type = params.remove("client_assertion_type");
+        let client_assertion = params.remove("client_assertion");
+
+        let (basic_client_id, basic_client_secret) = authorization
+            .map(|basic| (basic.username, basic.password))
+            .unzip();
+        let client_id = params.remove("client_id").or(basic_client_id);
+        let client_secret = params.remove("client_secret").or(basic_client_secret);
+
+        if let Some(client_assertion) = client_assertion {
+            if client_assertion_type.as_deref() != Some(JWT_BEARER_ASSERTION_TYPE) {
+                return Ok(Err("unsupported client_assertion_type".into()));
+            }
+
+            return self
+                .authenticate_client_assertion(client_id, &client_assertion)
+                .await;
+        }
+
+        let Some(client_id) = client_id else {
+            return Ok(Err("missing parameter `client_id`".into()));
+        };
+
+        let Some(client) = self.get_client_by_client_id(&client_id).await? else {
+            return Ok(Err("client not found".into()));
+        };
+
+        match client.token_endpoint_auth_method() {
+            TokenEndpointAuthMethod::ClientSecretBasic => {
+                let Some(client_secret) = client_secret else {
+                    return Ok(Err("missing parameter `client_secret`".into()));
+                };
+                if !verify_client_secret(&client_secret, &client.client_secret_hash) {
+                    return Ok(Err("wrong client secret".into()));
+                }
+            }
+            TokenEndpointAuthMethod::PrivateKeyJwt => {
+                return Ok(Err("client must authenticate with private_key_jwt".into()));
+            }
+            TokenEndpointAuthMethod::None => {
+                if client_secret.is_some() {
+                    return Ok(Err("public clients must not use a client secret".into()));
+                }
+            }
+        }
+
+        Ok(Ok(client))
+    }
+
+    /// Authenticate a client with a `private_key_jwt` assertion (RFC 7523)
+    async fn authenticate_client_assertion(
+        &self,
+        client_id: Option<String>,
+        client_assertion: &str,
+    ) -> Result<Result<ClientInfo, ClientAuthError>, Status> {
+        let Ok(header) = jsonwebtoken::decode_header(client_assertion) else {
+            return Ok(Err("invalid client_assertion".into()));
+        };
+        if !ALLOWED_ASSERTION_ALGORITHMS.contains(&header.alg) {
+            return Ok(Err("unsupported client_assertion algorithm".into()));
+        }
+
+        // client_id is optional when using assertions, the issuer is the client
+        let client_id = if let Some(client_id) = client_id {
+            client_id
+        } else {
+            let mut validation = Validation::new(header.alg);
+            validation.insecure_disable_signature_validation();
+            validation.validate_aud = false;
+            validation.validate_exp = false;
+            validation.set_required_spec_claims(&["iss"]);
+
+            let Ok(unverified) = jsonwebtoken::decode::<UnverifiedClientAssertionClaims>(
+                client_assertion,
+                &DecodingKey::from_secret(&[]),
+                &validation,
+            ) else {
+                return Ok(Err("invalid client_assertion".into()));
+            };
+            unverified.claims.iss
+        };
+
+        let Some(client) = self.get_client_by_client_id(&client_id).await? else {
+            return Ok(Err("client not found".into()));
+        };
+        let Some(jwks) = &client.jwks else {
+            return Ok(Err("client has no registered JWKS".into()));
+        };
+        let jwks: JwkSet = serde_json::from_str(jwks)
+            .map_err(|err| Status::coded(Code::Internal, ErrorCode::Internal).with_source(err))?;
+
+        let jwk = match &header.kid {
+            Some(kid) => jwks.find(kid),
+            None if jwks.keys.len() == 1 => jwks.keys.first(),
+            None => None,
+        };
+        let Some(jwk) = jwk else {
+            return Ok(Err("client_assertion signing key not found".into()));
+        };
+        let Ok(decoding_key) = DecodingKey::from_jwk(jwk) else {
+            return Ok(Err("client_assertion signing key is invalid".into()));
+        };
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_audience(&[
+            format!("{}/openid/token", self.frontend_root),
+            self.issuer.to_string(),
+        ]);
+        validation.set_issuer(&[&client.client_id]);
+        validation.sub = Some(client.client_id.clone());
+        validation.set_required_spec_claims(&["exp", "iss", "sub", "aud"]);
+
+        let claims = match jsonwebtoken::decode::<ClientAssertionClaims>(
+            client_assertion,
+            &decoding_key,
+            &validation,
+        ) {
+            Ok(data) => data.claims,
+            Err(err) => return Ok(Err(format!("invalid client_assertion: {err}"))),
+        };
+
+        let Some(expires_at) = DateTime::<Utc>::from_timestamp(claims.exp, 0) else {
+            return Ok(Err("invalid client_assertion expiration".into()));
+        };
+        if expires_at > Utc::now() + MAX_ASSERTION_LIFETIME {
+            return Ok(Err("client_assertion expires too late".into()));
+        }
+
+        // prevent replay
+        sqlx::query!("delete from auth_oauth_provider.client_assertions where expires_at < now()",)
+            .execute(&self.db)
+            .await
+            .map_err(Status::db)?;
+
+        let result = sqlx::query!(
+            "insert into auth_oauth_provider.client_assertions (client_id, jti, expires_at)
+             values ($1, $2, $3)
+             on conflict do nothing",
+            client.id,
+            claims.jti,
+            expires_at,
+        )
+        .execute(&self.db)
+        .await
+        .map_err(Status::db)?;
+        if result.rows_affected() == 0 {
+            return Ok(Err("client_assertion has already been used".into()));
+        }
+
+        Ok(Ok(client))
+    }
+
+    /// Get a client by its internal ID
+    ///
+    /// # Errors
+    ///
+    /// - If the client does not exist
+    /// - Miscellaneous internal errors
+    pub async fn get_client_by_id(&self, id: i64) -> Result<ClientInfo, Status> {
+        sqlx::query_as!(
+            ClientInfo,
+            "select * from auth_oauth_provider.clients where id = $1",
+            id,
+        )
+        .fetch_optional(&self.db)
+        .await
+        .map_err(Status::db)?
+        .ok_or_else(|| Status::coded(Code::NotFound, ErrorCode::ClientNotFound))
+    }
+
+    async fn get_client_by_client_id(&self, client_id: &str) -> Result<Option<ClientInfo>, Status> {
+        sqlx::query_as!(
+            ClientInfo,
+            "select * from auth_oauth_provider.clients where client_id = $1",
+            client_id,
+        )
+        .fetch_optional(&self.db)
+        .await
+        .map_err(Status::db)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{
+        TOKEN_ENDPOINT, generate_es256, jwks, public_client_details, test_client_details,
+    };
+    use bfx_core::service::database::Db;
+    use bfx_proto::auth::ClientDetails;
+    use jsonwebtoken::{EncodingKey, Header};
+    use serde_json::{Value, json};
+
+    fn make_assertion(key: &EncodingKey, kid: &str, claims: &Value) -> String {
+        let mut header = Header::new(Algorithm::ES256);
+        header.kid = Some(kid.into());
+        jsonwebtoken::encode(&header, claims, key).unwrap()
+    }
+
+    fn assertion_claims(client_id: &str) -> Value {
+        json!({
+            "iss": client_id,
+            "sub": client_id,
+            "aud": TOKEN_ENDPOINT,
+            "exp": (Utc::now() + Duration::from_mins(1)).timestamp(),
+            "jti": nanoid!(),
+        })
+    }
+
+    async fn authenticate(
+        service: &AuthOAuthProviderService,
+        params: &[(&str, &str)],
+        authorization: Option<(&str, &str)>,
+    ) -> Result<ClientInfo, ClientAuthError> {
+        let mut params = params
+            .iter()
+            .map(|(key, value)| ((*key).to_string(), (*value).to_string()))
+            .collect();
+        let authorization = authorization.map(|(username, password)| BasicAuthorization {
+            username: username.into(),
+            password: password.into(),
+        });
+
+        service
+            .authenticate_client(&mut params, authorization)
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn client_secret() {
+        let (client_secret, hash) = generate_client_secret();
+
+        assert_eq!(client_secret.len(), 48);
+        assert!(verify_client_secret(&client_secret, &hash));
+        assert!(!verify_client_secret(&client_secret[1..], &hash));
+        assert!(!verify_client_secret("", &hash));
+        assert!(!verify_client_secret(&client_secret, &[]));
+    }
+
+    #[test]
+    fn client_secrets_differ() {
+        let (secret_a, hash_a) = generate_client_secret();
+        let (secret_b, hash_b) = generate_client_secret();
+
+        assert_ne!(secret_a, secret_b);
+        assert!(!verify_client_secret(&secret_a, &hash_b));
+        assert!(!verify_client_secret(&secret_b, &hash_a));
+    }
+
+    #[test]
+    fn client_jwks() {
+        let (_, jwk) = generate_es256("key-1");
+        assert!(check_client_jwks(&jwks(&jwk)).is_ok());
+
+        assert!(check_client_jwks("not json").is_err());
+        assert!(check_client_jwks(r#"{"keys": []}"#).is_err());
+        assert!(check_client_jwks(r#"{"keys": [{"kty": "oct", "k": "c2VjcmV0"}]}"#).is_err());
+        assert!(check_client_jwks(&" ".repeat(MAX_JWKS_LENGTH + 1)).is_err());
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn secret_auth(db: Db) {
+        let service = AuthOAuthProviderService::for_tests(db);
+        let (client, client_secret) = service.create_test_client(test_client_details()).await;
+        let client_id = client.client_id.as_str();
+
+        let basic = authenticate(&service, &[], Some((client_id, &client_secret))).await;
+        assert_eq!(basic.unwrap().id, client.id);
+
+        let post = authenticate(
+            &service,
+            &[("client_id", client_id), ("client_secret", &client_secret)],
+            None,
+        )
+        .await;
+        assert_eq!(post.unwrap().id, client.id);
+
+        assert!(
+            authenticate(&service, &[], Some((client_id, "wrong")))
+                .await
+                .is_err()
+        );
+        assert!(
+            authenticate(&service, &[("client_id", client_id)], None)
+                .await
+                .is_err()
+        );
+        assert!(
+            authenticate(&service, &[], Some(("unknown", &client_secret)))
+                .await
+                .is_err()
+        );
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn assertion_auth(db: Db) {
+        let service = AuthOAuthProviderService::for_tests(db);
+        let (key, jwk) = generate_es256("key-1");
+        let (client, client_secret) = service
+            .create_test_client(ClientDetails {
+                jwks: Some(jwks(&jwk)),
+                ..test_client_details()
+            })
+            .await;
+        let client_id = client.client_id.as_str();
+
+        let assertion = make_assertion(&key, "key-1", &assertion_claims(client_id));
+        let params = [
+            ("client_assertion_type", JWT_BEARER_ASSERTION_TYPE),
+            ("client_assertion", &assertion),
+        ];
+        assert_eq!(
+            authenticate(&service, &params, None).await.unwrap().id,
+            client.id
+        );
+
+        // replay
+        assert!(authenticate(&service, &params, None).await.is_err());
+
+        // clients with a JWKS can't use their secret
+        assert!(
+            authenticate(&service, &[], Some((client_id, &client_secret)))
+                .await
+                .is_err()
+        );
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn invalid_assertions(db: Db) {
+        let service = AuthOAuthProviderService::for_tests(db);
+        let (key, jwk) = generate_es256("key-1");
+        let (client, _) = service
+            .create_test_client(ClientDetails {
+                jwks: Some(jwks(&jwk)),
+                ..test_client_details()
+            })
+            .await;
+        let client_id = client.client_id.as_str();
+
+        let (other_key, _) = generate_es256("key-1");
+        let mut wrong_audience = assertion_claims(client_id);
+        wrong_audience["aud"] = "https://other.example.com/token".into();
+        let mut expired = assertion_claims(client_id);
+        expired["exp"] = (Utc::now() - Duration::from_mins(5)).timestamp().into();
+        let mut long_lived = assertion_claims(client_id);
+        long_lived["exp"] = (Utc::now() + Duration::from_hours(1)).timestamp().into();
+        let mut wrong_subject = assertion_claims(client_id);
+        wrong_subject["sub"] = "someone-else".into();
+
+        let assertions = [
+            make_assertion(&other_key, "key-1", &assertion_claims(client_id)),
+            make_assertion(&key, "key-2", &assertion_claims(client_id)),
+            make_assertion(&key, "key-1", &wrong_audience),
+            make_assertion(&key, "key-1", &expired),
+            make_assertion(&key, "key-1", &long_lived),
+            make_assertion(&key, "key-1", &wrong_subject),
+        ];
+        for assertion in &assertions {
+            let params = [
+                ("client_assertion_type", JWT_BEARER_ASSERTION_TYPE),
+                ("client_assertion", assertion.as_str()),
+            ];
+            assert!(authenticate(&service, &params, None).await.is_err());
+        }
+
+        let valid = make_assertion(&key, "key-1", &assertion_claims(client_id));
+        let params = [
+            ("client_assertion_type", "urn:example:unsupported"),
+            ("client_assertion", valid.as_str()),
+        ];
+        assert!(authenticate(&service, &params, None).await.is_err());
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn public_client_auth(db: Db) {
+        let service = AuthOAuthProviderService::for_tests(db);
+        let (client, client_secret) = service.create_test_client(public_client_details()).await;
+        let client_id = client.client_id.as_str();
+        assert!(client.is_public());
+
+        let result = authenticate(&service, &[("client_id", client_id)], None).await;
+        assert_eq!(result.unwrap().id, client.id);
+
+        // the client is registered as public, so it must not have or use a secret
+        assert!(
+            authenticate(&service, &[], Some((client_id, &client_secret)))
+                .await
+                .is_err()
+        );
+    }
+}
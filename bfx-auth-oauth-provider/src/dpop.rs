@@ -0,0 +1,333 @@
+use crate::AuthOAuthProviderService;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use bfx_core::status::StatusExt;
+use jsonwebtoken::jwk::Jwk;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use openidconnect::url::Url;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use sqlx::types::chrono::{DateTime, Utc};
+use std::time::Duration;
+use tonic::Status;
+
+/// `token_type` of `DPoP`-bound access tokens
+pub const DPOP_TOKEN_TYPE: &str = "DPoP";
+
+const DPOP_PROOF_TYPE: &str = "dpop+jwt";
+
+// only asymmetric algorithms, the key is in the proof itself
+const ALLOWED_DPOP_ALGORITHMS: &[Algorithm] = &[
+    Algorithm::RS256,
+    Algorithm::PS256,
+    Algorithm::ES256,
+    Algorithm::ES384,
+    Algorithm::EdDSA,
+];
+
+// proofs are made right before the request, this also allows for some clock skew
+const MAX_DPOP_PROOF_AGE: Duration = Duration::from_mins(5);
+
+/// Reason why a `DPoP` proof was rejected
+///
+/// This is returned to the client as `error_description`.
+pub type DpopError = String;
+
+#[derive(Deserialize)]
+struct DpopProofClaims {
+    jti: String,
+    htm: String,
+    htu: String,
+    iat: i64,
+    ath: Option<String>,
+}
+
+/// Get the JWK SHA-256 thumbprint (RFC 7638) of a public key
+fn jwk_thumbprint(jwk: &Jwk) -> Option<String> {
+    let Value::Object(jwk) = serde_json::to_value(jwk).ok()? else {
+        return None;
+    };
+
+    // the required members of each key type, in lexicographic order
+    let members: &[&str] = match jwk.get("kty")?.as_str()? {
+        "EC" => &["crv", "kty", "x", "y"],
+        "RSA" => &["e", "kty", "n"],
+        "OKP" => &["crv", "kty", "x"],
+        _ => return None,
+    };
+    let mut required = Map::new();
+    for member in members {
+        required.insert((*member).to_string(), jwk.get(*member)?.clone());
+    }
+
+    // the members are inserted in order and serialized without whitespace
+    let json = serde_json::to_string(&required).ok()?;
+    Some(URL_SAFE_NO_PAD.encode(Sha256::digest(json.as_bytes())))
+}
+
+/// Get the `ath` claim for an access token
+fn access_token_hash(access_token: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(access_token.as_bytes()))
+}
+
+/// Compare two URIs, ignoring query and fragment (RFC 9449 section 4.3)
+fn is_same_uri(a: &str, b: &str) -> bool {
+    let normalize = |uri: &str| {
+        let mut url = Url::parse(uri).ok()?;
+        url.set_query(None);
+        url.set_fragment(None);
+        Some(url)
+    };
+
+    normalize(a).is_some_and(|a| Some(a) == normalize(b))
+}
+
+impl AuthOAuthProviderService {
+    /// Validate a `DPoP` proof (RFC 9449) and get the JWK thumbprint of its key
+    ///
+    /// `http_path` is relative to `FRONTEND_ROOT`, since all public endpoints are served there.
+    /// If the proof is for a request to a resource, `access_token` must be given.
+    ///
+    /// # Errors
+    ///
+    /// - Returns `Ok(Err(_))` if the proof is invalid or has already been used
+    /// - Miscellaneous internal errors
+    pub async fn verify_dpop_proof(
+        &self,
+        proof: &str,
+        http_method: &str,
+        http_path: &str,
+        access_token: Option<&str>,
+    ) -> Result<Result<String, DpopError>, Status> {
+        let Ok(header) = jsonwebtoken::decode_header(proof) else {
+            return Ok(Err("invalid DPoP proof".into()));
+        };
+        if header.typ.as_deref() != Some(DPOP_PROOF_TYPE) {
+            return Ok(Err("invalid DPoP proof type".into()));
+        }
+        if !ALLOWED_DPOP_ALGORITHMS.contains(&header.alg) {
+            return Ok(Err("unsupported DPoP proof algorithm".into()));
+        }
+
+        let Some(jwk) = &header.jwk else {
+            return Ok(Err("DPoP proof has no key".into()));
+        };
+        let (Some(jkt), Ok(decoding_key)) = (jwk_thumbprint(jwk), DecodingKey::from_jwk(jwk))
+        else {
+            return Ok(Err("DPoP proof key is invalid".into()));
+        };
+
+        let mut validation = Validation::new(header.alg);
+        validation.validate_exp = false;
+        validation.validate_aud = false;
+        validation.set_required_spec_claims::<&str>(&[]);
+
+        let claims =
+            match jsonwebtoken::decode::<DpopProofClaims>(proof, &decoding_key, &validation) {
+                Ok(data) => data.claims,
+                Err(err) => return Ok(Err(format!("invalid DPoP proof: {err}"))),
+            };
+
+        if !claims.htm.eq_ignore_ascii_case(http_method) {
+            return Ok(Err("DPoP proof `htm` does not match".into()));
+        }
+        if !is_same_uri(&claims.htu, &format!("{}{http_path}", self.frontend_root)) {
+            return Ok(Err("DPoP proof `htu` does not match".into()));
+        }
+
+        let Some(issued_at) = DateTime::<Utc>::from_timestamp(claims.iat, 0) else {
+            return Ok(Err("invalid DPoP proof `iat`".into()));
+        };
+        let now = Utc::now();
+        if issued_at < now - MAX_DPOP_PROOF_AGE || issued_at > now + MAX_DPOP_PROOF_AGE {
+            return Ok(Err("DPoP proof is expired or issued in the future".into()));
+        }
+
+        if let Some(access_token) = access_token
+            && claims.ath != Some(access_token_hash(access_token))
+        {
+            return Ok(Err("DPoP proof `ath` does not match".into()));
+        }
+
+        // prevent replay
+        sqlx::query!("delete from auth_oauth_provider.dpop_proofs where expires_at < now()")
+            .execute(&self.db)
+            .await
+            .map_err(Status::db)?;
+
+        let result = sqlx::query!(
+            "insert into auth_oauth_provider.dpop_proofs (jkt, jti, expires_at)
+             values ($1, $2, $3)
+             on conflict do nothing",
+            jkt,
+            claims.jti,
+            issued_at + MAX_DPOP_PROOF_AGE,
+        )
+        .execute(&self.db)
+        .await
+        .map_err(Status::db)?;
+        if result.rows_affected() == 0 {
+            return Ok(Err("DPoP proof has already been used".into()));
+        }
+
+        Ok(Ok(jkt))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{
+        TOKEN_ENDPOINT, dpop_proof_claims, generate_es256, make_dpop_proof, make_dpop_proof_with,
+    };
+    use bfx_core::service::database::Db;
+    use jsonwebtoken::Header;
+    use serde_json::json;
+
+    #[test]
+    fn rfc_7638_thumbprint() {
+        // example from RFC 7638 section 3.1
+        let jwk: Jwk = serde_json::from_value(json!({
+            "kty": "RSA",
+            "n": "0vx7agoebGcQSuuPiLJXZptN9nndrQmbXEps2aiAFbWhM78LhWx4cbbfAAtVT86zwu1RK7aPFFxuhDR1L6tS\
+                  oc_BJECPebWKRXjBZCiFV4n3oknjhMstn64tZ_2W-5JsGY4Hc5n9yBXArwl93lqt7_RN5w6Cf0h4QyQ5v-65YGjQR0_FD\
+                  W2QvzqY368QQMicAtaSqzs8KJZgnYb9c7d0zgdAZHzu6qMQvRL5hajrn1n91CbOpbISD08qNLyrdkt-bFTWhAI4vMQFh6\
+                  WeZu0fM4lFd2NcRwr3XPksINHaQ-G_xBniIqbw0Ls1jF44-csFCur-kEgU8awapJzKnqDKgw",
+            "e": "AQAB",
+            "alg": "RS256",
+            "kid": "2011-04-29",
+        }))
+        .unwrap();
+
+        assert_eq!(
+            jwk_thumbprint(&jwk).as_deref(),
+            Some("NzbLsXh8uDCcd-6MNwXF4W_7noWXFZAfHkxZsRGC9Xs")
+        );
+    }
+
+    #[test]
+    fn thumbprint_ignores_optional_members() {
+        let (_, jwk) = generate_es256("key-1");
+        let mut other_kid = jwk.clone();
+        other_kid.common.key_id = Some("key-2".into());
+        let (_, other_key) = generate_es256("key-1");
+
+        let thumbprint = jwk_thumbprint(&jwk).unwrap();
+        assert_eq!(jwk_thumbprint(&other_kid), Some(thumbprint.clone()));
+        assert_ne!(jwk_thumbprint(&other_key), Some(thumbprint));
+    }
+
+    #[test]
+    fn thumbprint_of_symmetric_key() {
+        let jwk: Jwk = serde_json::from_value(json!({"kty": "oct", "k": "c2VjcmV0"})).unwrap();
+
+        assert_eq!(jwk_thumbprint(&jwk), None);
+    }
+
+    #[test]
+    fn same_uri() {
+        assert!(is_same_uri(TOKEN_ENDPOINT, TOKEN_ENDPOINT));
+        assert!(is_same_uri(
+            &format!("{TOKEN_ENDPOINT}?a=b#c"),
+            TOKEN_ENDPOINT
+        ));
+        assert!(!is_same_uri(
+            "https://other.example.com/openid/token",
+            TOKEN_ENDPOINT
+        ));
+        assert!(!is_same_uri("not a uri", "not a uri"));
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn valid_proof(db: Db) {
+        let service = AuthOAuthProviderService::for_tests(db);
+        let (key, jwk) = generate_es256("key-1");
+
+        let proof = make_dpop_proof(&key, &jwk);
+        let jkt = service
+            .verify_dpop_proof(&proof, "POST", "/openid/token", None)
+            .await
+            .unwrap();
+        assert_eq!(jkt, Ok(jwk_thumbprint(&jwk).unwrap()));
+
+        // replay
+        let replayed = service
+            .verify_dpop_proof(&proof, "POST", "/openid/token", None)
+            .await
+            .unwrap();
+        assert!(replayed.is_err());
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn proof_for_access_token(db: Db) {
+        let service = AuthOAuthProviderService::for_tests(db);
+        let (key, jwk) = generate_es256("key-1");
+
+        let mut claims = dpop_proof_claims();
+        claims["htm"] = "GET".into();
+        claims["htu"] = "https://bfx.example.com/openid/userinfo".into();
+        claims["ath"] = access_token_hash("token").into();
+        let proof = make_dpop_proof_with(&key, &jwk, DPOP_PROOF_TYPE, &claims);
+
+        let other_token = service
+            .verify_dpop_proof(&proof, "GET", "/openid/userinfo", Some("other-token"))
+            .await
+            .unwrap();
+        assert!(other_token.is_err());
+
+        let jkt = service
+            .verify_dpop_proof(&proof, "GET", "/openid/userinfo", Some("token"))
+            .await
+            .unwrap();
+        assert_eq!(jkt, Ok(jwk_thumbprint(&jwk).unwrap()));
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn invalid_proofs(db: Db) {
+        let service = AuthOAuthProviderService::for_tests(db);
+        let (key, jwk) = generate_es256("key-1");
+        let (_, other_jwk) = generate_es256("key-1");
+
+        let mut wrong_method = dpop_proof_claims();
+        wrong_method["htm"] = "GET".into();
+        let mut wrong_uri = dpop_proof_claims();
+        wrong_uri["htu"] = "https://bfx.example.com/openid/revoke".into();
+        let mut too_old = dpop_proof_claims();
+        too_old["iat"] = (Utc::now() - Duration::from_mins(10)).timestamp().into();
+        let mut in_future = dpop_proof_claims();
+        in_future["iat"] = (Utc::now() + Duration::from_mins(10)).timestamp().into();
+        let mut without_ath = dpop_proof_claims();
+        without_ath["ath"] = Value::Null;
+
+        let mut header = Header::new(Algorithm::ES256);
+        header.typ = Some(DPOP_PROOF_TYPE.into());
+        let without_key = jsonwebtoken::encode(&header, &dpop_proof_claims(), &key).unwrap();
+
+        let proofs = [
+            make_dpop_proof_with(&key, &jwk, "JWT", &dpop_proof_claims()),
+            make_dpop_proof_with(&key, &other_jwk, DPOP_PROOF_TYPE, &dpop_proof_claims()),
+            make_dpop_proof_with(&key, &jwk, DPOP_PROOF_TYPE, &wrong_method),
+            make_dpop_proof_with(&key, &jwk, DPOP_PROOF_TYPE, &wrong_uri),
+            make_dpop_proof_with(&key, &jwk, DPOP_PROOF_TYPE, &too_old),
+            make_dpop_proof_with(&key, &jwk, DPOP_PROOF_TYPE, &in_future),
+            without_key,
+            "not a jwt".into(),
+        ];
+        for proof in &proofs {
+            let result = service
+                .verify_dpop_proof(proof, "POST", "/openid/token", None)
+                .await
+                .unwrap();
+            assert!(result.is_err(), "{proof}");
+        }
+
+        // proofs for a resource need the access token hash
+        let proof = make_dpop_proof_with(&key, &jwk, DPOP_PROOF_TYPE, &without_ath);
+        let result = service
+            .verify_dpop_proof(&proof, "POST", "/openid/token", Some("token"))
+            .await
+            .unwrap();
+        assert!(result.is_err());
+    }
+}
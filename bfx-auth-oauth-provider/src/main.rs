@@ -1,25 +1,48 @@
+mod access_token;
+mod client_auth;
+mod dpop;
 mod methods;
 pub mod models;
+mod public_dns;
+mod signing_keys;
+mod subject;
+#[cfg(test)]
+mod test_utils;
 
+use arc_swap::ArcSwap;
 use bfx_core::logging::setup_logging;
 use bfx_core::service::client::require_router;
 use bfx_core::service::database::{Db, require_db};
-use bfx_core::service::environment::{require_env, require_env_file};
+use bfx_core::service::environment::require_env;
 use bfx_core::service::id_encryption::{IdEncryptor, require_id_encryptor};
 use bfx_core::service::start_service;
 use bfx_proto::auth::auth_o_auth_provider_server::{AuthOAuthProvider, AuthOAuthProviderServer};
 use bfx_proto::auth::{
-    AcceptAuthorizationReply, AcceptAuthorizationRequest, GetAccessTokenReply,
-    GetAccessTokenRequest, GetAuthorizationInfoReply, GetAuthorizationInfoRequest, GetJwkSetReply,
-    GetJwkSetRequest, GetOpenidConfigurationReply, GetOpenidConfigurationRequest,
-    TokenEndpointReply, TokenEndpointRequest, UserinfoEndpointReply, UserinfoEndpointRequest,
+    AcceptAuthorizationReply, AcceptAuthorizationRequest, AcceptDeviceAuthorizationReply,
+    AcceptDeviceAuthorizationRequest, BackchannelLogoutReply, BackchannelLogoutRequest,
+    CreateClientReply, CreateClientRequest, DeleteClientReply, DeleteClientRequest,
+    DeviceAuthorizationEndpointReply, DeviceAuthorizationEndpointRequest, GetAccessTokenReply,
+    GetAccessTokenRequest, GetAuthorizationInfoReply, GetAuthorizationInfoRequest, GetClientsReply,
+    GetClientsRequest, GetDeviceAuthorizationInfoReply, GetDeviceAuthorizationInfoRequest,
+    GetEndSessionInfoReply, GetEndSessionInfoRequest, GetJwkSetReply, GetJwkSetRequest,
+    GetOpenidConfigurationReply, GetOpenidConfigurationRequest, IntrospectionEndpointReply,
+    IntrospectionEndpointRequest, ListGrantsReply, ListGrantsRequest,
+    PushedAuthorizationRequestEndpointReply, PushedAuthorizationRequestEndpointRequest,
+    RevocationEndpointReply, RevocationEndpointRequest, RevokeGrantReply, RevokeGrantRequest,
+    RotateClientSecretReply, RotateClientSecretRequest, TokenEndpointReply, TokenEndpointRequest,
+    UpdateClientReply, UpdateClientRequest, UserinfoEndpointReply, UserinfoEndpointRequest,
 };
-use openidconnect::core::CoreRsaPrivateSigningKey;
-use openidconnect::{IssuerUrl, JsonWebKeyId};
+use openidconnect::IssuerUrl;
+use public_dns::PublicDnsResolver;
+use reqwest::redirect::Policy;
+use signing_keys::{SigningKey, SigningKeyEncryptor, require_signing_key_encryptor};
 use std::sync::Arc;
+use std::time::Duration;
 use tonic::transport::Channel;
 use tonic::{Request, Response, Status};
 
+const BACKCHANNEL_LOGOUT_TIMEOUT: Duration = Duration::from_secs(10);
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     setup_logging();
@@ -30,20 +53,28 @@ async fn main() -> anyhow::Result<()> {
         id_encryptor: require_id_encryptor()?,
         frontend_root: require_env("FRONTEND_ROOT")?,
         issuer: IssuerUrl::new(require_env("OPENID_ISSUER")?)?,
-        rs_256_signing_key: Arc::new(
-            CoreRsaPrivateSigningKey::from_pem(
-                &String::from_utf8_lossy(&require_env_file("OPENID_SIGNING_KEY_PATH")?),
-                Some(JsonWebKeyId::new(require_env("OPENID_SIGNING_KEY_ID")?)),
-            )
-            .map_err(|err| anyhow::anyhow!("failed to parse openid signing key: {err}"))?,
-        ),
+        signing_keys: Arc::default(),
+        signing_key_encryptor: require_signing_key_encryptor()?,
+        // only used for URLs registered by clients, which must not reach internal hosts
+        http_client: reqwest::Client::builder()
+            .redirect(Policy::none())
+            .timeout(BACKCHANNEL_LOGOUT_TIMEOUT)
+            .https_only(true)
+            .no_proxy()
+            .dns_resolver(Arc::new(PublicDnsResolver))
+            .build()?,
     };
 
+    service.import_signing_key_from_env().await?;
+    service.rotate_signing_keys().await?;
+    service.clone().start_signing_key_rotator();
+
     start_service(AuthOAuthProviderServer::new(service)).await?;
 
     Ok(())
 }
 
+#[derive(Clone)]
 pub struct AuthOAuthProviderService {
     db: Db,
     router: Channel,
@@ -51,7 +82,9 @@ pub struct AuthOAuthProviderService {
     id_encryptor: IdEncryptor,
     frontend_root: String,
     issuer: IssuerUrl,
-    rs_256_signing_key: Arc<CoreRsaPrivateSigningKey>,
+    signing_keys: Arc<ArcSwap<Vec<Arc<SigningKey>>>>,
+    signing_key_encryptor: SigningKeyEncryptor,
+    http_client: reqwest::Client,
 }
 
 #[tonic::async_trait]
@@ -91,6 +124,48 @@ impl AuthOAuthProvider for AuthOAuthProviderService {
         self.token_endpoint(request).await
     }
 
+    async fn revocation_endpoint(
+        &self,
+        request: Request<RevocationEndpointRequest>,
+    ) -> Result<Response<RevocationEndpointReply>, Status> {
+        self.revocation_endpoint(request).await
+    }
+
+    async fn introspection_endpoint(
+        &self,
+        request: Request<IntrospectionEndpointRequest>,
+    ) -> Result<Response<IntrospectionEndpointReply>, Status> {
+        self.introspection_endpoint(request).await
+    }
+
+    async fn device_authorization_endpoint(
+        &self,
+        request: Request<DeviceAuthorizationEndpointRequest>,
+    ) -> Result<Response<DeviceAuthorizationEndpointReply>, Status> {
+        self.device_authorization_endpoint(request).await
+    }
+
+    async fn pushed_authorization_request_endpoint(
+        &self,
+        request: Request<PushedAuthorizationRequestEndpointRequest>,
+    ) -> Result<Response<PushedAuthorizationRequestEndpointReply>, Status> {
+        self.pushed_authorization_request_endpoint(request).await
+    }
+
+    async fn get_device_authorization_info(
+        &self,
+        request: Request<GetDeviceAuthorizationInfoRequest>,
+    ) -> Result<Response<GetDeviceAuthorizationInfoReply>, Status> {
+        self.get_device_authorization_info(request).await
+    }
+
+    async fn accept_device_authorization(
+        &self,
+        request: Request<AcceptDeviceAuthorizationRequest>,
+    ) -> Result<Response<AcceptDeviceAuthorizationReply>, Status> {
+        self.accept_device_authorization(request).await
+    }
+
     async fn get_access_token(
         &self,
         request: Request<GetAccessTokenRequest>,
@@ -104,4 +179,67 @@ impl AuthOAuthProvider for AuthOAuthProviderService {
     ) -> Result<Response<UserinfoEndpointReply>, Status> {
         self.userinfo_endpoint(request).await
     }
+
+    async fn create_client(
+        &self,
+        request: Request<CreateClientRequest>,
+    ) -> Result<Response<CreateClientReply>, Status> {
+        self.create_client(request).await
+    }
+
+    async fn get_clients(
+        &self,
+        request: Request<GetClientsRequest>,
+    ) -> Result<Response<GetClientsReply>, Status> {
+        self.get_clients(request).await
+    }
+
+    async fn update_client(
+        &self,
+        request: Request<UpdateClientRequest>,
+    ) -> Result<Response<UpdateClientReply>, Status> {
+        self.update_client(request).await
+    }
+
+    async fn rotate_client_secret(
+        &self,
+        request: Request<RotateClientSecretRequest>,
+    ) -> Result<Response<RotateClientSecretReply>, Status> {
+        self.rotate_client_secret(request).await
+    }
+
+    async fn delete_client(
+        &self,
+        request: Request<DeleteClientRequest>,
+    ) -> Result<Response<DeleteClientReply>, Status> {
+        self.delete_client(request).await
+    }
+
+    async fn list_grants(
+        &self,
+        request: Request<ListGrantsRequest>,
+    ) -> Result<Response<ListGrantsReply>, Status> {
+        self.list_grants(request).await
+    }
+
+    async fn revoke_grant(
+        &self,
+        request: Request<RevokeGrantRequest>,
+    ) -> Result<Response<RevokeGrantReply>, Status> {
+        self.revoke_grant(request).await
+    }
+
+    async fn get_end_session_info(
+        &self,
+        request: Request<GetEndSessionInfoRequest>,
+    ) -> Result<Response<GetEndSessionInfoReply>, Status> {
+        self.get_end_session_info(request).await
+    }
+
+    async fn backchannel_logout(
+        &self,
+        request: Request<BackchannelLogoutRequest>,
+    ) -> Result<Response<BackchannelLogoutReply>, Status> {
+        self.backchannel_logout(request).await
+    }
 }
@@ -32,10 +32,15 @@ impl AuthOAuthProviderService {
         .map_err(Status::db)?
         .ok_or_else(|| Status::coded(Code::NotFound, ErrorCode::FlowNotFound))?;
 
-        if flow.created_at + Duration::from_secs(30 * 60) < Utc::now() {
+        if flow.created_at + Duration::from_mins(30) < Utc::now() {
             return Err(Status::coded(Code::NotFound, ErrorCode::FlowNotFound));
         }
 
+        // device flows are accepted with `accept_device_authorization`
+        let Some(redirect_uri) = flow.redirect_uri else {
+            return Err(Status::coded(Code::NotFound, ErrorCode::FlowNotFound));
+        };
+
         let grant = sqlx::query!(
             "insert into auth_oauth_provider.grants
              (client_id, user_id, scopes)
@@ -44,7 +49,7 @@ impl AuthOAuthProviderService {
              set scopes = auth_oauth_provider.merge_arrays(grants.scopes, excluded.scopes)
              returning id",
             flow.client_id,
-            flow.user_id,
+            request.user_id,
             flow.scopes as Vec<String>,
         )
         .fetch_one(&self.db)
@@ -65,7 +70,7 @@ impl AuthOAuthProviderService {
         .await
         .map_err(Status::db)?;
 
-        let redirect_uri = RedirectUrl::new(flow.redirect_uri.clone())
+        let redirect_uri = RedirectUrl::new(redirect_uri)
             .map_err(|_| Status::coded(Code::InvalidArgument, ErrorCode::InvalidRedirectUri))?;
 
         Ok(Response::new(AcceptAuthorizationReply {
@@ -0,0 +1,102 @@
+use crate::AuthOAuthProviderService;
+use crate::methods::device_authorization_endpoint::normalize_user_code;
+use bfx_core::status::{ErrorCode, StatusExt};
+use bfx_proto::auth::{AcceptDeviceAuthorizationReply, AcceptDeviceAuthorizationRequest};
+use tonic::{Code, Request, Response, Status};
+use tracing::info;
+
+impl AuthOAuthProviderService {
+    /// Approve or deny a device authorization request
+    ///
+    /// After approval, the device gets its tokens on the next poll of the token endpoint.
+    ///
+    /// # Errors
+    ///
+    /// - If the user code is not found, expired or has already been used
+    /// - Miscellaneous internal errors
+    pub async fn accept_device_authorization(
+        &self,
+        request: Request<AcceptDeviceAuthorizationRequest>,
+    ) -> Result<Response<AcceptDeviceAuthorizationReply>, Status> {
+        let request = request.into_inner();
+
+        let user_code = normalize_user_code(&request.user_code);
+
+        let mut tx = self.db.begin().await.map_err(Status::db)?;
+
+        let authorization = sqlx::query!(
+            "select id, client_id, scopes from auth_oauth_provider.device_authorizations
+             where user_code = $1 and flow_id is null and not denied and expires_at > now()
+             for update",
+            user_code,
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(Status::db)?
+        .ok_or_else(|| Status::coded(Code::NotFound, ErrorCode::UserCodeNotFound))?;
+
+        if !request.accept {
+            sqlx::query!(
+                "update auth_oauth_provider.device_authorizations set denied = true where id = $1",
+                authorization.id,
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(Status::db)?;
+
+            tx.commit().await.map_err(Status::db)?;
+
+            return Ok(Response::new(AcceptDeviceAuthorizationReply {}));
+        }
+
+        let grant = sqlx::query!(
+            "insert into auth_oauth_provider.grants
+             (client_id, user_id, scopes)
+             values ($1, $2, $3)
+             on conflict (user_id, client_id) do update
+             set scopes = auth_oauth_provider.merge_arrays(grants.scopes, excluded.scopes)
+             returning id",
+            authorization.client_id,
+            request.user_id,
+            &authorization.scopes,
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(Status::db)?;
+
+        // device flows have no redirect URI, the device polls the token endpoint instead
+        let flow = sqlx::query!(
+            "insert into auth_oauth_provider.flows
+             (client_id, grant_id, user_id, scopes)
+             values ($1, $2, $3, $4)
+             returning id",
+            authorization.client_id,
+            grant.id,
+            request.user_id,
+            &authorization.scopes,
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(Status::db)?;
+
+        sqlx::query!(
+            "update auth_oauth_provider.device_authorizations set flow_id = $1 where id = $2",
+            flow.id,
+            authorization.id,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(Status::db)?;
+
+        tx.commit().await.map_err(Status::db)?;
+
+        info!(
+            grant.id,
+            flow.id,
+            user_id = request.user_id,
+            "approved device authorization"
+        );
+
+        Ok(Response::new(AcceptDeviceAuthorizationReply {}))
+    }
+}
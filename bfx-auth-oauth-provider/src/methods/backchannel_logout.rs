@@ -0,0 +1,133 @@
+use crate::AuthOAuthProviderService;
+use crate::models::client_info::ClientInfo;
+use crate::signing_keys::KeyAlgorithm;
+use bfx_core::status::StatusExt;
+use bfx_proto::auth::{BackchannelLogoutReply, BackchannelLogoutRequest};
+use nanoid::nanoid;
+use serde::Serialize;
+use serde_json::{Value, json};
+use sqlx::types::chrono::Utc;
+use std::str::FromStr;
+use std::time::Duration;
+use tonic::{Request, Response, Status};
+use tracing::{info, warn};
+
+const LOGOUT_TOKEN_LIFETIME: Duration = Duration::from_mins(2);
+const LOGOUT_TOKEN_TYPE: &str = "logout+jwt";
+const BACKCHANNEL_LOGOUT_EVENT: &str = "http://schemas.openid.net/event/backchannel-logout";
+
+/// Claims of a logout token (OIDC Back-Channel Logout 1.0)
+#[derive(Serialize)]
+struct LogoutTokenClaims<'a> {
+    iss: &'a str,
+    aud: &'a str,
+    iat: i64,
+    exp: i64,
+    jti: String,
+    sub: String,
+    events: Value,
+}
+
+impl AuthOAuthProviderService {
+    /// Notify all clients authorized by the user that the user has logged out
+    ///
+    /// Only clients with a `backchannel_logout_uri` are notified.
+    /// Logout tokens are delivered in the background, failed deliveries are not retried.
+    ///
+    /// # Errors
+    ///
+    /// - Miscellaneous internal errors
+    pub async fn backchannel_logout(
+        &self,
+        request: Request<BackchannelLogoutRequest>,
+    ) -> Result<Response<BackchannelLogoutReply>, Status> {
+        let request = request.into_inner();
+
+        let clients = sqlx::query_as!(
+            ClientInfo,
+            "select c.*
+             from auth_oauth_provider.grants g
+             inner join auth_oauth_provider.clients c on g.client_id = c.id
+             where g.user_id = $1 and c.backchannel_logout_uri is not null",
+            request.user_id,
+        )
+        .fetch_all(&self.db)
+        .await
+        .map_err(Status::db)?;
+
+        self.send_logout_tokens(request.user_id, clients);
+
+        Ok(Response::new(BackchannelLogoutReply {}))
+    }
+
+    /// POST logout tokens for the user to the clients in the background
+    ///
+    /// Clients without a valid `backchannel_logout_uri` are skipped.
+    pub fn send_logout_tokens(&self, user_id: i64, clients: Vec<ClientInfo>) {
+        for client in clients {
+            let Some(backchannel_logout_uri) = client.backchannel_logout_uri.clone() else {
+                continue;
+            };
+            // it might have been registered before the URI rules got stricter
+            if Self::check_backchannel_logout_uri(&backchannel_logout_uri).is_err() {
+                warn!(
+                    client_id = client.client_id,
+                    "skipped invalid back-channel logout URI"
+                );
+                continue;
+            }
+
+            let logout_token = match self.make_logout_token(&client, user_id) {
+                Ok(logout_token) => logout_token,
+                Err(err) => {
+                    warn!(
+                        ?err,
+                        client_id = client.client_id,
+                        "failed to make logout token"
+                    );
+                    continue;
+                }
+            };
+
+            let client_id = client.client_id;
+            let http_client = self.http_client.clone();
+            tokio::spawn(async move {
+                let result = http_client
+                    .post(backchannel_logout_uri)
+                    .form(&[("logout_token", logout_token)])
+                    .send()
+                    .await
+                    .and_then(reqwest::Response::error_for_status);
+
+                match result {
+                    Ok(_) => info!(client_id, "sent back-channel logout"),
+                    Err(err) => warn!(
+                        err = %err,
+                        client_id,
+                        "failed to send back-channel logout"
+                    ),
+                }
+            });
+        }
+    }
+
+    fn make_logout_token(&self, client: &ClientInfo, user_id: i64) -> Result<String, Status> {
+        // logout tokens are validated like ID tokens, so use the same algorithm
+        let alg = KeyAlgorithm::from_str(&client.id_token_signed_response_alg)
+            .unwrap_or(KeyAlgorithm::Rs256);
+
+        let now = Utc::now();
+        let claims = LogoutTokenClaims {
+            iss: self.issuer.as_str(),
+            aud: &client.client_id,
+            iat: now.timestamp(),
+            exp: (now + LOGOUT_TOKEN_LIFETIME).timestamp(),
+            jti: nanoid!(32),
+            sub: self.get_subject(client, user_id),
+            events: json!({ BACKCHANNEL_LOGOUT_EVENT: {} }),
+        };
+
+        self.get_active_signing_key(alg)?
+            .sign_jwt(LOGOUT_TOKEN_TYPE, &claims)
+    }
+}
@@ -0,0 +1,34 @@
+use crate::AuthOAuthProviderService;
+use bfx_core::status::{ErrorCode, StatusExt};
+use bfx_proto::auth::{DeleteClientReply, DeleteClientRequest};
+use tonic::{Code, Request, Response, Status};
+
+impl AuthOAuthProviderService {
+    /// Delete an OAuth client with all of its grants and flows
+    ///
+    /// # Errors
+    ///
+    /// - If the client does not exist or is not owned by `owner_id`
+    /// - Miscellaneous internal errors
+    pub async fn delete_client(
+        &self,
+        request: Request<DeleteClientRequest>,
+    ) -> Result<Response<DeleteClientReply>, Status> {
+        let request = request.into_inner();
+
+        let result = sqlx::query!(
+            "delete from auth_oauth_provider.clients
+             where id = $1 and ($2::bigint is null or owner_id = $2)",
+            request.id,
+            request.owner_id,
+        )
+        .execute(&self.db)
+        .await
+        .map_err(Status::db)?;
+        if result.rows_affected() == 0 {
+            return Err(Status::coded(Code::NotFound, ErrorCode::ClientNotFound));
+        }
+
+        Ok(Response::new(DeleteClientReply {}))
+    }
+}
@@ -0,0 +1,128 @@
+use crate::AuthOAuthProviderService;
+use crate::methods::get_authorization_info::SUPPORTED_SCOPES;
+use bfx_core::status::{ErrorCode, StatusExt};
+use bfx_proto::auth::{
+    DeviceAuthorizationEndpointReply, DeviceAuthorizationEndpointRequest, TokenEndpointReply,
+};
+use nanoid::nanoid;
+use openidconnect::core::CoreErrorResponseType;
+use serde_json::json;
+use sqlx::types::chrono::Utc;
+use std::time::Duration;
+use tonic::{Code, Request, Response, Status};
+
+const DEVICE_AUTHORIZATION_LIFETIME: Duration = Duration::from_mins(10);
+const DEVICE_POLL_INTERVAL_SECS: u16 = 5;
+
+// no vowels (to avoid forming words) and no easily confused characters
+const USER_CODE_ALPHABET: [char; 20] = [
+    'B', 'C', 'D', 'F', 'G', 'H', 'J', 'K', 'L', 'M', 'N', 'P', 'Q', 'R', 'S', 'T', 'V', 'W', 'X',
+    'Z',
+];
+const USER_CODE_LENGTH: usize = 8;
+
+/// Normalize a user code entered by the user to the stored form
+///
+/// Dashes, spaces and case are ignored.
+#[must_use]
+pub fn normalize_user_code(user_code: &str) -> String {
+    user_code
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+impl AuthOAuthProviderService {
+    /// `/openid/device_authorization` endpoint (RFC 8628)
+    ///
+    /// # Errors
+    ///
+    /// Only internal errors are returned as `Err`.
+    /// All other request errors are returned as `Ok(Response)` with the appropriate error code.
+    pub async fn device_authorization_endpoint(
+        &self,
+        request: Request<DeviceAuthorizationEndpointRequest>,
+    ) -> Result<Response<DeviceAuthorizationEndpointReply>, Status> {
+        let request = request.into_inner();
+
+        let mut params = request.query;
+
+        let error_resp = |error, description: &str| {
+            let TokenEndpointReply { status, json } =
+                Self::make_error_resp(error, description).into_inner();
+            Response::new(DeviceAuthorizationEndpointReply { status, json })
+        };
+
+        let client = match self
+            .authenticate_client(&mut params, request.authorization)
+            .await?
+        {
+            Ok(client) => client,
+            Err(description) => {
+                return Ok(error_resp(
+                    CoreErrorResponseType::InvalidClient,
+                    &description,
+                ));
+            }
+        };
+
+        let scope = params.remove("scope").unwrap_or_else(|| "openid".into());
+        let scopes = scope.split(' ').collect::<Vec<_>>();
+        if scopes.iter().any(|scope| {
+            !SUPPORTED_SCOPES.contains(scope)
+                || !client.allowed_scopes.iter().any(|allowed| allowed == scope)
+        }) {
+            return Ok(error_resp(
+                CoreErrorResponseType::InvalidScope,
+                "unsupported or disallowed scope",
+            ));
+        }
+
+        sqlx::query!(
+            "delete from auth_oauth_provider.device_authorizations where expires_at < now()"
+        )
+        .execute(&self.db)
+        .await
+        .map_err(Status::db)?;
+
+        let device_code = format!("BF/D/{}", nanoid!(32));
+        let user_code = nanoid!(USER_CODE_LENGTH, &USER_CODE_ALPHABET);
+        let expires_at = Utc::now() + DEVICE_AUTHORIZATION_LIFETIME;
+
+        sqlx::query!(
+            "insert into auth_oauth_provider.device_authorizations
+             (client_id, scopes, device_code, user_code, poll_interval, expires_at)
+             values ($1, $2, $3, $4, $5, $6)",
+            client.id,
+            &scopes as &[&str],
+            device_code,
+            user_code,
+            i32::from(DEVICE_POLL_INTERVAL_SECS),
+            expires_at,
+        )
+        .execute(&self.db)
+        .await
+        .map_err(Status::db)?;
+
+        // show the user code as `XXXX-XXXX`, it's easier to read
+        let (first, second) = user_code.split_at(USER_CODE_LENGTH / 2);
+        let user_code = format!("{first}-{second}");
+
+        let verification_uri = format!("{}/openid/device", self.frontend_root);
+        let verification_uri_complete = format!("{verification_uri}?user_code={user_code}");
+
+        Ok(Response::new(DeviceAuthorizationEndpointReply {
+            status: 200,
+            json: serde_json::to_string(&json!({
+                "device_code": device_code,
+                "user_code": user_code,
+                "verification_uri": verification_uri,
+                "verification_uri_complete": verification_uri_complete,
+                "expires_in": DEVICE_AUTHORIZATION_LIFETIME.as_secs(),
+                "interval": DEVICE_POLL_INTERVAL_SECS,
+            }))
+            .map_err(|err| Status::coded(Code::Internal, ErrorCode::Internal).with_source(err))?,
+        }))
+    }
+}
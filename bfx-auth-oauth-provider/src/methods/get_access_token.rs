@@ -1,30 +1,30 @@
 use crate::AuthOAuthProviderService;
 use bfx_core::status::{ErrorCode, StatusExt};
-use bfx_proto::auth::{GetAccessTokenReply, GetAccessTokenRequest};
+use bfx_proto::auth::{DpopProof, GetAccessTokenReply, GetAccessTokenRequest};
 use tonic::{Code, Request, Response, Status};
 
 impl AuthOAuthProviderService {
     /// Gets information about an OAuth access token
     ///
+    /// Tokens from the `client_credentials` grant have no user and no grant.
+    /// Tokens bound to a `DPoP` key can only be used with a proof for the same key.
+    ///
     /// # Errors
     ///
     /// - If the access token is not found
     /// - If the access token is expired
+    /// - If the access token is bound to a `DPoP` key, and the proof is missing or invalid
     /// - Miscellaneous internal errors
     pub async fn get_access_token(
         &self,
         access_token: String,
+        dpop: Option<DpopProof>,
     ) -> Result<GetAccessTokenReply, Status> {
         let flow = sqlx::query!(
-            "select
-                 f.scopes,
-                 f.grant_id as \"grant_id!\",
-                 f.client_id,
-                 g.user_id
-             from auth_oauth_provider.flows f
-             inner join auth_oauth_provider.grants g on f.grant_id = g.id
-             where f.access_token = $1 and f.access_token_expires_at > now()
-                   and grant_id is not null",
+            "select scopes, grant_id, client_id, user_id, dpop_jkt
+             from auth_oauth_provider.flows
+             where access_token = $1 and access_token_expires_at > now()
+                   and (grant_id is not null or user_id is null)",
             access_token,
         )
         .fetch_optional(&self.db)
@@ -32,11 +32,44 @@ impl AuthOAuthProviderService {
         .map_err(Status::db)?
         .ok_or_else(|| Status::coded(Code::Unauthenticated, ErrorCode::InvalidToken))?;
 
+        if let Some(dpop_jkt) = flow.dpop_jkt {
+            let invalid_proof = |details: &str| {
+                Status::coded(Code::Unauthenticated, ErrorCode::InvalidDpopProof)
+                    .with_details(details)
+            };
+
+            let Some(dpop) = dpop else {
+                return Err(invalid_proof("access token is bound to a DPoP key"));
+            };
+            match self
+                .verify_dpop_proof(
+                    &dpop.proof,
+                    &dpop.http_method,
+                    &dpop.http_path,
+                    Some(&access_token),
+                )
+                .await?
+            {
+                Ok(jkt) if jkt == dpop_jkt => {}
+                Ok(_) => return Err(invalid_proof("DPoP proof is for another key")),
+                Err(description) => return Err(invalid_proof(&description)),
+            }
+        }
+
+        let subject = match flow.user_id {
+            Some(user_id) => {
+                let client = self.get_client_by_id(flow.client_id).await?;
+                Some(self.get_subject(&client, user_id))
+            }
+            None => None,
+        };
+
         Ok(GetAccessTokenReply {
             user_id: flow.user_id,
             grant_id: flow.grant_id,
             client_id: flow.client_id,
             scope: flow.scopes,
+            subject,
         })
     }
 
@@ -52,7 +85,8 @@ impl AuthOAuthProviderService {
         let request = request.into_inner();
 
         Ok(Response::new(
-            self.get_access_token(request.access_token).await?,
+            self.get_access_token(request.access_token, request.dpop)
+                .await?,
         ))
     }
 }
@@ -6,6 +6,8 @@ use nanoid::nanoid;
 use openidconnect::RedirectUrl;
 use openidconnect::core::{CoreAuthErrorResponseType, CoreResponseType};
 use serde_json::Value;
+use sqlx::types::Json;
+use std::collections::HashMap;
 use tonic::{Code, Request, Response, Status};
 use tracing::info;
 
@@ -47,23 +49,46 @@ macro_rules! is_subset {
     };
 }
 
-pub(crate) use {get_param, invalid_param, is_subset, missing_param};
+pub(crate) use is_subset;
 
 // also applies to nonce
 const MAX_STATE_LEN: usize = 256;
 
-const SUPPORTED_SCOPES: &[&str] = &["openid", "email", "profile", "offline_access"];
+pub const SUPPORTED_SCOPES: &[&str] = &[
+    "openid",
+    "email",
+    "profile",
+    "offline_access",
+    // API scopes, enforced by bfx-graphql
+    "profile:write",
+    "notifications:read",
+];
+
+/// An authorization request that passed validation
+pub struct AuthorizationRequest {
+    pub client: ClientInfo,
+    pub scopes: Vec<String>,
+    pub redirect_uri: RedirectUrl,
+    pub state: Option<String>,
+    pub nonce: Option<String>,
+    pub code_challenge: Option<String>,
+    pub code_challenge_method: Option<String>,
+    pub prompt: Option<String>,
+}
 
 impl AuthOAuthProviderService {
     /// Get information for displaying a page on `/openid/authorize`
     ///
+    /// The parameters are either given inline, or pushed beforehand and referenced by `request_uri`
+    /// (RFC 9126). Pushed requests can be used until a flow is created from them.
+    ///
     /// # Errors
     ///
-    /// - If some param is missing or invalid
-    /// - If `response_type` is not `code`
-    /// - If one of the `scope`s is not supported
-    /// - If `client_id` does not exist
-    /// - If `code_challenge_method` is not `S256` (if required or specified)
+    /// - If the authorization request is invalid
+    ///   (see [`AuthOAuthProviderService::validate_authorization_request`])
+    /// - If `request_uri` is unknown, expired or was pushed by another client
+    /// - If the client requires pushed authorization requests, but the parameters are inline
+    #[allow(clippy::too_many_lines)]
     pub async fn get_authorization_info(
         &self,
         request: Request<GetAuthorizationInfoRequest>,
@@ -72,102 +97,42 @@ impl AuthOAuthProviderService {
 
         let mut query = request.query;
 
-        // gather parameters
-        let scope = get_param!(query, "scope");
-        let response_type = get_param!(query, "response_type");
-        let client_id = get_param!(query, "client_id");
-        let redirect_uri = query.remove("redirect_uri");
-        let state = query.remove("state");
-        let nonce = query.remove("nonce");
-        let code_challenge = query.remove("code_challenge");
-        let code_challenge_method = query.remove("code_challenge_method");
-        let prompt = query.remove("prompt");
-
-        // parse and validate some parameters
-        let scopes = scope.split(' ').collect::<Vec<_>>();
-        let response_type: CoreResponseType = serde_json::from_value(Value::String(response_type))
-            .map_err(invalid_param!("response_type"))?;
-
-        //// check param length
-        if state.as_ref().map_or(0, String::len) > MAX_STATE_LEN {
-            return Err(invalid_param!(val "state"));
-        }
-        if nonce.as_ref().map_or(0, String::len) > MAX_STATE_LEN {
-            return Err(invalid_param!(val "nonce"));
-        }
-
-        //// check response type
-        if response_type != CoreResponseType::Code {
-            return Err(
-                Status::coded(Code::InvalidArgument, ErrorCode::InvalidParameter)
-                    .with_details("response_type must be `code`"),
-            );
-        }
-
-        //// check scopes
-        // the spec says that provider SHOULD ignore unsupported scopes,
-        // but for now we'll return an error
-        if scopes.iter().any(|scope| !SUPPORTED_SCOPES.contains(scope)) {
-            return Err(
-                Status::coded(Code::InvalidArgument, ErrorCode::InvalidParameter)
-                    .with_details("unsupported scope"),
-            );
-        }
-
-        // get client
-        let client = sqlx::query_as!(
-            ClientInfo,
-            "select * from auth_oauth_provider.clients where client_id = $1",
-            client_id,
-        )
-        .fetch_optional(&self.db)
-        .await
-        .map_err(Status::db)?
-        .ok_or_else(|| Status::coded(Code::InvalidArgument, ErrorCode::ClientNotFound))?;
-
-        // check redirect_uri
-        let redirect_uri = if let Some(redirect_uri) = redirect_uri {
-            if !client.redirect_uris.contains(&redirect_uri) {
-                return Err(Status::coded(
-                    Code::InvalidArgument,
-                    ErrorCode::InvalidRedirectUri,
-                ));
-            }
-            RedirectUrl::new(redirect_uri).map_err(invalid_param!("redirect_uri"))?
-        } else if client.redirect_uris.len() == 1 {
-            RedirectUrl::new(client.redirect_uris[0].clone())
-                .map_err(invalid_param!("redirect_uri"))?
+        // the pushed parameters replace inline ones, only `client_id` is still required
+        let pushed_request_id = if let Some(request_uri) = query.remove("request_uri") {
+            let client_id = get_param!(query, "client_id");
+
+            let pushed = sqlx::query!(
+                r#"select p.id, p.params as "params: Json<HashMap<String, String>>"
+                   from auth_oauth_provider.pushed_authorization_requests p
+                   inner join auth_oauth_provider.clients c on p.client_id = c.id
+                   where p.request_uri = $1 and c.client_id = $2 and p.expires_at > now()"#,
+                request_uri,
+                client_id,
+            )
+            .fetch_optional(&self.db)
+            .await
+            .map_err(Status::db)?
+            .ok_or_else(|| invalid_param!(val "request_uri"))?;
+
+            query = pushed.params.0;
+            Some(pushed.id)
         } else {
-            return Err(missing_param!(val "redirect_uri"));
+            None
         };
 
-        // check scopes
-        let disallowed_scope = scopes.iter().find(|scope| {
-            !client
-                .allowed_scopes
-                .iter()
-                .any(|allowed_scope| allowed_scope == **scope)
-        });
-        if let Some(disallowed_scope) = disallowed_scope {
-            return Err(
-                Status::coded(Code::InvalidArgument, ErrorCode::InvalidScope)
-                    .with_details(&format!("disallowed scope `{disallowed_scope}`")),
-            );
-        }
-
-        // check code_challenge
-        if client.enforce_code_challenge && code_challenge.is_none() {
-            return Err(missing_param!(val "code_challenge"));
-        }
-
-        if let Some(code_challenge) = &code_challenge {
-            if code_challenge_method.as_deref() != Some("S256") {
-                return Err(invalid_param!(val "code_challenge_method"));
-            }
-
-            if code_challenge.len() != 43 {
-                return Err(invalid_param!(val "code_challenge"));
-            }
+        let AuthorizationRequest {
+            client,
+            scopes,
+            redirect_uri,
+            state,
+            nonce,
+            code_challenge,
+            code_challenge_method,
+            prompt,
+        } = self.validate_authorization_request(&mut query).await?;
+
+        if client.require_pushed_authorization_requests && pushed_request_id.is_none() {
+            return Err(missing_param!(val "request_uri"));
         }
 
         //// all checks passed
@@ -186,12 +151,23 @@ impl AuthOAuthProviderService {
             // if not logged in, just should the rp info
             return Ok(Response::new(GetAuthorizationInfoReply {
                 rp_info: Some(client.into()),
-                scopes: scopes.into_iter().map(String::from).collect(),
+                scopes,
                 flow_id: None,
                 redirect_to: default_redirect_to,
             }));
         };
 
+        // a flow is created below, so the pushed request can't be used again
+        if let Some(pushed_request_id) = pushed_request_id {
+            sqlx::query!(
+                "delete from auth_oauth_provider.pushed_authorization_requests where id = $1",
+                pushed_request_id,
+            )
+            .execute(&self.db)
+            .await
+            .map_err(Status::db)?;
+        }
+
         let existing_grant = sqlx::query!(
             "select * from auth_oauth_provider.grants where client_id = $1 and user_id = $2",
             client.id,
@@ -217,7 +193,7 @@ impl AuthOAuthProviderService {
                     grant.id,
                     user_id,
                     redirect_uri.as_str(),
-                    &scopes as &[&str],
+                    &scopes,
                     state,
                     nonce,
                     code_challenge,
@@ -230,7 +206,7 @@ impl AuthOAuthProviderService {
 
                 Ok(Response::new(GetAuthorizationInfoReply {
                     rp_info: Some(client.into()),
-                    scopes: scopes.into_iter().map(String::from).collect(),
+                    scopes,
                     flow_id: Some(flow.id),
                     redirect_to: Some(self.make_code_redirect(
                         &redirect_uri,
@@ -250,7 +226,7 @@ impl AuthOAuthProviderService {
                     grant.map(|g| g.id),
                     user_id,
                     redirect_uri.as_str(),
-                    &scopes as &[&str],
+                    &scopes,
                     state,
                     nonce,
                     code_challenge,
@@ -262,7 +238,7 @@ impl AuthOAuthProviderService {
 
                 Ok(Response::new(GetAuthorizationInfoReply {
                     rp_info: Some(client.into()),
-                    scopes: scopes.into_iter().map(String::from).collect(),
+                    scopes,
                     flow_id: Some(flow.id),
                     redirect_to: default_redirect_to,
                 }))
@@ -270,6 +246,134 @@ impl AuthOAuthProviderService {
         }
     }
 
+    /// Validate the parameters of an authorization request
+    ///
+    /// Used by `/openid/authorize` and `/openid/par`. The used parameters are removed from `query`.
+    ///
+    /// # Errors
+    ///
+    /// - If some param is missing or invalid
+    /// - If `response_type` is not `code`
+    /// - If one of the `scope`s is not supported
+    /// - If `client_id` does not exist
+    /// - If `code_challenge_method` is not `S256` (if required or specified)
+    pub async fn validate_authorization_request(
+        &self,
+        query: &mut HashMap<String, String>,
+    ) -> Result<AuthorizationRequest, Status> {
+        // gather parameters
+        let scope = get_param!(query, "scope");
+        let response_type = get_param!(query, "response_type");
+        let client_id = get_param!(query, "client_id");
+        let redirect_uri = query.remove("redirect_uri");
+        let state = query.remove("state");
+        let nonce = query.remove("nonce");
+        let code_challenge = query.remove("code_challenge");
+        let code_challenge_method = query.remove("code_challenge_method");
+        let prompt = query.remove("prompt");
+
+        // parse and validate some parameters
+        let scopes = scope.split(' ').map(String::from).collect::<Vec<_>>();
+        let response_type: CoreResponseType = serde_json::from_value(Value::String(response_type))
+            .map_err(invalid_param!("response_type"))?;
+
+        //// check param length
+        if state.as_ref().map_or(0, String::len) > MAX_STATE_LEN {
+            return Err(invalid_param!(val "state"));
+        }
+        if nonce.as_ref().map_or(0, String::len) > MAX_STATE_LEN {
+            return Err(invalid_param!(val "nonce"));
+        }
+
+        //// check response type
+        if response_type != CoreResponseType::Code {
+            return Err(
+                Status::coded(Code::InvalidArgument, ErrorCode::InvalidParameter)
+                    .with_details("response_type must be `code`"),
+            );
+        }
+
+        //// check scopes
+        // the spec says that provider SHOULD ignore unsupported scopes,
+        // but for now we'll return an error
+        if scopes
+            .iter()
+            .any(|scope| !SUPPORTED_SCOPES.contains(&scope.as_str()))
+        {
+            return Err(
+                Status::coded(Code::InvalidArgument, ErrorCode::InvalidParameter)
+                    .with_details("unsupported scope"),
+            );
+        }
+
+        // get client
+        let client = sqlx::query_as!(
+            ClientInfo,
+            "select * from auth_oauth_provider.clients where client_id = $1",
+            client_id,
+        )
+        .fetch_optional(&self.db)
+        .await
+        .map_err(Status::db)?
+        .ok_or_else(|| Status::coded(Code::InvalidArgument, ErrorCode::ClientNotFound))?;
+
+        // check redirect_uri
+        let redirect_uri = if let Some(redirect_uri) = redirect_uri {
+            if !client.redirect_uris.contains(&redirect_uri) {
+                return Err(Status::coded(
+                    Code::InvalidArgument,
+                    ErrorCode::InvalidRedirectUri,
+                ));
+            }
+            RedirectUrl::new(redirect_uri).map_err(invalid_param!("redirect_uri"))?
+        } else if client.redirect_uris.len() == 1 {
+            RedirectUrl::new(client.redirect_uris[0].clone())
+                .map_err(invalid_param!("redirect_uri"))?
+        } else {
+            return Err(missing_param!(val "redirect_uri"));
+        };
+
+        // check scopes
+        let disallowed_scope = scopes.iter().find(|scope| {
+            !client
+                .allowed_scopes
+                .iter()
+                .any(|allowed_scope| allowed_scope == *scope)
+        });
+        if let Some(disallowed_scope) = disallowed_scope {
+            return Err(
+                Status::coded(Code::InvalidArgument, ErrorCode::InvalidScope)
+                    .with_details(&format!("disallowed scope `{disallowed_scope}`")),
+            );
+        }
+
+        // check code_challenge, public clients always need it
+        if (client.enforce_code_challenge || client.is_public()) && code_challenge.is_none() {
+            return Err(missing_param!(val "code_challenge"));
+        }
+
+        if let Some(code_challenge) = &code_challenge {
+            if code_challenge_method.as_deref() != Some("S256") {
+                return Err(invalid_param!(val "code_challenge_method"));
+            }
+
+            if code_challenge.len() != 43 {
+                return Err(invalid_param!(val "code_challenge"));
+            }
+        }
+
+        Ok(AuthorizationRequest {
+            client,
+            scopes,
+            redirect_uri,
+            state,
+            nonce,
+            code_challenge,
+            code_challenge_method,
+            prompt,
+        })
+    }
+
     #[must_use]
     pub fn make_code_redirect(
         &self,
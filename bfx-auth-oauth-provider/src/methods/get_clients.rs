@@ -0,0 +1,34 @@
+use crate::AuthOAuthProviderService;
+use crate::models::client_info::ClientInfo;
+use bfx_core::status::StatusExt;
+use bfx_proto::auth::{GetClientsReply, GetClientsRequest};
+use tonic::{Request, Response, Status};
+
+impl AuthOAuthProviderService {
+    /// Get all OAuth clients owned by a user
+    ///
+    /// # Errors
+    ///
+    /// - Miscellaneous internal errors
+    pub async fn get_clients(
+        &self,
+        request: Request<GetClientsRequest>,
+    ) -> Result<Response<GetClientsReply>, Status> {
+        let request = request.into_inner();
+
+        let clients = sqlx::query_as!(
+            ClientInfo,
+            "select * from auth_oauth_provider.clients
+             where owner_id = $1
+             order by created_at",
+            request.owner_id,
+        )
+        .fetch_all(&self.db)
+        .await
+        .map_err(Status::db)?;
+
+        Ok(Response::new(GetClientsReply {
+            clients: clients.into_iter().map(From::from).collect(),
+        }))
+    }
+}
@@ -0,0 +1,47 @@
+use crate::AuthOAuthProviderService;
+use crate::methods::device_authorization_endpoint::normalize_user_code;
+use crate::models::client_info::ClientInfo;
+use bfx_core::status::{ErrorCode, StatusExt};
+use bfx_proto::auth::{GetDeviceAuthorizationInfoReply, GetDeviceAuthorizationInfoRequest};
+use tonic::{Code, Request, Response, Status};
+
+impl AuthOAuthProviderService {
+    /// Get information for displaying the consent page for a device authorization
+    ///
+    /// # Errors
+    ///
+    /// - If the user code is not found, expired or has already been used
+    /// - Miscellaneous internal errors
+    pub async fn get_device_authorization_info(
+        &self,
+        request: Request<GetDeviceAuthorizationInfoRequest>,
+    ) -> Result<Response<GetDeviceAuthorizationInfoReply>, Status> {
+        let request = request.into_inner();
+
+        let user_code = normalize_user_code(&request.user_code);
+
+        let authorization = sqlx::query!(
+            "select client_id, scopes from auth_oauth_provider.device_authorizations
+             where user_code = $1 and flow_id is null and not denied and expires_at > now()",
+            user_code,
+        )
+        .fetch_optional(&self.db)
+        .await
+        .map_err(Status::db)?
+        .ok_or_else(|| Status::coded(Code::NotFound, ErrorCode::UserCodeNotFound))?;
+
+        let client = sqlx::query_as!(
+            ClientInfo,
+            "select * from auth_oauth_provider.clients where id = $1",
+            authorization.client_id,
+        )
+        .fetch_one(&self.db)
+        .await
+        .map_err(Status::db)?;
+
+        Ok(Response::new(GetDeviceAuthorizationInfoReply {
+            rp_info: Some(client.into()),
+            scopes: authorization.scopes,
+        }))
+    }
+}
@@ -0,0 +1,167 @@
+use crate::AuthOAuthProviderService;
+use crate::models::client_info::ClientInfo;
+use bfx_core::status::{ErrorCode, StatusExt};
+use bfx_proto::auth::{GetEndSessionInfoReply, GetEndSessionInfoRequest};
+use jsonwebtoken::jwk::Jwk;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use openidconnect::PrivateSigningKey;
+use openidconnect::url::Url;
+use serde::Deserialize;
+use serde_json::Value;
+use tonic::{Code, Request, Response, Status};
+
+const ID_TOKEN_HINT_ALGORITHMS: &[Algorithm] =
+    &[Algorithm::RS256, Algorithm::ES256, Algorithm::EdDSA];
+
+#[derive(Deserialize)]
+struct IdTokenHintClaims {
+    sub: String,
+    // either a string or an array of strings
+    aud: Value,
+}
+
+impl AuthOAuthProviderService {
+    /// Get information for displaying a page on `/openid/logout` (RP-initiated logout)
+    ///
+    /// The client is identified by `id_token_hint` or `client_id`.
+    /// `post_logout_redirect_uri` must be registered by the client.
+    ///
+    /// # Errors
+    ///
+    /// - If `id_token_hint` is invalid or was issued to another user or client
+    /// - If the client does not exist
+    /// - If `post_logout_redirect_uri` is given without identifying the client, or is not registered
+    pub async fn get_end_session_info(
+        &self,
+        request: Request<GetEndSessionInfoRequest>,
+    ) -> Result<Response<GetEndSessionInfoReply>, Status> {
+        let request = request.into_inner();
+
+        let mut query = request.query;
+
+        let id_token_hint = query.remove("id_token_hint");
+        let client_id = query.remove("client_id");
+        let post_logout_redirect_uri = query.remove("post_logout_redirect_uri");
+        let state = query.remove("state");
+
+        let invalid_hint = |details: &str| {
+            Status::coded(Code::InvalidArgument, ErrorCode::InvalidParameter).with_details(details)
+        };
+
+        let hint = id_token_hint
+            .map(|id_token_hint| {
+                self.verify_id_token_hint(&id_token_hint)
+                    .ok_or_else(|| invalid_hint("invalid id_token_hint"))
+            })
+            .transpose()?;
+
+        let client_id = if let Some((hint_client_id, _)) = &hint {
+            if client_id.is_some_and(|client_id| client_id != *hint_client_id) {
+                return Err(invalid_hint("id_token_hint was issued to another client"));
+            }
+
+            Some(hint_client_id.clone())
+        } else {
+            client_id
+        };
+
+        let client = if let Some(client_id) = client_id {
+            Some(
+                sqlx::query_as!(
+                    ClientInfo,
+                    "select * from auth_oauth_provider.clients where client_id = $1",
+                    client_id,
+                )
+                .fetch_optional(&self.db)
+                .await
+                .map_err(Status::db)?
+                .ok_or_else(|| Status::coded(Code::InvalidArgument, ErrorCode::ClientNotFound))?,
+            )
+        } else {
+            None
+        };
+
+        // `sub` can be pairwise, so it can only be checked once the client is known
+        if let (Some((_, sub)), Some(client)) = (&hint, &client) {
+            let hint_user_id = self
+                .parse_subject(client, sub)
+                .ok_or_else(|| invalid_hint("invalid id_token_hint"))?;
+
+            if request
+                .user_id
+                .is_some_and(|user_id| user_id != hint_user_id)
+            {
+                return Err(invalid_hint("id_token_hint was issued to another user"));
+            }
+        }
+
+        let redirect_to = if let Some(post_logout_redirect_uri) = post_logout_redirect_uri {
+            let Some(client) = &client else {
+                return Err(
+                    Status::coded(Code::InvalidArgument, ErrorCode::MissingParameter)
+                        .with_details("missing parameter `client_id` or `id_token_hint`"),
+                );
+            };
+            if !client
+                .post_logout_redirect_uris
+                .contains(&post_logout_redirect_uri)
+            {
+                return Err(Status::coded(
+                    Code::InvalidArgument,
+                    ErrorCode::InvalidRedirectUri,
+                ));
+            }
+
+            let mut url = Url::parse(&post_logout_redirect_uri)
+                .map_err(|_| Status::coded(Code::InvalidArgument, ErrorCode::InvalidRedirectUri))?;
+            if let Some(state) = state {
+                url.query_pairs_mut().append_pair("state", &state);
+            }
+
+            Some(url.to_string())
+        } else {
+            None
+        };
+
+        Ok(Response::new(GetEndSessionInfoReply {
+            rp_info: client.map(Into::into),
+            redirect_to,
+        }))
+    }
+
+    /// Check that an ID token was issued by us and get its client ID and `sub`
+    ///
+    /// Expired ID tokens are accepted, as recommended by the spec.
+    fn verify_id_token_hint(&self, id_token_hint: &str) -> Option<(String, String)> {
+        let header = jsonwebtoken::decode_header(id_token_hint).ok()?;
+        if !ID_TOKEN_HINT_ALGORITHMS.contains(&header.alg) {
+            return None;
+        }
+
+        let signing_key = self.get_signing_key(header.kid.as_deref()?)?;
+        let jwk: Jwk = serde_json::to_value(signing_key.as_verification_key())
+            .and_then(serde_json::from_value)
+            .ok()?;
+        let decoding_key = DecodingKey::from_jwk(&jwk).ok()?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[self.issuer.as_str()]);
+        validation.validate_exp = false;
+        validation.validate_aud = false;
+        validation.set_required_spec_claims(&["iss", "sub", "aud"]);
+
+        let claims =
+            jsonwebtoken::decode::<IdTokenHintClaims>(id_token_hint, &decoding_key, &validation)
+                .ok()?
+                .claims;
+
+        // we only ever issue ID tokens with a single audience
+        let client_id = match claims.aud {
+            Value::String(aud) => aud,
+            Value::Array(aud) => aud.first()?.as_str()?.to_string(),
+            _ => return None,
+        };
+
+        Some((client_id, claims.sub))
+    }
+}
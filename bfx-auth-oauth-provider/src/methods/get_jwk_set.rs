@@ -1,13 +1,14 @@
 use crate::AuthOAuthProviderService;
 use bfx_core::status::{ErrorCode, StatusExt};
 use bfx_proto::auth::{GetJwkSetReply, GetJwkSetRequest};
-use openidconnect::PrivateSigningKey;
 use openidconnect::core::CoreJsonWebKeySet;
 use tonic::{Code, Request, Response, Status};
 
 impl AuthOAuthProviderService {
     /// `/openid/jwks` endpoint
     ///
+    /// Publishes the next, active and retired keys of every algorithm.
+    ///
     /// # Errors
     ///
     /// - Miscellaneous internal errors
@@ -15,7 +16,7 @@ impl AuthOAuthProviderService {
         &self,
         _request: Request<GetJwkSetRequest>,
     ) -> Result<Response<GetJwkSetReply>, Status> {
-        let jwks = CoreJsonWebKeySet::new(vec![self.rs_256_signing_key.as_verification_key()]);
+        let jwks = CoreJsonWebKeySet::new(self.get_verification_keys());
 
         Ok(Response::new(GetJwkSetReply {
             json: serde_json::to_string(&jwks).map_err(|err| {
@@ -1,4 +1,6 @@
 use crate::AuthOAuthProviderService;
+use crate::methods::get_authorization_info::SUPPORTED_SCOPES;
+use crate::signing_keys::KeyAlgorithm;
 use bfx_core::status::{ErrorCode, StatusExt};
 use bfx_proto::auth::{GetOpenidConfigurationReply, GetOpenidConfigurationRequest};
 use openidconnect::core::{
@@ -18,6 +20,26 @@ pub struct BfxAdditionalProviderMetadata {
     // no idea why it's not included in `openidconnect`, it's part of the spec:
     // https://www.rfc-editor.org/rfc/rfc8414.html#section-2
     pub code_challenge_methods_supported: Vec<PkceCodeChallengeMethod>,
+    // RFC 7009
+    pub revocation_endpoint: String,
+    pub revocation_endpoint_auth_methods_supported: Vec<CoreClientAuthMethod>,
+    pub revocation_endpoint_auth_signing_alg_values_supported: Vec<CoreJwsSigningAlgorithm>,
+    // RFC 7662
+    pub introspection_endpoint: String,
+    pub introspection_endpoint_auth_methods_supported: Vec<CoreClientAuthMethod>,
+    pub introspection_endpoint_auth_signing_alg_values_supported: Vec<CoreJwsSigningAlgorithm>,
+    // RFC 8628
+    pub device_authorization_endpoint: String,
+    // RFC 9126
+    pub pushed_authorization_request_endpoint: String,
+    pub require_pushed_authorization_requests: bool,
+    // RFC 9449
+    pub dpop_signing_alg_values_supported: Vec<CoreJwsSigningAlgorithm>,
+    // OpenID Connect RP-Initiated Logout 1.0
+    pub end_session_endpoint: String,
+    // OpenID Connect Back-Channel Logout 1.0
+    pub backchannel_logout_supported: bool,
+    pub backchannel_logout_session_supported: bool,
 }
 impl AdditionalProviderMetadata for BfxAdditionalProviderMetadata {}
 
@@ -46,21 +68,79 @@ impl AuthOAuthProviderService {
     /// # Panics
     ///
     /// If `FRONTEND_ROOT` is not a valid URL.
+    #[allow(clippy::too_many_lines)]
     pub fn get_openid_configuration(
         &self,
         _request: Request<GetOpenidConfigurationRequest>,
     ) -> Result<Response<GetOpenidConfigurationReply>, Status> {
+        let client_auth_methods = vec![
+            CoreClientAuthMethod::ClientSecretBasic,
+            CoreClientAuthMethod::ClientSecretPost,
+            CoreClientAuthMethod::PrivateKeyJwt,
+        ];
+        // public clients can use every endpoint except introspection
+        let public_client_auth_methods = [
+            client_auth_methods.clone(),
+            vec![CoreClientAuthMethod::None],
+        ]
+        .concat();
+        // keep in sync with `client_auth::ALLOWED_ASSERTION_ALGORITHMS`
+        let client_auth_signing_algs = vec![
+            CoreJwsSigningAlgorithm::RsaSsaPkcs1V15Sha256,
+            CoreJwsSigningAlgorithm::RsaSsaPkcs1V15Sha384,
+            CoreJwsSigningAlgorithm::RsaSsaPkcs1V15Sha512,
+            CoreJwsSigningAlgorithm::RsaSsaPssSha256,
+            CoreJwsSigningAlgorithm::RsaSsaPssSha384,
+            CoreJwsSigningAlgorithm::RsaSsaPssSha512,
+            CoreJwsSigningAlgorithm::EcdsaP256Sha256,
+            CoreJwsSigningAlgorithm::EcdsaP384Sha384,
+            CoreJwsSigningAlgorithm::EdDsa,
+        ];
+
         let provider_metadata = BfxProviderMetadata::new(
             self.issuer.clone(),
             AuthUrl::new(format!("{}/openid/authorize", self.frontend_root)).unwrap(),
             JsonWebKeySetUrl::new(format!("{}/openid/jwks", self.frontend_root)).unwrap(),
             vec![ResponseTypes::new(vec![CoreResponseType::Code])],
-            vec![CoreSubjectIdentifierType::Public],
-            vec![CoreJwsSigningAlgorithm::RsaSsaPkcs1V15Sha256],
+            vec![
+                CoreSubjectIdentifierType::Public,
+                CoreSubjectIdentifierType::Pairwise,
+            ],
+            KeyAlgorithm::ALL
+                .into_iter()
+                .map(KeyAlgorithm::jws_algorithm)
+                .collect(),
             BfxAdditionalProviderMetadata {
                 code_challenge_methods_supported: vec![PkceCodeChallengeMethod::new(
                     "S256".to_string(),
                 )],
+                revocation_endpoint: format!("{}/openid/revoke", self.frontend_root),
+                revocation_endpoint_auth_methods_supported: public_client_auth_methods.clone(),
+                revocation_endpoint_auth_signing_alg_values_supported: client_auth_signing_algs
+                    .clone(),
+                introspection_endpoint: format!("{}/openid/introspect", self.frontend_root),
+                introspection_endpoint_auth_methods_supported: client_auth_methods,
+                introspection_endpoint_auth_signing_alg_values_supported: client_auth_signing_algs
+                    .clone(),
+                device_authorization_endpoint: format!(
+                    "{}/openid/device_authorization",
+                    self.frontend_root
+                ),
+                pushed_authorization_request_endpoint: format!("{}/openid/par", self.frontend_root),
+                // only required for some clients
+                require_pushed_authorization_requests: false,
+                // keep in sync with `dpop::ALLOWED_DPOP_ALGORITHMS`
+                dpop_signing_alg_values_supported: vec![
+                    CoreJwsSigningAlgorithm::RsaSsaPkcs1V15Sha256,
+                    CoreJwsSigningAlgorithm::RsaSsaPssSha256,
+                    CoreJwsSigningAlgorithm::EcdsaP256Sha256,
+                    CoreJwsSigningAlgorithm::EcdsaP384Sha384,
+                    CoreJwsSigningAlgorithm::EdDsa,
+                ],
+                end_session_endpoint: format!("{}/openid/logout", self.frontend_root),
+                backchannel_logout_supported: true,
+                // logout tokens only have `sub`, there is no `sid`
+                backchannel_logout_session_supported: false,
             },
         )
         .set_token_endpoint(Some(
@@ -69,10 +149,16 @@ impl AuthOAuthProviderService {
         .set_userinfo_endpoint(Some(
             UserInfoUrl::new(format!("{}/openid/userinfo", self.frontend_root)).unwrap(),
         ))
+        .set_grant_types_supported(Some(vec![
+            CoreGrantType::AuthorizationCode,
+            CoreGrantType::RefreshToken,
+            CoreGrantType::ClientCredentials,
+            CoreGrantType::DeviceCode,
+        ]))
         .set_scopes_supported(Some(
-            vec!["openid", "email", "profile", "offline_access"]
-                .into_iter()
-                .map(|str| Scope::new(str.to_string()))
+            SUPPORTED_SCOPES
+                .iter()
+                .map(|str| Scope::new((*str).to_string()))
                 .collect(),
         ))
         .set_claims_supported(Some(
@@ -93,10 +179,8 @@ impl AuthOAuthProviderService {
             .map(|str| CoreClaimName::new(str.to_string()))
             .collect(),
         ))
-        .set_token_endpoint_auth_methods_supported(Some(vec![
-            CoreClientAuthMethod::ClientSecretBasic,
-            CoreClientAuthMethod::ClientSecretPost,
-        ]));
+        .set_token_endpoint_auth_methods_supported(Some(public_client_auth_methods))
+        .set_token_endpoint_auth_signing_alg_values_supported(Some(client_auth_signing_algs));
 
         Ok(Response::new(GetOpenidConfigurationReply {
             json: serde_json::to_string(&provider_metadata).map_err(|err| {
@@ -0,0 +1,202 @@
+use crate::AuthOAuthProviderService;
+use crate::access_token::Confirmation;
+use crate::dpop::DPOP_TOKEN_TYPE;
+use crate::models::client_info::ClientInfo;
+use bfx_core::status::{ErrorCode, StatusExt};
+use bfx_proto::auth::{
+    IntrospectionEndpointReply, IntrospectionEndpointRequest, TokenEndpointReply,
+};
+use openidconnect::core::CoreErrorResponseType;
+use serde::Serialize;
+use sqlx::types::chrono::{DateTime, Utc};
+use tonic::{Code, Request, Response, Status};
+
+#[derive(Serialize, Default)]
+struct IntrospectionResponse {
+    active: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scope: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sub: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token_type: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exp: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    iat: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    iss: Option<String>,
+    // RFC 9449 section 6.2
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cnf: Option<Confirmation>,
+}
+
+impl AuthOAuthProviderService {
+    /// `/openid/introspect` endpoint (RFC 7662)
+    ///
+    /// Clients can introspect their own tokens, resource servers can introspect any token.
+    /// Public clients can't use this endpoint.
+    /// Unknown, expired and revoked tokens, and tokens of other clients,
+    /// are returned as `{"active": false}`.
+    ///
+    /// # Errors
+    ///
+    /// Only internal errors are returned as `Err`.
+    /// All other request errors are returned as `Ok(Response)` with the appropriate error code.
+    pub async fn introspection_endpoint(
+        &self,
+        request: Request<IntrospectionEndpointRequest>,
+    ) -> Result<Response<IntrospectionEndpointReply>, Status> {
+        let request = request.into_inner();
+
+        let mut params = request.query;
+
+        let error_resp = |error, description: &str| {
+            let TokenEndpointReply { status, json } =
+                Self::make_error_resp(error, description).into_inner();
+            Response::new(IntrospectionEndpointReply { status, json })
+        };
+
+        let caller = match self
+            .authenticate_client(&mut params, request.authorization)
+            .await?
+        {
+            Ok(client) => client,
+            Err(description) => {
+                return Ok(error_resp(
+                    CoreErrorResponseType::InvalidClient,
+                    &description,
+                ));
+            }
+        };
+
+        // anyone can claim to be a public client
+        if caller.is_public() {
+            return Ok(error_resp(
+                CoreErrorResponseType::InvalidClient,
+                "public clients can't introspect tokens",
+            ));
+        }
+
+        let Some(token) = params.remove("token") else {
+            return Ok(error_resp(
+                CoreErrorResponseType::InvalidRequest,
+                "missing parameter `token`",
+            ));
+        };
+
+        // the token type is encoded in the token itself, so `token_type_hint` isn't needed
+        let resp = self
+            .introspect_token(&caller, &token)
+            .await?
+            .unwrap_or_default();
+
+        Ok(Response::new(IntrospectionEndpointReply {
+            status: 200,
+            json: serde_json::to_string(&resp).map_err(|err| {
+                Status::coded(Code::Internal, ErrorCode::Internal).with_source(err)
+            })?,
+        }))
+    }
+
+    async fn introspect_token(
+        &self,
+        caller: &ClientInfo,
+        token: &str,
+    ) -> Result<Option<IntrospectionResponse>, Status> {
+        let Some((token_type, flow_id)) = self.parse_token(token) else {
+            return Ok(None);
+        };
+
+        let flow = match token_type.as_str() {
+            "A" => sqlx::query!(
+                "select
+                     f.scopes,
+                     f.user_id,
+                     f.authorized_at,
+                     f.access_token_expires_at as expires_at,
+                     f.client_id,
+                     f.dpop_jkt
+                 from auth_oauth_provider.flows f
+                 where f.id = $1 and f.access_token = $2 and f.access_token_expires_at > now()",
+                flow_id,
+                token,
+            )
+            .fetch_optional(&self.db)
+            .await
+            .map_err(Status::db)?
+            .map(|flow| {
+                (
+                    flow.scopes,
+                    flow.user_id,
+                    flow.authorized_at,
+                    flow.expires_at,
+                    flow.client_id,
+                    flow.dpop_jkt,
+                )
+            }),
+            "R" => sqlx::query!(
+                "select
+                     f.scopes,
+                     f.user_id,
+                     f.authorized_at,
+                     f.refresh_token_expires_at as expires_at,
+                     f.client_id,
+                     f.dpop_jkt
+                 from auth_oauth_provider.flows f
+                 where f.id = $1 and f.refresh_token = $2
+                       and (f.refresh_token_expires_at is null
+                            or f.refresh_token_expires_at > now())",
+                flow_id,
+                token,
+            )
+            .fetch_optional(&self.db)
+            .await
+            .map_err(Status::db)?
+            .map(|flow| {
+                (
+                    flow.scopes,
+                    flow.user_id,
+                    flow.authorized_at,
+                    flow.expires_at,
+                    flow.client_id,
+                    flow.dpop_jkt,
+                )
+            }),
+            _ => None,
+        };
+
+        let Some((scopes, user_id, authorized_at, expires_at, client_id, dpop_jkt)) = flow else {
+            return Ok(None);
+        };
+        // don't tell other clients who the token belongs to
+        if client_id != caller.id && !caller.resource_server {
+            return Ok(None);
+        }
+        let client = self.get_client_by_id(client_id).await?;
+
+        let access_token_type = if dpop_jkt.is_some() {
+            DPOP_TOKEN_TYPE
+        } else {
+            "Bearer"
+        };
+
+        Ok(Some(IntrospectionResponse {
+            active: true,
+            scope: Some(scopes.join(" ")),
+            // client-only tokens act as the client itself (like in RFC 9068)
+            sub: Some(user_id.map_or_else(
+                || client.client_id.clone(),
+                |user_id| self.get_subject(&client, user_id),
+            )),
+            client_id: Some(client.client_id),
+            token_type: (token_type == "A").then_some(access_token_type),
+            exp: expires_at.as_ref().map(DateTime::<Utc>::timestamp),
+            iat: authorized_at.as_ref().map(DateTime::<Utc>::timestamp),
+            iss: Some(self.issuer.to_string()),
+            cnf: dpop_jkt.map(|jkt| Confirmation { jkt }),
+        }))
+    }
+}
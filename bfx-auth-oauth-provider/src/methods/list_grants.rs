@@ -0,0 +1,56 @@
+use crate::AuthOAuthProviderService;
+use bfx_core::status::StatusExt;
+use bfx_proto::auth::{ListGrantsReply, ListGrantsRequest, OAuthGrant, RpInfo};
+use tonic::{Request, Response, Status};
+
+impl AuthOAuthProviderService {
+    /// List the OAuth clients a user has authorized
+    ///
+    /// # Errors
+    ///
+    /// - Miscellaneous internal errors
+    pub async fn list_grants(
+        &self,
+        request: Request<ListGrantsRequest>,
+    ) -> Result<Response<ListGrantsReply>, Status> {
+        let request = request.into_inner();
+
+        let grants = sqlx::query!(
+            "select
+                 g.id,
+                 g.scopes,
+                 g.created_at,
+                 c.id as client_id,
+                 c.display_name,
+                 c.privacy_url,
+                 c.tos_url,
+                 c.official
+             from auth_oauth_provider.grants g
+             inner join auth_oauth_provider.clients c on g.client_id = c.id
+             where g.user_id = $1
+             order by g.created_at desc",
+            request.user_id,
+        )
+        .fetch_all(&self.db)
+        .await
+        .map_err(Status::db)?;
+
+        Ok(Response::new(ListGrantsReply {
+            grants: grants
+                .into_iter()
+                .map(|grant| OAuthGrant {
+                    id: grant.id,
+                    client: Some(RpInfo {
+                        id: grant.client_id,
+                        display_name: grant.display_name,
+                        privacy_url: grant.privacy_url,
+                        tos_url: grant.tos_url,
+                        official: grant.official,
+                    }),
+                    scopes: grant.scopes,
+                    created_at: Some(grant.created_at.into()),
+                })
+                .collect(),
+        }))
+    }
+}
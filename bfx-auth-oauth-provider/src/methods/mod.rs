@@ -1,7 +1,22 @@
 mod accept_authorization;
+mod accept_device_authorization;
+mod backchannel_logout;
+mod create_client;
+mod delete_client;
+mod device_authorization_endpoint;
 mod get_access_token;
 mod get_authorization_info;
+mod get_clients;
+mod get_device_authorization_info;
+mod get_end_session_info;
 mod get_jwk_set;
 mod get_openid_configuration;
+mod introspection_endpoint;
+mod list_grants;
+mod pushed_authorization_request_endpoint;
+mod revocation_endpoint;
+mod revoke_grant;
+mod rotate_client_secret;
 mod token_endpoint;
+mod update_client;
 mod userinfo_endpoint;
@@ -0,0 +1,119 @@
+use crate::AuthOAuthProviderService;
+use bfx_core::status::{ErrorCode, StatusExt};
+use bfx_proto::auth::{
+    PushedAuthorizationRequestEndpointReply, PushedAuthorizationRequestEndpointRequest,
+    TokenEndpointReply,
+};
+use nanoid::nanoid;
+use openidconnect::core::CoreErrorResponseType;
+use serde_json::json;
+use sqlx::types::Json;
+use sqlx::types::chrono::Utc;
+use std::time::Duration;
+use tonic::{Code, Request, Response, Status};
+
+// the user may still have to log in before the flow is created from it
+const PUSHED_AUTHORIZATION_REQUEST_LIFETIME: Duration = Duration::from_mins(5);
+
+const REQUEST_URI_PREFIX: &str = "urn:ietf:params:oauth:request_uri:";
+
+impl AuthOAuthProviderService {
+    /// `/openid/par` endpoint (RFC 9126)
+    ///
+    /// The parameters are validated like on `/openid/authorize` and stored,
+    /// so they can be referenced by the returned `request_uri`.
+    ///
+    /// # Errors
+    ///
+    /// Only internal errors are returned as `Err`.
+    /// All other request errors are returned as `Ok(Response)` with the appropriate error code.
+    pub async fn pushed_authorization_request_endpoint(
+        &self,
+        request: Request<PushedAuthorizationRequestEndpointRequest>,
+    ) -> Result<Response<PushedAuthorizationRequestEndpointReply>, Status> {
+        let request = request.into_inner();
+
+        let mut params = request.query;
+
+        let error_resp = |error, description: &str| {
+            let TokenEndpointReply { status, json } =
+                Self::make_error_resp(error, description).into_inner();
+            Response::new(PushedAuthorizationRequestEndpointReply { status, json })
+        };
+
+        let client = match self
+            .authenticate_client(&mut params, request.authorization)
+            .await?
+        {
+            Ok(client) => client,
+            Err(description) => {
+                return Ok(error_resp(
+                    CoreErrorResponseType::InvalidClient,
+                    &description,
+                ));
+            }
+        };
+
+        if params.contains_key("request_uri") {
+            return Ok(error_resp(
+                CoreErrorResponseType::InvalidRequest,
+                "`request_uri` can't be pushed",
+            ));
+        }
+
+        // the client is authenticated, so it's the one the request is for
+        params.insert("client_id".to_string(), client.client_id.clone());
+
+        if let Err(status) = self
+            .validate_authorization_request(&mut params.clone())
+            .await
+        {
+            if status.code() != Code::InvalidArgument {
+                return Err(status);
+            }
+
+            let error = if status.to_error_code() == Some(ErrorCode::InvalidScope) {
+                CoreErrorResponseType::InvalidScope
+            } else {
+                CoreErrorResponseType::InvalidRequest
+            };
+            let description = status
+                .message()
+                .split_once(": ")
+                .map_or_else(|| status.message(), |(_, details)| details);
+
+            return Ok(error_resp(error, description));
+        }
+
+        sqlx::query!(
+            "delete from auth_oauth_provider.pushed_authorization_requests where expires_at < now()"
+        )
+        .execute(&self.db)
+        .await
+        .map_err(Status::db)?;
+
+        let request_uri = format!("{REQUEST_URI_PREFIX}{}", nanoid!(32));
+
+        sqlx::query!(
+            "insert into auth_oauth_provider.pushed_authorization_requests
+             (client_id, request_uri, params, expires_at)
+             values ($1, $2, $3, $4)",
+            client.id,
+            request_uri,
+            Json(&params) as _,
+            Utc::now() + PUSHED_AUTHORIZATION_REQUEST_LIFETIME,
+        )
+        .execute(&self.db)
+        .await
+        .map_err(Status::db)?;
+
+        Ok(Response::new(PushedAuthorizationRequestEndpointReply {
+            status: 201,
+            json: serde_json::to_string(&json!({
+                "request_uri": request_uri,
+                "expires_in": PUSHED_AUTHORIZATION_REQUEST_LIFETIME.as_secs(),
+            }))
+            .map_err(|err| Status::coded(Code::Internal, ErrorCode::Internal).with_source(err))?,
+        }))
+    }
+}
@@ -0,0 +1,95 @@
+use crate::AuthOAuthProviderService;
+use bfx_core::status::StatusExt;
+use bfx_proto::auth::{RevocationEndpointReply, RevocationEndpointRequest, TokenEndpointReply};
+use openidconnect::core::CoreErrorResponseType;
+use tonic::{Request, Response, Status};
+use tracing::info;
+
+impl AuthOAuthProviderService {
+    /// `/openid/revoke` endpoint (RFC 7009)
+    ///
+    /// Revoking a refresh token also revokes the access token issued with it.
+    /// Unknown or already revoked tokens are not an error.
+    ///
+    /// # Errors
+    ///
+    /// Only internal errors are returned as `Err`.
+    /// All other request errors are returned as `Ok(Response)` with the appropriate error code.
+    pub async fn revocation_endpoint(
+        &self,
+        request: Request<RevocationEndpointRequest>,
+    ) -> Result<Response<RevocationEndpointReply>, Status> {
+        let request = request.into_inner();
+
+        let mut params = request.query;
+
+        let error_resp = |error, description: &str| {
+            let TokenEndpointReply { status, json } =
+                Self::make_error_resp(error, description).into_inner();
+            Response::new(RevocationEndpointReply { status, json })
+        };
+
+        let client = match self
+            .authenticate_client(&mut params, request.authorization)
+            .await?
+        {
+            Ok(client) => client,
+            Err(description) => {
+                return Ok(error_resp(
+                    CoreErrorResponseType::InvalidClient,
+                    &description,
+                ));
+            }
+        };
+
+        let Some(token) = params.remove("token") else {
+            return Ok(error_resp(
+                CoreErrorResponseType::InvalidRequest,
+                "missing parameter `token`",
+            ));
+        };
+
+        // the token type is encoded in the token itself, so `token_type_hint` isn't needed
+        let ok = Response::new(RevocationEndpointReply {
+            status: 200,
+            json: String::new(),
+        });
+
+        let Some((token_type, flow_id)) = self.parse_token(&token) else {
+            return Ok(ok);
+        };
+
+        let result = match token_type.as_str() {
+            "A" => sqlx::query!(
+                "update auth_oauth_provider.flows
+                 set access_token = null, access_token_expires_at = null
+                 where id = $1 and client_id = $2 and access_token = $3",
+                flow_id,
+                client.id,
+                token,
+            )
+            .execute(&self.db)
+            .await
+            .map_err(Status::db)?,
+            "R" => sqlx::query!(
+                "update auth_oauth_provider.flows
+                 set access_token = null, access_token_expires_at = null,
+                     refresh_token = null, refresh_token_expires_at = null
+                 where id = $1 and client_id = $2 and refresh_token = $3",
+                flow_id,
+                client.id,
+                token,
+            )
+            .execute(&self.db)
+            .await
+            .map_err(Status::db)?,
+            _ => return Ok(ok),
+        };
+
+        if result.rows_affected() > 0 {
+            info!(flow_id, client_id = client.id, token_type, "revoked token");
+        }
+
+        Ok(ok)
+    }
+}
@@ -0,0 +1,81 @@
+use crate::AuthOAuthProviderService;
+use crate::models::client_info::ClientInfo;
+use bfx_core::log_if_error::LogIfErrorExt;
+use bfx_core::status::{ErrorCode, StatusExt};
+use bfx_proto::auth::{RevokeGrantReply, RevokeGrantRequest};
+use bfx_proto::notification::SendNotificationRequest;
+use bfx_proto::notification::notification_client::NotificationClient;
+use bfx_proto::param_map;
+use sqlx::types::chrono::Utc;
+use tonic::{Code, Request, Response, Status};
+use tracing::info;
+
+impl AuthOAuthProviderService {
+    /// Revoke a user's authorization of an OAuth client
+    ///
+    /// All tokens issued under the grant are invalidated,
+    /// and the client is notified if it supports back-channel logout.
+    ///
+    /// # Errors
+    ///
+    /// - If the grant does not exist or belongs to another user
+    /// - Miscellaneous internal errors
+    pub async fn revoke_grant(
+        &self,
+        request: Request<RevokeGrantRequest>,
+    ) -> Result<Response<RevokeGrantReply>, Status> {
+        let request = request.into_inner();
+
+        let user_context = request
+            .user_context
+            .ok_or_else(|| Status::coded(Code::InvalidArgument, ErrorCode::Internal))?;
+
+        // flows (and with them, all tokens) are deleted by the cascade
+        let client = sqlx::query_as!(
+            ClientInfo,
+            "with g as (
+                 delete from auth_oauth_provider.grants
+                 where id = $1 and user_id = $2
+                 returning client_id
+             )
+             select c.*
+             from g
+             inner join auth_oauth_provider.clients c on g.client_id = c.id",
+            request.id,
+            request.user_id,
+        )
+        .fetch_optional(&self.db)
+        .await
+        .map_err(Status::db)?
+        .ok_or_else(|| Status::coded(Code::NotFound, ErrorCode::GrantNotFound))?;
+
+        info!(
+            grant_id = request.id,
+            user_id = request.user_id,
+            "revoked grant"
+        );
+
+        let display_name = client.display_name.clone();
+
+        // the client should end the user's sessions too
+        self.send_logout_tokens(request.user_id, vec![client]);
+
+        let mut notification = NotificationClient::new(self.router.clone());
+        notification
+            .send_notification(SendNotificationRequest {
+                user_id: request.user_id,
+                user_override: None,
+                definition_id: "oauth_grant_revoked".to_string(),
+                params: param_map! {
+                    "audit_ip" => user_context.ip,
+                    "audit_time" => Utc::now().to_rfc3339(),
+                    "client" => display_name,
+                },
+                send_at: None,
+            })
+            .await
+            .log_if_error("sending oauth grant revoked notification");
+
+        Ok(Response::new(RevokeGrantReply {}))
+    }
+}
@@ -0,0 +1,41 @@
+use crate::AuthOAuthProviderService;
+use crate::client_auth::generate_client_secret;
+use bfx_core::status::{ErrorCode, StatusExt};
+use bfx_proto::auth::{RotateClientSecretReply, RotateClientSecretRequest};
+use tonic::{Code, Request, Response, Status};
+
+impl AuthOAuthProviderService {
+    /// Replace the secret of an OAuth client with a new one
+    ///
+    /// The old secret stops working immediately.
+    ///
+    /// # Errors
+    ///
+    /// - If the client does not exist or is not owned by `owner_id`
+    /// - Miscellaneous internal errors
+    pub async fn rotate_client_secret(
+        &self,
+        request: Request<RotateClientSecretRequest>,
+    ) -> Result<Response<RotateClientSecretReply>, Status> {
+        let request = request.into_inner();
+
+        let (client_secret, client_secret_hash) = generate_client_secret();
+
+        let result = sqlx::query!(
+            "update auth_oauth_provider.clients
+             set client_secret_hash = $3
+             where id = $1 and ($2::bigint is null or owner_id = $2)",
+            request.id,
+            request.owner_id,
+            client_secret_hash,
+        )
+        .execute(&self.db)
+        .await
+        .map_err(Status::db)?;
+        if result.rows_affected() == 0 {
+            return Err(Status::coded(Code::NotFound, ErrorCode::ClientNotFound));
+        }
+
+        Ok(Response::new(RotateClientSecretReply { client_secret }))
+    }
+}
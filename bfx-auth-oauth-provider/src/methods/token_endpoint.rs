@@ -1,18 +1,25 @@
 use crate::AuthOAuthProviderService;
+use crate::dpop::DPOP_TOKEN_TYPE;
 use crate::methods::get_authorization_info::is_subset;
+use crate::models::client_info::ClientInfo;
 use crate::models::flow::Flow;
+use crate::signing_keys::KeyAlgorithm;
+use crate::subject::SubjectType;
 use bfx_core::log_if_error::LogIfErrorExt;
 use bfx_core::service::id_encryption::IdType;
 use bfx_core::status::{ErrorCode, StatusExt};
 use bfx_proto::auth::auth_core_client::AuthCoreClient;
 use bfx_proto::auth::{TokenEndpointReply, TokenEndpointRequest, User};
 use bfx_proto::image::image_client::ImageClient;
+use bfx_proto::notification::SendNotificationRequest;
+use bfx_proto::notification::notification_client::NotificationClient;
+use bfx_proto::param_map;
 use bfx_proto::profile::ProfileDetails;
 use bfx_proto::profile::profile_client::ProfileClient;
 use nanoid::nanoid;
 use openidconnect::core::{
     CoreErrorResponseType, CoreGenderClaim, CoreGrantType, CoreIdToken, CoreIdTokenClaims,
-    CoreIdTokenFields, CoreJwsSigningAlgorithm, CoreTokenResponse, CoreTokenType,
+    CoreIdTokenFields, CoreTokenResponse, CoreTokenType,
 };
 use openidconnect::{
     AccessToken, Audience, AuthorizationCode, ClientId, EmptyAdditionalClaims,
@@ -23,6 +30,7 @@ use openidconnect::{
 use serde_json::{Value, json};
 use sqlx::types::chrono::Utc;
 use sqlx::{Postgres, Transaction};
+use std::str::FromStr;
 use std::time::Duration;
 use tonic::{Code, Request, Response, Status};
 use tracing::warn;
@@ -41,7 +49,21 @@ macro_rules! let_some {
     };
 }
 
-const OAUTH_ACCESS_TOKEN_LIFETIME: Duration = Duration::from_secs(3600);
+// old refresh tokens are kept this long to detect reuse
+const USED_REFRESH_TOKEN_RETENTION: Duration = Duration::from_hours(30 * 24);
+
+// scopes that can be granted without a user (`client_credentials` grant),
+// all other scopes are about the data of a user. none of the API scopes work without one yet
+const CLIENT_ONLY_SCOPES: &[&str] = &[];
+
+/// Get the `token_type` of an access token, depending on whether it's bound to a `DPoP` key
+fn token_type(dpop_jkt: Option<&str>) -> CoreTokenType {
+    if dpop_jkt.is_some() {
+        CoreTokenType::Extension(DPOP_TOKEN_TYPE.into())
+    } else {
+        CoreTokenType::Bearer
+    }
+}
 
 impl AuthOAuthProviderService {
     /// `/openid/token` endpoint
@@ -50,6 +72,7 @@ impl AuthOAuthProviderService {
     ///
     /// Only internal errors are returned as `Err`.
     /// All other request errors are returned as `Ok(Response)` with the appropriate error code.
+    #[allow(clippy::too_many_lines)]
     pub async fn token_endpoint(
         &self,
         request: Request<TokenEndpointRequest>,
@@ -58,17 +81,6 @@ impl AuthOAuthProviderService {
 
         let mut params = request.query;
 
-        let (client_id, client_secret) = match request.authorization {
-            Some(basic) => (Some(basic.username), Some(basic.password)),
-            None => (None, None),
-        };
-
-        let client_id = params.remove("client_id").or(client_id);
-        let client_secret = params.remove("client_secret").or(client_secret);
-
-        let_some!(self, client_id, CoreErrorResponseType::InvalidClient);
-        let_some!(self, client_secret, CoreErrorResponseType::InvalidClient);
-
         let grant_type = params.remove("grant_type");
         let_some!(self, grant_type);
         let grant_type: Result<CoreGrantType, _> =
@@ -80,26 +92,51 @@ impl AuthOAuthProviderService {
             ));
         };
 
-        let client = sqlx::query!(
-            "select * from auth_oauth_provider.clients where client_id = $1",
-            client_id,
-        )
-        .fetch_optional(&self.db)
-        .await
-        .map_err(Status::db)?;
+        let client = match self
+            .authenticate_client(&mut params, request.authorization)
+            .await?
+        {
+            Ok(client) => client,
+            Err(description) => {
+                return Ok(Self::make_error_resp(
+                    CoreErrorResponseType::InvalidClient,
+                    &description,
+                ));
+            }
+        };
 
-        let Some(client) = client else {
-            return Ok(Self::make_error_resp(
-                CoreErrorResponseType::InvalidClient,
-                "client not found",
-            ));
+        // sender-constrain the tokens to the key of the proof
+        let dpop_jkt = if let Some(dpop) = request.dpop {
+            match self
+                .verify_dpop_proof(&dpop, "POST", "/openid/token", None)
+                .await?
+            {
+                Ok(jkt) => Some(jkt),
+                Err(description) => {
+                    return Ok(Self::make_error_resp(
+                        CoreErrorResponseType::Extension("invalid_dpop_proof".into()),
+                        &description,
+                    ));
+                }
+            }
+        } else {
+            None
         };
 
-        if client.client_secret != client_secret {
-            return Ok(Self::make_error_resp(
-                CoreErrorResponseType::InvalidClient,
-                "wrong client secret",
-            ));
+        // public clients can't prove who they are, so their tokens must at least be bound to a key
+        if client.is_public() {
+            if grant_type == CoreGrantType::ClientCredentials {
+                return Ok(Self::make_error_resp(
+                    CoreErrorResponseType::UnauthorizedClient,
+                    "public clients can't use the client_credentials grant",
+                ));
+            }
+            if dpop_jkt.is_none() {
+                return Ok(Self::make_error_resp(
+                    CoreErrorResponseType::Extension("invalid_dpop_proof".into()),
+                    "public clients must use DPoP",
+                ));
+            }
         }
 
         match grant_type {
@@ -123,7 +160,7 @@ impl AuthOAuthProviderService {
                 .await
                 .map_err(Status::db)?;
 
-                let Some(flow) = flow else {
+                let Some(mut flow) = flow else {
                     tx.rollback().await.map_err(Status::db)?;
                     return Ok(Self::make_error_resp(
                         CoreErrorResponseType::InvalidGrant,
@@ -132,6 +169,13 @@ impl AuthOAuthProviderService {
                 };
 
                 // check that code_verifier is specified if required or the other way around
+                if flow.code_challenge.is_none() && client.is_public() {
+                    tx.rollback().await.map_err(Status::db)?;
+                    return Ok(Self::make_error_resp(
+                        CoreErrorResponseType::InvalidGrant,
+                        "public clients must use PKCE",
+                    ));
+                }
                 if flow.code_challenge.is_some() && code_verifier.is_none() {
                     tx.rollback().await.map_err(Status::db)?;
                     return Ok(Self::make_error_resp(
@@ -188,8 +232,10 @@ impl AuthOAuthProviderService {
                     ));
                 }
 
+                flow.dpop_jkt = dpop_jkt;
+
                 let ret = self
-                    .get_token_response_for_flow(flow, None, Some(code), &mut tx)
+                    .get_token_response_for_flow(flow, false, Some(code), &mut tx)
                     .await?;
 
                 tx.commit().await.map_err(Status::db)?;
@@ -199,120 +245,452 @@ impl AuthOAuthProviderService {
             CoreGrantType::RefreshToken => {
                 let refresh_token = params.remove("refresh_token");
                 let_some!(self, refresh_token, CoreErrorResponseType::InvalidRequest);
+                let scope = params.remove("scope");
 
-                let flow_id = refresh_token.split('/').nth(2);
-                let Some(flow_id) = flow_id else {
-                    return Ok(Self::make_error_resp(
-                        CoreErrorResponseType::InvalidGrant,
-                        "invalid refresh token",
-                    ));
-                };
-                let flow_id = self.id_encryptor.decrypt_id(IdType::OAuthFlow, flow_id);
-                let Ok(flow_id) = flow_id else {
-                    return Ok(Self::make_error_resp(
-                        CoreErrorResponseType::InvalidGrant,
-                        "invalid refresh token",
-                    ));
-                };
+                self.exchange_refresh_token(&client, &refresh_token, scope, dpop_jkt)
+                    .await
+            }
+            CoreGrantType::ClientCredentials => {
+                let scope = params.remove("scope");
 
-                let mut tx = self.db.begin().await.map_err(Status::db)?;
+                self.exchange_client_credentials(&client, scope, dpop_jkt)
+                    .await
+            }
+            CoreGrantType::DeviceCode => {
+                let device_code = params.remove("device_code");
+                let_some!(self, device_code, CoreErrorResponseType::InvalidRequest);
 
-                let flow = sqlx::query_as!(
-                    Flow,
-                    "select * from auth_oauth_provider.flows
-                     where id = $1 and refresh_token = $2 and client_id = $3",
-                    flow_id,
-                    refresh_token,
-                    client.id,
-                )
-                .fetch_optional(&mut *tx)
-                .await
-                .map_err(Status::db)?;
+                self.exchange_device_code(&client, &device_code, dpop_jkt)
+                    .await
+            }
+            typ => Ok(Self::make_error_resp(
+                CoreErrorResponseType::UnsupportedGrantType,
+                &format!("unsupported grant type `{}`", typ.as_ref()),
+            )),
+        }
+    }
 
-                let Some(mut flow) = flow else {
-                    tx.rollback().await.map_err(Status::db)?;
-                    return Ok(Self::make_error_resp(
-                        CoreErrorResponseType::InvalidGrant,
-                        "invalid refresh token",
-                    ));
-                };
+    /// Exchange a refresh token for new tokens (`refresh_token` grant)
+    ///
+    /// Refresh tokens are rotated, every refresh issues a new one and invalidates the old one.
+    /// If an old refresh token is used again, it was most likely stolen, so the whole grant
+    /// is revoked and the user is notified.
+    ///
+    /// Refresh tokens issued with `DPoP` can only be used with a proof for the same key.
+    #[allow(clippy::too_many_lines)]
+    async fn exchange_refresh_token(
+        &self,
+        client: &ClientInfo,
+        refresh_token: &str,
+        scope: Option<String>,
+        dpop_jkt: Option<String>,
+    ) -> Result<Response<TokenEndpointReply>, Status> {
+        let invalid_grant = || {
+            Ok(Self::make_error_resp(
+                CoreErrorResponseType::InvalidGrant,
+                "invalid refresh token",
+            ))
+        };
 
-                let scope = params.remove("scope");
-                if let Some(scope) = scope {
-                    let scope = scope.split(' ').collect::<Vec<_>>();
+        let Some((token_type, flow_id)) = self.parse_token(refresh_token) else {
+            return invalid_grant();
+        };
+        if token_type != "R" {
+            return invalid_grant();
+        }
 
-                    if !is_subset!(&scope, &flow.scopes) {
-                        tx.rollback().await.map_err(Status::db)?;
-                        return Ok(Self::make_error_resp(
-                            CoreErrorResponseType::InvalidScope,
-                            "invalid scope",
-                        ));
-                    }
+        let mut tx = self.db.begin().await.map_err(Status::db)?;
 
-                    flow.scopes = scope.into_iter().map(String::from).collect();
-                }
+        // locked, so a concurrent refresh with the same token is detected as reuse
+        let flow = sqlx::query_as!(
+            Flow,
+            "select * from auth_oauth_provider.flows
+             where id = $1 and refresh_token = $2 and client_id = $3
+             for update",
+            flow_id,
+            refresh_token,
+            client.id,
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(Status::db)?;
 
-                let ret = self
-                    .get_token_response_for_flow(flow, Some(refresh_token), None, &mut tx)
-                    .await?;
+        let Some(mut flow) = flow else {
+            let reused = sqlx::query!(
+                "select f.id, f.grant_id, f.user_id
+                 from auth_oauth_provider.used_refresh_tokens u
+                 inner join auth_oauth_provider.flows f on u.flow_id = f.id
+                 where u.refresh_token = $1 and f.client_id = $2",
+                refresh_token,
+                client.id,
+            )
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(Status::db)?;
 
-                tx.commit().await.map_err(Status::db)?;
+            let Some(reused) = reused else {
+                tx.rollback().await.map_err(Status::db)?;
+                return invalid_grant();
+            };
 
-                Ok(Response::new(ret))
+            // the other flows of the grant are deleted by the cascade
+            sqlx::query!(
+                "delete from auth_oauth_provider.grants where id = $1",
+                reused.grant_id,
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(Status::db)?;
+            sqlx::query!(
+                "delete from auth_oauth_provider.flows where id = $1",
+                reused.id,
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(Status::db)?;
+
+            tx.commit().await.map_err(Status::db)?;
+
+            warn!(
+                flow_id = reused.id,
+                grant_id = reused.grant_id,
+                client_id = client.client_id,
+                "refresh token reused, revoked grant"
+            );
+
+            if let Some(user_id) = reused.user_id {
+                self.notify_refresh_token_reuse(client, user_id).await;
             }
-            typ => Ok(Self::make_error_resp(
-                CoreErrorResponseType::UnsupportedGrantType,
-                &format!("unsupported grant type `{}`", typ.as_ref()),
-            )),
+
+            return invalid_grant();
+        };
+
+        if flow.dpop_jkt.is_some() && flow.dpop_jkt != dpop_jkt {
+            tx.rollback().await.map_err(Status::db)?;
+            return Ok(Self::make_error_resp(
+                CoreErrorResponseType::InvalidGrant,
+                "refresh token is bound to another DPoP key",
+            ));
+        }
+        flow.dpop_jkt = dpop_jkt;
+
+        if let Some(scope) = scope {
+            let scope = scope.split(' ').collect::<Vec<_>>();
+
+            if !is_subset!(&scope, &flow.scopes) {
+                tx.rollback().await.map_err(Status::db)?;
+                return Ok(Self::make_error_resp(
+                    CoreErrorResponseType::InvalidScope,
+                    "invalid scope",
+                ));
+            }
+
+            flow.scopes = scope.into_iter().map(String::from).collect();
+        }
+
+        sqlx::query!(
+            "delete from auth_oauth_provider.used_refresh_tokens
+             where flow_id = $1 and used_at < $2",
+            flow.id,
+            Utc::now() - USED_REFRESH_TOKEN_RETENTION,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(Status::db)?;
+        sqlx::query!(
+            "insert into auth_oauth_provider.used_refresh_tokens (flow_id, refresh_token)
+             values ($1, $2)",
+            flow.id,
+            refresh_token,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(Status::db)?;
+
+        let ret = self
+            .get_token_response_for_flow(flow, true, None, &mut tx)
+            .await?;
+
+        tx.commit().await.map_err(Status::db)?;
+
+        Ok(Response::new(ret))
+    }
+
+    /// Revoke the client's logins and tell the user that a refresh token was reused
+    async fn notify_refresh_token_reuse(&self, client: &ClientInfo, user_id: i64) {
+        // the client should end the user's sessions too
+        self.send_logout_tokens(user_id, vec![client.clone()]);
+
+        let mut notification = NotificationClient::new(self.router.clone());
+        notification
+            .send_notification(SendNotificationRequest {
+                user_id,
+                user_override: None,
+                definition_id: "oauth_refresh_token_reused".to_string(),
+                params: param_map! {
+                    "audit_time" => Utc::now().to_rfc3339(),
+                    "client" => client.display_name.clone(),
+                },
+                send_at: None,
+            })
+            .await
+            .log_if_error("sending oauth refresh token reused notification");
+    }
+
+    /// Issue a client-only access token (`client_credentials` grant)
+    ///
+    /// The token has no user, so it has no refresh token or ID token either.
+    /// Only scopes in [`CLIENT_ONLY_SCOPES`] are granted, requesting others is `invalid_scope`.
+    async fn exchange_client_credentials(
+        &self,
+        client: &ClientInfo,
+        scope: Option<String>,
+        dpop_jkt: Option<String>,
+    ) -> Result<Response<TokenEndpointReply>, Status> {
+        let scopes = if let Some(scope) = scope {
+            let scopes = scope.split(' ').map(String::from).collect::<Vec<_>>();
+
+            if scopes.iter().any(|scope| {
+                !CLIENT_ONLY_SCOPES.contains(&scope.as_str())
+                    || !client.allowed_scopes.contains(scope)
+            }) {
+                return Ok(Self::make_error_resp(
+                    CoreErrorResponseType::InvalidScope,
+                    "invalid scope",
+                ));
+            }
+
+            scopes
+        } else {
+            client
+                .allowed_scopes
+                .iter()
+                .filter(|scope| CLIENT_ONLY_SCOPES.contains(&scope.as_str()))
+                .cloned()
+                .collect()
+        };
+
+        let mut tx = self.db.begin().await.map_err(Status::db)?;
+
+        let flow = sqlx::query!(
+            "insert into auth_oauth_provider.flows
+             (client_id, scopes, authorized_at, dpop_jkt)
+             values ($1, $2, now(), $3)
+             returning id",
+            client.id,
+            &scopes,
+            dpop_jkt,
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(Status::db)?;
+
+        let (access_token, at_lifetime) =
+            self.make_access_token(client, flow.id, None, &scopes, dpop_jkt.as_deref())?;
+
+        sqlx::query!(
+            "update auth_oauth_provider.flows
+             set access_token = $1, access_token_expires_at = $2
+             where id = $3",
+            access_token,
+            Utc::now() + at_lifetime,
+            flow.id,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(Status::db)?;
+
+        tx.commit().await.map_err(Status::db)?;
+
+        let mut resp = CoreTokenResponse::new(
+            AccessToken::new(access_token),
+            token_type(dpop_jkt.as_deref()),
+            CoreIdTokenFields::new(None, EmptyExtraTokenFields {}),
+        );
+        resp.set_scopes(Some(scopes.into_iter().map(Scope::new).collect()));
+        resp.set_expires_in(Some(&at_lifetime));
+
+        Ok(Response::new(TokenEndpointReply {
+            status: 200,
+            json: serde_json::to_string(&resp).map_err(|err| {
+                Status::coded(Code::Internal, ErrorCode::Internal).with_source(err)
+            })?,
+        }))
+    }
+
+    /// Poll for the tokens of a device authorization (RFC 8628)
+    async fn exchange_device_code(
+        &self,
+        client: &ClientInfo,
+        device_code: &str,
+        dpop_jkt: Option<String>,
+    ) -> Result<Response<TokenEndpointReply>, Status> {
+        let mut tx = self.db.begin().await.map_err(Status::db)?;
+
+        let authorization = sqlx::query!(
+            "select * from auth_oauth_provider.device_authorizations
+             where device_code = $1 and client_id = $2
+             for update",
+            device_code,
+            client.id,
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(Status::db)?;
+
+        let Some(authorization) = authorization else {
+            tx.rollback().await.map_err(Status::db)?;
+            return Ok(Self::make_error_resp(
+                CoreErrorResponseType::InvalidGrant,
+                "invalid device code",
+            ));
+        };
+
+        let now = Utc::now();
+
+        // the device authorization is finished in all of these cases
+        let error = if authorization.expires_at < now {
+            Some("expired_token")
+        } else if authorization.denied {
+            Some("access_denied")
+        } else {
+            None
+        };
+        if authorization.flow_id.is_some() || error.is_some() {
+            sqlx::query!(
+                "delete from auth_oauth_provider.device_authorizations where id = $1",
+                authorization.id,
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(Status::db)?;
         }
+        if let Some(error) = error {
+            tx.commit().await.map_err(Status::db)?;
+            return Ok(Self::make_error_resp(
+                CoreErrorResponseType::Extension(error.into()),
+                "device authorization is no longer valid",
+            ));
+        }
+
+        let Some(flow_id) = authorization.flow_id else {
+            let poll_interval =
+                Duration::from_secs(authorization.poll_interval.unsigned_abs().into());
+            let too_fast = authorization
+                .last_polled_at
+                .is_some_and(|last_polled_at| last_polled_at + poll_interval > now);
+
+            // RFC 8628 section 3.5: the interval is increased by 5 seconds on `slow_down`
+            sqlx::query!(
+                "update auth_oauth_provider.device_authorizations
+                 set last_polled_at = now(), poll_interval = poll_interval + $1
+                 where id = $2",
+                if too_fast { 5 } else { 0 },
+                authorization.id,
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(Status::db)?;
+
+            tx.commit().await.map_err(Status::db)?;
+
+            return Ok(if too_fast {
+                Self::make_error_resp(
+                    CoreErrorResponseType::Extension("slow_down".into()),
+                    "polling too fast",
+                )
+            } else {
+                Self::make_error_resp(
+                    CoreErrorResponseType::Extension("authorization_pending".into()),
+                    "the user hasn't approved the authorization yet",
+                )
+            });
+        };
+
+        let mut flow = sqlx::query_as!(
+            Flow,
+            "select * from auth_oauth_provider.flows where id = $1",
+            flow_id,
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(Status::db)?;
+        flow.dpop_jkt = dpop_jkt;
+
+        let ret = self
+            .get_token_response_for_flow(flow, false, None, &mut tx)
+            .await?;
+
+        tx.commit().await.map_err(Status::db)?;
+
+        Ok(Response::new(ret))
     }
 
     async fn get_token_response_for_flow(
         &self,
         flow: Flow,
-        refresh_token: Option<String>,
+        is_refresh: bool,
         code: Option<String>,
         tx: &mut Transaction<'_, Postgres>,
     ) -> Result<TokenEndpointReply, Status> {
         let flow_id = self.id_encryptor.encrypt_id(IdType::OAuthFlow, flow.id);
 
-        let access_token = format!("BF/A/{}/{}", flow_id, nanoid!(32));
-        let refresh_token = if let Some(refresh_token) = refresh_token {
-            Some(refresh_token)
-        } else if flow.scopes.iter().any(|scope| scope == "offline_access") {
-            Some(format!("BF/R/{}/{}", flow_id, nanoid!(32)))
-        } else {
-            None
-        };
+        let client = sqlx::query_as!(
+            ClientInfo,
+            "select * from auth_oauth_provider.clients where id = $1",
+            flow.client_id,
+        )
+        .fetch_one(&mut **tx)
+        .await
+        .map_err(Status::db)?;
+
+        let user_id = flow.user_id.ok_or_else(|| {
+            Status::coded(Code::Internal, ErrorCode::Internal).with_details("flow has no user")
+        })?;
+
+        let (access_token, at_lifetime) = self.make_access_token(
+            &client,
+            flow.id,
+            Some(user_id),
+            &flow.scopes,
+            flow.dpop_jkt.as_deref(),
+        )?;
+        // refresh tokens are rotated on every refresh
+        let refresh_token =
+            if is_refresh || flow.scopes.iter().any(|scope| scope == "offline_access") {
+                Some(format!("BF/R/{}/{}", flow_id, nanoid!(32)))
+            } else {
+                None
+            };
 
-        let at_expiration = Utc::now() + OAUTH_ACCESS_TOKEN_LIFETIME;
+        let at_expiration = Utc::now() + at_lifetime;
 
         sqlx::query!(
             "update auth_oauth_provider.flows
              set access_token = $1,
                  refresh_token = $2,
                  access_token_expires_at = $3,
-                 authorized_at = now()
-             where id = $4",
+                 authorized_at = now(),
+                 dpop_jkt = $4
+             where id = $5",
             access_token,
             refresh_token,
             at_expiration,
+            flow.dpop_jkt,
             flow.id,
         )
         .execute(&mut **tx)
         .await
         .map_err(Status::db)?;
 
-        let standard_claims = self.get_standard_claims(flow.user_id, &flow.scopes).await;
+        let standard_claims = self
+            .get_standard_claims(&client, user_id, &flow.scopes)
+            .await;
 
-        let client_id = sqlx::query_scalar!(
-            "select client_id from auth_oauth_provider.clients
-             where id = $1",
-            flow.client_id,
-        )
-        .fetch_one(&mut **tx)
-        .await
-        .map_err(Status::db)?;
+        let alg = KeyAlgorithm::from_str(&client.id_token_signed_response_alg)
+            .map_err(|err| Status::coded(Code::Internal, ErrorCode::Internal).with_source(err))?;
+        let client_id = client.client_id;
+        let signing_key = self.get_active_signing_key(alg)?;
 
         let access_token = AccessToken::new(access_token);
         let id_token = CoreIdToken::new(
@@ -333,9 +711,9 @@ impl AuthOAuthProviderService {
             // nonce
             .set_nonce(flow.nonce.map(Nonce::new)),
             // signing key
-            &*self.rs_256_signing_key,
+            &*signing_key,
             // alg
-            CoreJwsSigningAlgorithm::RsaSsaPkcs1V15Sha256,
+            alg.jws_algorithm(),
             // at_hash
             Some(&access_token),
             // c_hash
@@ -345,12 +723,12 @@ impl AuthOAuthProviderService {
 
         let mut resp = CoreTokenResponse::new(
             access_token,
-            CoreTokenType::Bearer,
+            token_type(flow.dpop_jkt.as_deref()),
             CoreIdTokenFields::new(Some(id_token), EmptyExtraTokenFields {}),
         );
         resp.set_refresh_token(refresh_token.map(RefreshToken::new));
         resp.set_scopes(Some(flow.scopes.into_iter().map(Scope::new).collect()));
-        resp.set_expires_in(Some(&OAUTH_ACCESS_TOKEN_LIFETIME));
+        resp.set_expires_in(Some(&at_lifetime));
 
         Ok(TokenEndpointReply {
             status: 200,
@@ -363,6 +741,7 @@ impl AuthOAuthProviderService {
     /// Gets the ID token claims for a user
     pub async fn get_standard_claims(
         &self,
+        client: &ClientInfo,
         user_id: i64,
         scopes: &[String],
     ) -> StandardClaims<CoreGenderClaim> {
@@ -378,15 +757,15 @@ impl AuthOAuthProviderService {
             .await
             .or_with_log_default("getting profile for claims");
 
-        let user_id = self.id_encryptor.encrypt_id(IdType::User, user_id);
-        let mut standard_claims = StandardClaims::new(SubjectIdentifier::new(user_id));
+        let sub = self.get_subject(client, user_id);
+        let mut standard_claims = StandardClaims::new(SubjectIdentifier::new(sub));
 
         if let Some(user) = user {
             standard_claims = self.set_user_claims(standard_claims, user, scopes);
         }
         if let Some(profile) = profile {
             standard_claims = self
-                .set_profile_claims(standard_claims, profile, scopes)
+                .set_profile_claims(client, standard_claims, profile, scopes)
                 .await;
         }
 
@@ -410,6 +789,7 @@ impl AuthOAuthProviderService {
 
     pub async fn set_profile_claims(
         &self,
+        client: &ClientInfo,
         mut standard_claims: StandardClaims<CoreGenderClaim>,
         profile: ProfileDetails,
         scopes: &[String],
@@ -438,26 +818,36 @@ impl AuthOAuthProviderService {
             standard_claims = standard_claims
                 .set_name(Some(EndUserName::new(profile.display_name().into()).into()))
                 .set_preferred_username(Some(EndUserUsername::new(profile.username)))
-                .set_profile(Some(
+                .set_picture(avatar.and_then(|img| {
+                    img.full
+                        .or(img.thumbnail)
+                        .map(|img| EndUserPictureUrl::new(img.url).into())
+                }));
+
+            // the profile URL contains the public user ID, which pairwise clients must not learn
+            if client.subject_type() == SubjectType::Public {
+                standard_claims = standard_claims.set_profile(Some(
                     EndUserProfileUrl::new(format!(
                         "{}/user/{}",
                         self.frontend_root,
                         self.id_encryptor.encrypt_id(IdType::User, profile.user_id)
                     ))
                     .into(),
-                ))
-                .set_picture(avatar.and_then(|img| {
-                    img.full
-                        .or(img.thumbnail)
-                        .map(|img| EndUserPictureUrl::new(img.url).into())
-                }));
+                ));
+            }
         }
 
         standard_claims
     }
 
+    /// Make an OAuth error response for a back-channel endpoint
+    ///
+    /// # Panics
+    ///
+    /// Never, serializing a JSON object can't fail.
+    #[must_use]
     #[allow(clippy::needless_pass_by_value)] // for convenience (this is called many times)
-    fn make_error_resp(
+    pub fn make_error_resp(
         error: CoreErrorResponseType,
         description: &str,
     ) -> Response<TokenEndpointReply> {
@@ -475,3 +865,280 @@ impl AuthOAuthProviderService {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{
+        generate_es256, make_dpop_proof, public_client_details, test_client_details,
+    };
+    use bfx_core::service::database::Db;
+    use bfx_proto::auth::{BasicAuthorization, ClientDetails};
+    use std::collections::HashMap;
+
+    struct TestClient {
+        client: ClientInfo,
+        client_secret: String,
+    }
+
+    impl TestClient {
+        async fn new(service: &AuthOAuthProviderService) -> Self {
+            let (client, client_secret) = service.create_test_client(test_client_details()).await;
+
+            Self {
+                client,
+                client_secret,
+            }
+        }
+
+        /// Make a grant with a refresh token, like after an authorization code exchange
+        async fn issue_refresh_token(&self, service: &AuthOAuthProviderService) -> (i64, String) {
+            let grant_id: i64 = sqlx::query_scalar(
+                "insert into auth_oauth_provider.grants (client_id, user_id, scopes)
+                 values ($1, 42, '{openid, offline_access}')
+                 returning id",
+            )
+            .bind(self.client.id)
+            .fetch_one(&service.db)
+            .await
+            .unwrap();
+            let flow_id: i64 = sqlx::query_scalar(
+                "insert into auth_oauth_provider.flows
+                 (client_id, grant_id, user_id, scopes, authorized_at)
+                 values ($1, $2, 42, '{openid, offline_access}', now())
+                 returning id",
+            )
+            .bind(self.client.id)
+            .bind(grant_id)
+            .fetch_one(&service.db)
+            .await
+            .unwrap();
+
+            let refresh_token = format!(
+                "BF/R/{}/{}",
+                service.id_encryptor.encrypt_id(IdType::OAuthFlow, flow_id),
+                nanoid!(32)
+            );
+            sqlx::query("update auth_oauth_provider.flows set refresh_token = $1 where id = $2")
+                .bind(&refresh_token)
+                .bind(flow_id)
+                .execute(&service.db)
+                .await
+                .unwrap();
+
+            (grant_id, refresh_token)
+        }
+
+        async fn refresh(&self, service: &AuthOAuthProviderService, refresh_token: &str) -> Value {
+            self.request(
+                service,
+                &[
+                    ("grant_type", "refresh_token"),
+                    ("refresh_token", refresh_token),
+                ],
+            )
+            .await
+        }
+
+        async fn request(
+            &self,
+            service: &AuthOAuthProviderService,
+            params: &[(&str, &str)],
+        ) -> Value {
+            let query = params
+                .iter()
+                .map(|(key, value)| ((*key).to_string(), (*value).to_string()))
+                .collect();
+            let reply = service
+                .token_endpoint(Request::new(TokenEndpointRequest {
+                    query,
+                    authorization: Some(BasicAuthorization {
+                        username: self.client.client_id.clone(),
+                        password: self.client_secret.clone(),
+                    }),
+                    dpop: None,
+                }))
+                .await
+                .unwrap()
+                .into_inner();
+
+            serde_json::from_str(&reply.json).unwrap()
+        }
+    }
+
+    async fn grant_exists(service: &AuthOAuthProviderService, grant_id: i64) -> bool {
+        sqlx::query_scalar("select exists (select 1 from auth_oauth_provider.grants where id = $1)")
+            .bind(grant_id)
+            .fetch_one(&service.db)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn refresh_token_rotation(db: Db) {
+        let service = AuthOAuthProviderService::for_tests(db);
+        service.add_test_signing_key().await;
+        let client = TestClient::new(&service).await;
+        let (grant_id, first) = client.issue_refresh_token(&service).await;
+
+        let resp = client.refresh(&service, &first).await;
+        let second = resp["refresh_token"].as_str().unwrap();
+        assert_ne!(second, first);
+
+        let resp = client.refresh(&service, second).await;
+        let third = resp["refresh_token"].as_str().unwrap();
+        assert_ne!(third, second);
+        assert!(grant_exists(&service, grant_id).await);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn refresh_token_reuse_revokes_grant(db: Db) {
+        let service = AuthOAuthProviderService::for_tests(db);
+        service.add_test_signing_key().await;
+        let client = TestClient::new(&service).await;
+        let (grant_id, first) = client.issue_refresh_token(&service).await;
+        let (other_grant_id, _) = TestClient::new(&service)
+            .await
+            .issue_refresh_token(&service)
+            .await;
+
+        let resp = client.refresh(&service, &first).await;
+        let second = resp["refresh_token"].as_str().unwrap().to_string();
+
+        // e.g. an attacker using the stolen token after the client already refreshed
+        let resp = client.refresh(&service, &first).await;
+        assert_eq!(resp["error"], "invalid_grant");
+        assert!(!grant_exists(&service, grant_id).await);
+
+        // the current token of the grant is revoked too
+        let resp = client.refresh(&service, &second).await;
+        assert_eq!(resp["error"], "invalid_grant");
+
+        // other grants are not affected
+        assert!(grant_exists(&service, other_grant_id).await);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn refresh_token_of_other_client(db: Db) {
+        let service = AuthOAuthProviderService::for_tests(db);
+        service.add_test_signing_key().await;
+        let client = TestClient::new(&service).await;
+        let other_client = TestClient::new(&service).await;
+        let (grant_id, first) = client.issue_refresh_token(&service).await;
+
+        let resp = client.refresh(&service, &first).await;
+        assert!(resp["refresh_token"].is_string());
+
+        // only the client of the grant can detect reuse, others don't know the token
+        let resp = other_client.refresh(&service, &first).await;
+        assert_eq!(resp["error"], "invalid_grant");
+        assert!(grant_exists(&service, grant_id).await);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn unknown_refresh_token(db: Db) {
+        let service = AuthOAuthProviderService::for_tests(db);
+        service.add_test_signing_key().await;
+        let client = TestClient::new(&service).await;
+        let (grant_id, first) = client.issue_refresh_token(&service).await;
+
+        for refresh_token in [&format!("{first}x"), "BF/R/invalid/token", "garbage"] {
+            let resp = client.refresh(&service, refresh_token).await;
+            assert_eq!(resp["error"], "invalid_grant");
+        }
+        assert!(grant_exists(&service, grant_id).await);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn client_credentials_without_user_scopes(db: Db) {
+        let service = AuthOAuthProviderService::for_tests(db);
+        let (client, client_secret) = service
+            .create_test_client(ClientDetails {
+                allowed_scopes: [
+                    "openid",
+                    "email",
+                    "profile",
+                    "offline_access",
+                    "profile:write",
+                ]
+                .map(String::from)
+                .to_vec(),
+                ..test_client_details()
+            })
+            .await;
+        let client = TestClient {
+            client,
+            client_secret,
+        };
+
+        // the user scopes the client is allowed to request aren't granted by default
+        let resp = client
+            .request(&service, &[("grant_type", "client_credentials")])
+            .await;
+        assert!(resp["access_token"].is_string());
+        let access = service
+            .get_access_token(resp["access_token"].as_str().unwrap().into(), None)
+            .await
+            .unwrap();
+        assert_eq!(access.user_id, None);
+        assert!(access.scope.is_empty());
+
+        for scope in [
+            "openid",
+            "email",
+            "profile",
+            "offline_access",
+            "profile:write",
+        ] {
+            let resp = client
+                .request(
+                    &service,
+                    &[("grant_type", "client_credentials"), ("scope", scope)],
+                )
+                .await;
+            assert_eq!(resp["error"], "invalid_scope");
+        }
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn public_clients_need_dpop(db: Db) {
+        let service = AuthOAuthProviderService::for_tests(db);
+        service.add_test_signing_key().await;
+        let (client, _) = service.create_test_client(public_client_details()).await;
+        let client = TestClient {
+            client,
+            client_secret: String::new(),
+        };
+        let (_, refresh_token) = client.issue_refresh_token(&service).await;
+        let (key, jwk) = generate_es256("key-1");
+
+        let request = |grant_type: &str, dpop: Option<String>| {
+            Request::new(TokenEndpointRequest {
+                query: HashMap::from([
+                    ("grant_type".into(), grant_type.into()),
+                    ("client_id".into(), client.client.client_id.clone()),
+                    ("refresh_token".into(), refresh_token.clone()),
+                ]),
+                authorization: None,
+                dpop,
+            })
+        };
+        let send = async |request| {
+            let reply = service.token_endpoint(request).await.unwrap().into_inner();
+            serde_json::from_str::<Value>(&reply.json).unwrap()
+        };
+
+        let resp = send(request("refresh_token", None)).await;
+        assert_eq!(resp["error"], "invalid_dpop_proof");
+
+        let resp = send(request(
+            "client_credentials",
+            Some(make_dpop_proof(&key, &jwk)),
+        ))
+        .await;
+        assert_eq!(resp["error"], "unauthorized_client");
+
+        let resp = send(request("refresh_token", Some(make_dpop_proof(&key, &jwk)))).await;
+        assert_eq!(resp["token_type"], DPOP_TOKEN_TYPE);
+    }
+}
@@ -0,0 +1,228 @@
+use crate::AuthOAuthProviderService;
+use crate::models::client_info::ClientInfo;
+use crate::signing_keys::KeyAlgorithm;
+use crate::subject::{SubjectType, sector_identifier_for};
+use bfx_core::status::{ErrorCode, StatusExt};
+use bfx_proto::auth::{UpdateClientReply, UpdateClientRequest};
+use tonic::{Code, Request, Response, Status};
+
+impl AuthOAuthProviderService {
+    /// Update the details of an OAuth client
+    ///
+    /// Changing the redirect URIs of an official client makes it non-official again,
+    /// unless `official` is set in the same request.
+    ///
+    /// The sector of pairwise subjects follows the redirect URIs. A pairwise client can't
+    /// move its redirect URIs to another sector, its users' subjects would change with it.
+    /// Otherwise, a client could register URIs on the host of another client,
+    /// and then move them to its own host, keeping the same subjects as the other client.
+    ///
+    /// # Errors
+    ///
+    /// - If the client does not exist or is not owned by `owner_id`
+    /// - If the client details are invalid (see [`AuthOAuthProviderService::check_client_details`])
+    /// - If the client is pairwise and the redirect URIs would change its sector
+    /// - Miscellaneous internal errors
+    pub async fn update_client(
+        &self,
+        request: Request<UpdateClientRequest>,
+    ) -> Result<Response<UpdateClientReply>, Status> {
+        let request = request.into_inner();
+
+        let details = request
+            .details
+            .ok_or_else(|| Status::coded(Code::InvalidArgument, ErrorCode::Internal))?;
+        Self::check_client_details(&details)?;
+
+        let mut tx = self.db.begin().await.map_err(Status::db)?;
+
+        let current = sqlx::query!(
+            "select subject_type, sector_identifier from auth_oauth_provider.clients
+             where id = $1 and ($2::bigint is null or owner_id = $2)
+             for update",
+            request.id,
+            request.owner_id,
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(Status::db)?
+        .ok_or_else(|| Status::coded(Code::NotFound, ErrorCode::ClientNotFound))?;
+
+        let sector_identifier = sector_identifier_for(&details.redirect_uris);
+        if current.subject_type == SubjectType::Pairwise.to_string()
+            && current.sector_identifier != sector_identifier
+        {
+            return Err(
+                Status::coded(Code::InvalidArgument, ErrorCode::InvalidRedirectUri)
+                    .with_details("redirect URIs of a pairwise client can't change its sector"),
+            );
+        }
+
+        let client = sqlx::query_as!(
+            ClientInfo,
+            "update auth_oauth_provider.clients
+             set redirect_uris = $3, display_name = $4, privacy_url = $5, tos_url = $6,
+                 allowed_scopes = $7, enforce_code_challenge = $8,
+                 official = case
+                     when $9::bool is not null then $9
+                     when redirect_uris is distinct from $3
+                          or post_logout_redirect_uris is distinct from $13 then false
+                     else official
+                 end,
+                 jwks = $10,
+                 id_token_signed_response_alg = $11, jwt_access_tokens = $12,
+                 post_logout_redirect_uris = $13, backchannel_logout_uri = $14,
+                 subject_type = coalesce($15, subject_type),
+                 require_pushed_authorization_requests = $16,
+                 resource_server = coalesce($17, resource_server),
+                 token_endpoint_auth_method = coalesce($18, token_endpoint_auth_method),
+                 sector_identifier = $19
+             where id = $1 and ($2::bigint is null or owner_id = $2)
+             returning *",
+            request.id,
+            request.owner_id,
+            &details.redirect_uris,
+            details.display_name,
+            details.privacy_url,
+            details.tos_url,
+            &details.allowed_scopes,
+            details.enforce_code_challenge,
+            request.official,
+            details.jwks,
+            details
+                .id_token_signed_response_alg
+                .unwrap_or_else(|| KeyAlgorithm::Rs256.to_string()),
+            details.jwt_access_tokens,
+            &details.post_logout_redirect_uris,
+            details.backchannel_logout_uri,
+            details.subject_type,
+            details.require_pushed_authorization_requests,
+            request.resource_server,
+            details.token_endpoint_auth_method,
+            sector_identifier,
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(Status::db)?;
+
+        tx.commit().await.map_err(Status::db)?;
+
+        Ok(Response::new(UpdateClientReply {
+            client: Some(client.into()),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::test_client_details;
+    use bfx_core::service::database::Db;
+    use bfx_proto::auth::ClientDetails;
+
+    fn client_details(redirect_uri: &str, subject_type: SubjectType) -> ClientDetails {
+        ClientDetails {
+            redirect_uris: vec![redirect_uri.into()],
+            subject_type: Some(subject_type.to_string()),
+            ..test_client_details()
+        }
+    }
+
+    async fn update(
+        service: &AuthOAuthProviderService,
+        client: &ClientInfo,
+        details: ClientDetails,
+    ) -> Result<ClientInfo, Status> {
+        service
+            .update_client(Request::new(UpdateClientRequest {
+                id: client.id,
+                owner_id: Some(client.owner_id),
+                details: Some(details),
+                official: None,
+                resource_server: None,
+            }))
+            .await?;
+
+        Ok(service.get_client_by_id(client.id).await.unwrap())
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn pairwise_clients_keep_their_sector(db: Db) {
+        let service = AuthOAuthProviderService::for_tests(db);
+        let (victim, _) = service
+            .create_test_client(client_details(
+                "https://victim.example.com/callback",
+                SubjectType::Pairwise,
+            ))
+            .await;
+        let (client, _) = service
+            .create_test_client(client_details(
+                "https://victim.example.com/other",
+                SubjectType::Pairwise,
+            ))
+            .await;
+        let victim_sub = service.get_subject(&victim, 42);
+        assert_eq!(service.get_subject(&client, 42), victim_sub);
+
+        // moving to another host would keep the subjects of the victim's sector
+        let result = update(
+            &service,
+            &client,
+            client_details(
+                "https://attacker.example.com/callback",
+                SubjectType::Pairwise,
+            ),
+        )
+        .await;
+        assert_eq!(result.unwrap_err().code(), Code::InvalidArgument);
+        let client = service.get_client_by_id(client.id).await.unwrap();
+        assert_eq!(client.redirect_uris, ["https://victim.example.com/other"]);
+
+        // other paths on the same host are fine
+        let client = update(
+            &service,
+            &client,
+            client_details("https://victim.example.com/new", SubjectType::Pairwise),
+        )
+        .await
+        .unwrap();
+        assert_eq!(service.get_subject(&client, 42), victim_sub);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn sector_follows_redirect_uris(db: Db) {
+        let service = AuthOAuthProviderService::for_tests(db);
+        let (victim, _) = service
+            .create_test_client(client_details(
+                "https://victim.example.com/callback",
+                SubjectType::Pairwise,
+            ))
+            .await;
+        let (client, _) = service
+            .create_test_client(client_details(
+                "https://victim.example.com/other",
+                SubjectType::Public,
+            ))
+            .await;
+
+        // switching to pairwise together with the move doesn't keep the old sector either
+        let client = update(
+            &service,
+            &client,
+            client_details(
+                "https://attacker.example.com/callback",
+                SubjectType::Pairwise,
+            ),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            client.sector_identifier.as_deref(),
+            Some("attacker.example.com")
+        );
+        assert_ne!(
+            service.get_subject(&client, 42),
+            service.get_subject(&victim, 42)
+        );
+    }
+}
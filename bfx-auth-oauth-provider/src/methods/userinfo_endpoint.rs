@@ -11,6 +11,7 @@ impl AuthOAuthProviderService {
     /// # Errors
     ///
     /// - If [`AuthOAuthProviderService::get_access_token`] fails
+    /// - If the access token has no user (`client_credentials` grant)
     /// - Miscellaneous internal errors
     pub async fn userinfo_endpoint(
         &self,
@@ -18,9 +19,22 @@ impl AuthOAuthProviderService {
     ) -> Result<Response<UserinfoEndpointReply>, Status> {
         let request = request.into_inner();
 
-        let token = self.get_access_token(request.access_token).await?;
+        let token = self
+            .get_access_token(request.access_token, request.dpop)
+            .await?;
 
-        let standard_claims = self.get_standard_claims(token.user_id, &token.scope).await;
+        // client-only tokens have no user to return info about
+        let Some(user_id) = token.user_id else {
+            return Err(
+                Status::coded(Code::Unauthenticated, ErrorCode::InvalidToken)
+                    .with_details("access token has no user"),
+            );
+        };
+
+        let client = self.get_client_by_id(token.client_id).await?;
+        let standard_claims = self
+            .get_standard_claims(&client, user_id, &token.scope)
+            .await;
 
         let userinfo_response = CoreUserInfoClaims::new(standard_claims, EmptyAdditionalClaims {})
             .set_issuer(Some(self.issuer.clone()));
@@ -1,11 +1,13 @@
 use sqlx::types::chrono::{DateTime, Utc};
 
+// mirrors the table, the flags are independent
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Debug, Clone)]
 pub struct ClientInfo {
     pub id: i64,
     pub owner_id: i64,
     pub client_id: String,
-    pub client_secret: String,
+    pub client_secret_hash: Vec<u8>,
     pub redirect_uris: Vec<String>,
     pub display_name: String,
     pub privacy_url: Option<String>,
@@ -14,6 +16,16 @@ pub struct ClientInfo {
     pub allowed_scopes: Vec<String>,
     pub enforce_code_challenge: bool,
     pub created_at: DateTime<Utc>,
+    pub jwks: Option<String>,
+    pub id_token_signed_response_alg: String,
+    pub jwt_access_tokens: bool,
+    pub post_logout_redirect_uris: Vec<String>,
+    pub backchannel_logout_uri: Option<String>,
+    pub subject_type: String,
+    pub sector_identifier: Option<String>,
+    pub require_pushed_authorization_requests: bool,
+    pub resource_server: bool,
+    pub token_endpoint_auth_method: String,
 }
 
 impl From<ClientInfo> for bfx_proto::auth::RpInfo {
@@ -27,3 +39,34 @@ impl From<ClientInfo> for bfx_proto::auth::RpInfo {
         }
     }
 }
+
+impl From<ClientInfo> for bfx_proto::auth::OAuthClient {
+    fn from(value: ClientInfo) -> Self {
+        let subject_type = value.subject_type().to_string();
+        let token_endpoint_auth_method = value.token_endpoint_auth_method().to_string();
+
+        Self {
+            id: value.id,
+            owner_id: value.owner_id,
+            client_id: value.client_id,
+            redirect_uris: value.redirect_uris,
+            display_name: value.display_name,
+            privacy_url: value.privacy_url,
+            tos_url: value.tos_url,
+            official: value.official,
+            allowed_scopes: value.allowed_scopes,
+            enforce_code_challenge: value.enforce_code_challenge,
+            created_at: Some(value.created_at.into()),
+            jwks: value.jwks,
+            id_token_signed_response_alg: value.id_token_signed_response_alg,
+            jwt_access_tokens: value.jwt_access_tokens,
+            post_logout_redirect_uris: value.post_logout_redirect_uris,
+            backchannel_logout_uri: value.backchannel_logout_uri,
+            subject_type,
+            sector_identifier: value.sector_identifier,
+            require_pushed_authorization_requests: value.require_pushed_authorization_requests,
+            resource_server: value.resource_server,
+            token_endpoint_auth_method,
+        }
+    }
+}
@@ -4,8 +4,8 @@ pub struct Flow {
     pub id: i64,
     pub client_id: i64,
     pub grant_id: Option<i64>,
-    pub user_id: i64,
-    pub redirect_uri: String,
+    pub user_id: Option<i64>,
+    pub redirect_uri: Option<String>,
     pub scopes: Vec<String>,
     pub state: Option<String>,
     pub nonce: Option<String>,
@@ -18,4 +18,5 @@ pub struct Flow {
     pub authorized_at: Option<DateTime<Utc>>,
     pub access_token_expires_at: Option<DateTime<Utc>>,
     pub refresh_token_expires_at: Option<DateTime<Utc>>,
+    pub dpop_jkt: Option<String>,
 }
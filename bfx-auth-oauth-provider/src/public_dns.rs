@@ -0,0 +1,125 @@
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use tokio::net::lookup_host;
+
+/// DNS resolver for requests to URLs registered by clients (e.g. back-channel logout)
+///
+/// Anyone can register a client, so these requests must not reach the loopback interface
+/// or internal hosts. Hosts that resolve to any non-public address are refused,
+/// this also catches public names that point to internal addresses.
+pub struct PublicDnsResolver;
+
+impl Resolve for PublicDnsResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addrs = lookup_host((name.as_str(), 0)).await?.collect::<Vec<_>>();
+
+            if let Some(addr) = addrs.iter().find(|addr| !is_public_ip(addr.ip())) {
+                return Err(format!(
+                    "`{}` resolves to the non-public address {}",
+                    name.as_str(),
+                    addr.ip()
+                )
+                .into());
+            }
+
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Check if an IP address is reachable on the public internet
+///
+/// Like the unstable `IpAddr::is_global`, IPv4-mapped IPv6 addresses are checked as IPv4.
+#[must_use]
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => ip
+            .to_ipv4_mapped()
+            .map_or_else(|| is_public_ipv6(ip), is_public_ipv4),
+    }
+}
+
+const fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        // "this network" (0.0.0.0/8)
+        || a == 0
+        // shared address space of carrier-grade NATs (100.64.0.0/10)
+        || (a == 100 && (b & 0xc0) == 64)
+        // IETF protocol assignments (192.0.0.0/24)
+        || (a == 192 && b == 0 && c == 0)
+        // benchmarking (198.18.0.0/15)
+        || (a == 198 && (b & 0xfe) == 18)
+        // reserved (240.0.0.0/4)
+        || a >= 240)
+}
+
+const fn is_public_ipv6(ip: Ipv6Addr) -> bool {
+    let [a, b, ..] = ip.segments();
+
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        || ip.is_unique_local()
+        || ip.is_unicast_link_local()
+        // documentation (2001:db8::/32)
+        || (a == 0x2001 && b == 0xdb8)
+        // NAT64, which can reach private IPv4 addresses (64:ff9b::/96 and 64:ff9b:1::/48)
+        || (a == 0x64 && (b == 0xff9b || b == 0xff9b + 1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn public_ips() {
+        for ip in [
+            "93.184.215.14",
+            "1.1.1.1",
+            "2606:2800:21f:cb07:6820:80da:af6b:8b2c",
+        ] {
+            assert!(is_public_ip(ip.parse().unwrap()), "{ip}");
+        }
+
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "255.255.255.255",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+            "::ffff:10.0.0.1",
+            "64:ff9b::a00:1",
+        ] {
+            assert!(!is_public_ip(ip.parse().unwrap()), "{ip}");
+        }
+    }
+
+    #[test]
+    fn refuse_loopback_names() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+
+        let result = runtime.block_on(PublicDnsResolver.resolve("localhost".parse().unwrap()));
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,694 @@
+use crate::AuthOAuthProviderService;
+use aes_gcm_siv::aead::{Aead, Payload};
+use aes_gcm_siv::{Aes256GcmSiv, KeyInit, Nonce};
+use base64::Engine;
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use bfx_core::service::environment::require_env;
+use bfx_core::status::{ErrorCode, StatusExt};
+use hkdf::Hkdf;
+use nanoid::nanoid;
+use openidconnect::core::{
+    CoreEdDsaPrivateSigningKey, CoreJsonCurveType, CoreJsonWebKey, CoreJwsSigningAlgorithm,
+    CoreRsaPrivateSigningKey,
+};
+use openidconnect::{JsonWebKeyId, PrivateSigningKey, SigningError};
+use p256::ecdsa::signature::Signer;
+use p256::pkcs8::{DecodePrivateKey, EncodePrivateKey, LineEnding};
+use rand_core::{OsRng, RngCore};
+use rsa::pkcs1::EncodeRsaPrivateKey;
+use serde::Serialize;
+use serde_json::json;
+use sha2::Sha256;
+use sqlx::types::chrono::{DateTime, Utc};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use strum::{Display, EnumString};
+use tokio::time::sleep;
+use tonic::{Code, Status};
+use tracing::{info, warn};
+
+// how long a key is used for signing before it's replaced by the next one
+const KEY_ROTATION_INTERVAL: Duration = Duration::from_hours(30 * 24);
+// the next key is published for at least this long before it's used,
+// so that relying parties with a cached JWKS can verify its signatures
+const NEXT_KEY_PUBLISH_PERIOD: Duration = Duration::from_hours(24);
+// must be longer than the lifetime of anything signed with the key
+const RETIRED_KEY_RETENTION: Duration = Duration::from_hours(7 * 24);
+const KEY_ROTATION_CHECK_INTERVAL: Duration = Duration::from_mins(10);
+
+const RSA_KEY_BITS: usize = 2048;
+
+const NONCE_LEN: usize = 12;
+// keys that were stored before they were encrypted
+const PLAINTEXT_KEY_PREFIX: &str = "-----BEGIN";
+
+/// Algorithms that ID tokens can be signed with
+///
+/// There is a separate set of keys for each algorithm.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Display, EnumString)]
+pub enum KeyAlgorithm {
+    #[strum(serialize = "RS256")]
+    Rs256,
+    #[strum(serialize = "ES256")]
+    Es256,
+    #[strum(serialize = "EdDSA")]
+    EdDsa,
+}
+
+impl KeyAlgorithm {
+    pub const ALL: [Self; 3] = [Self::Rs256, Self::Es256, Self::EdDsa];
+
+    #[must_use]
+    pub const fn jws_algorithm(self) -> CoreJwsSigningAlgorithm {
+        match self {
+            Self::Rs256 => CoreJwsSigningAlgorithm::RsaSsaPkcs1V15Sha256,
+            Self::Es256 => CoreJwsSigningAlgorithm::EcdsaP256Sha256,
+            Self::EdDsa => CoreJwsSigningAlgorithm::EdDsa,
+        }
+    }
+}
+
+/// Lifecycle of a signing key
+///
+/// All keys are published in the JWKS, but only the active one is used for signing.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Display, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum KeyState {
+    Next,
+    Active,
+    Retired,
+}
+
+enum SigningKeyPair {
+    Rsa(CoreRsaPrivateSigningKey),
+    Ecdsa(p256::ecdsa::SigningKey),
+    EdDsa(CoreEdDsaPrivateSigningKey),
+}
+
+/// A private key used to sign ID tokens and JWT access tokens
+pub struct SigningKey {
+    pub kid: String,
+    pub alg: KeyAlgorithm,
+    pub state: KeyState,
+    key_pair: SigningKeyPair,
+}
+
+impl SigningKey {
+    fn from_pem(
+        kid: String,
+        alg: KeyAlgorithm,
+        state: KeyState,
+        pem: &str,
+    ) -> Result<Self, String> {
+        let key_id = Some(JsonWebKeyId::new(kid.clone()));
+
+        let key_pair = match alg {
+            KeyAlgorithm::Rs256 => {
+                SigningKeyPair::Rsa(CoreRsaPrivateSigningKey::from_pem(pem, key_id)?)
+            }
+            KeyAlgorithm::Es256 => SigningKeyPair::Ecdsa(
+                p256::ecdsa::SigningKey::from_pkcs8_pem(pem).map_err(|err| err.to_string())?,
+            ),
+            KeyAlgorithm::EdDsa => {
+                SigningKeyPair::EdDsa(CoreEdDsaPrivateSigningKey::from_ed25519_pem(pem, key_id)?)
+            }
+        };
+
+        Ok(Self {
+            kid,
+            alg,
+            state,
+            key_pair,
+        })
+    }
+
+    /// Generate a new private key in PEM format
+    fn generate_pem(alg: KeyAlgorithm) -> Result<String, String> {
+        let pem = match alg {
+            KeyAlgorithm::Rs256 => rsa::RsaPrivateKey::new(&mut OsRng, RSA_KEY_BITS)
+                .map_err(|err| err.to_string())?
+                .to_pkcs1_pem(LineEnding::LF)
+                .map_err(|err| err.to_string())?,
+            KeyAlgorithm::Es256 => p256::ecdsa::SigningKey::random(&mut OsRng)
+                .to_pkcs8_pem(LineEnding::LF)
+                .map_err(|err| err.to_string())?,
+            KeyAlgorithm::EdDsa => ed25519_dalek::SigningKey::generate(&mut OsRng)
+                .to_pkcs8_pem(LineEnding::LF)
+                .map_err(|err| err.to_string())?,
+        };
+
+        Ok(pem.to_string())
+    }
+
+    /// Sign arbitrary claims as a JWT with the `typ` header set to `typ`
+    ///
+    /// # Errors
+    ///
+    /// - If the claims can't be serialized or signing fails
+    pub fn sign_jwt(&self, typ: &str, claims: &impl Serialize) -> Result<String, Status> {
+        let internal =
+            |err: String| Status::coded(Code::Internal, ErrorCode::Internal).with_details(&err);
+
+        let header = json!({
+            "alg": self.alg.to_string(),
+            "typ": typ,
+            "kid": self.kid,
+        });
+        let header = serde_json::to_vec(&header).map_err(|err| internal(err.to_string()))?;
+        let claims = serde_json::to_vec(claims).map_err(|err| internal(err.to_string()))?;
+
+        let message = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(header),
+            URL_SAFE_NO_PAD.encode(claims),
+        );
+        let signature = self
+            .sign(&self.alg.jws_algorithm(), message.as_bytes())
+            .map_err(|err| internal(err.to_string()))?;
+
+        Ok(format!("{message}.{}", URL_SAFE_NO_PAD.encode(signature)))
+    }
+}
+
+impl PrivateSigningKey for SigningKey {
+    type VerificationKey = CoreJsonWebKey;
+
+    fn sign(
+        &self,
+        signature_alg: &CoreJwsSigningAlgorithm,
+        message: &[u8],
+    ) -> Result<Vec<u8>, SigningError> {
+        match &self.key_pair {
+            SigningKeyPair::Rsa(key) => key.sign(signature_alg, message),
+            SigningKeyPair::EdDsa(key) => key.sign(signature_alg, message),
+            // `openidconnect` can't sign with ECDSA keys, so do it ourselves
+            SigningKeyPair::Ecdsa(key) => {
+                if *signature_alg != CoreJwsSigningAlgorithm::EcdsaP256Sha256 {
+                    return Err(SigningError::UnsupportedAlg(format!("{signature_alg:?}")));
+                }
+                let signature: p256::ecdsa::Signature = key.sign(message);
+                Ok(signature.to_bytes().to_vec())
+            }
+        }
+    }
+
+    fn as_verification_key(&self) -> CoreJsonWebKey {
+        match &self.key_pair {
+            SigningKeyPair::Rsa(key) => key.as_verification_key(),
+            SigningKeyPair::EdDsa(key) => key.as_verification_key(),
+            SigningKeyPair::Ecdsa(key) => {
+                let point = key.verifying_key().to_encoded_point(false);
+                CoreJsonWebKey::new_ec(
+                    point.x().map(|x| x.to_vec()).unwrap_or_default(),
+                    point.y().map(|y| y.to_vec()).unwrap_or_default(),
+                    CoreJsonCurveType::P256,
+                    Some(JsonWebKeyId::new(self.kid.clone())),
+                )
+            }
+        }
+    }
+}
+
+/// Encrypts private keys before they are stored in the database
+///
+/// A database leak alone isn't enough to sign tokens then.
+#[derive(Clone)]
+pub struct SigningKeyEncryptor {
+    cipher: Aes256GcmSiv,
+}
+
+impl SigningKeyEncryptor {
+    /// Construct a [`SigningKeyEncryptor`] from a secret key
+    ///
+    /// # Errors
+    ///
+    /// - Miscellaneous internal errors
+    pub fn from_key(key: &str) -> anyhow::Result<Self> {
+        let hk = Hkdf::<Sha256>::new(None, key.as_bytes());
+        let mut key = [0u8; 32];
+        hk.expand(b"openid-signing-key-encryption-key", &mut key)
+            .map_err(|err| anyhow::anyhow!("invalid OPENID_SIGNING_KEY_ENCRYPTION_KEY: {err:?}"))?;
+
+        Ok(Self {
+            cipher: Aes256GcmSiv::new(&key.into()),
+        })
+    }
+
+    /// Encrypt a PEM private key
+    ///
+    /// The key is bound to its `kid`, so a stored key can't be swapped for another one.
+    fn encrypt(&self, kid: &str, pem: &str) -> Result<String, String> {
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce);
+
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                &Nonce::from(nonce),
+                Payload {
+                    msg: pem.as_bytes(),
+                    aad: kid.as_bytes(),
+                },
+            )
+            .map_err(|err| err.to_string())?;
+
+        // result <== nonce + ciphertext
+        Ok(STANDARD.encode([nonce.as_slice(), &ciphertext].concat()))
+    }
+
+    /// Decrypt a key encrypted with [`SigningKeyEncryptor::encrypt`]
+    fn decrypt(&self, kid: &str, encrypted: &str) -> Result<String, String> {
+        let data = STANDARD.decode(encrypted).map_err(|err| err.to_string())?;
+        if data.len() < NONCE_LEN {
+            return Err("invalid data length".into());
+        }
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+
+        let pem = self
+            .cipher
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: kid.as_bytes(),
+                },
+            )
+            .map_err(|_| "decryption failed".to_string())?;
+
+        String::from_utf8(pem).map_err(|err| err.to_string())
+    }
+}
+
+/// Construct a [`SigningKeyEncryptor`] from the `OPENID_SIGNING_KEY_ENCRYPTION_KEY` environment variable
+///
+/// # Errors
+///
+/// - If the environment variable is not set
+/// - Miscellaneous internal errors
+pub fn require_signing_key_encryptor() -> anyhow::Result<SigningKeyEncryptor> {
+    SigningKeyEncryptor::from_key(&require_env("OPENID_SIGNING_KEY_ENCRYPTION_KEY")?)
+}
+
+impl AuthOAuthProviderService {
+    /// Get the key that is currently used for signing with `alg`
+    ///
+    /// # Errors
+    ///
+    /// - If there is no active key for the algorithm (should never happen after startup)
+    pub fn get_active_signing_key(&self, alg: KeyAlgorithm) -> Result<Arc<SigningKey>, Status> {
+        self.signing_keys
+            .load()
+            .iter()
+            .find(|key| key.alg == alg && key.state == KeyState::Active)
+            .cloned()
+            .ok_or_else(|| {
+                Status::coded(Code::Internal, ErrorCode::Internal)
+                    .with_details(&format!("no active {alg} signing key"))
+            })
+    }
+
+    /// Get a published signing key by its `kid`
+    #[must_use]
+    pub fn get_signing_key(&self, kid: &str) -> Option<Arc<SigningKey>> {
+        self.signing_keys
+            .load()
+            .iter()
+            .find(|key| key.kid == kid)
+            .cloned()
+    }
+
+    /// Get the public keys of all published signing keys
+    #[must_use]
+    pub fn get_verification_keys(&self) -> Vec<CoreJsonWebKey> {
+        self.signing_keys
+            .load()
+            .iter()
+            .map(|key| key.as_verification_key())
+            .collect()
+    }
+
+    /// Import the key from `OPENID_SIGNING_KEY_PATH` as the active RS256 key
+    ///
+    /// This is only done if there are no RS256 keys yet,
+    /// so that the key used before key rotation existed stays valid.
+    ///
+    /// # Errors
+    ///
+    /// - If the key can't be read or parsed
+    /// - Miscellaneous database errors
+    pub async fn import_signing_key_from_env(&self) -> anyhow::Result<()> {
+        let Ok(path) = std::env::var("OPENID_SIGNING_KEY_PATH") else {
+            return Ok(());
+        };
+        let kid = require_env("OPENID_SIGNING_KEY_ID")?;
+        let pem = std::fs::read_to_string(&path)
+            .map_err(|err| anyhow::anyhow!("failed to read `{path}`: {err}"))?;
+
+        SigningKey::from_pem(kid.clone(), KeyAlgorithm::Rs256, KeyState::Active, &pem)
+            .map_err(|err| anyhow::anyhow!("failed to parse openid signing key: {err}"))?;
+        let private_key = self
+            .signing_key_encryptor
+            .encrypt(&kid, &pem)
+            .map_err(|err| anyhow::anyhow!("failed to encrypt openid signing key: {err}"))?;
+
+        let result = sqlx::query!(
+            "insert into auth_oauth_provider.signing_keys
+             (kid, alg, state, private_key, activated_at)
+             select $1, $2, $3, $4, now()
+             where not exists (select 1 from auth_oauth_provider.signing_keys where alg = $2)
+             on conflict do nothing",
+            kid,
+            KeyAlgorithm::Rs256.to_string(),
+            KeyState::Active.to_string(),
+            private_key,
+        )
+        .execute(&self.db)
+        .await?;
+
+        if result.rows_affected() > 0 {
+            info!(kid, "imported signing key from OPENID_SIGNING_KEY_PATH");
+        }
+
+        Ok(())
+    }
+
+    /// Reload the signing keys from the database
+    ///
+    /// # Errors
+    ///
+    /// - Miscellaneous database errors
+    pub async fn load_signing_keys(&self) -> Result<(), Status> {
+        let rows = sqlx::query!(
+            "select kid, alg, state, private_key from auth_oauth_provider.signing_keys
+             order by created_at",
+        )
+        .fetch_all(&self.db)
+        .await
+        .map_err(Status::db)?;
+
+        let mut keys = vec![];
+        for row in rows {
+            let (Ok(alg), Ok(state)) = (
+                KeyAlgorithm::from_str(&row.alg),
+                KeyState::from_str(&row.state),
+            ) else {
+                warn!(row.kid, row.alg, row.state, "unknown signing key type");
+                continue;
+            };
+
+            let key = self
+                .signing_key_encryptor
+                .decrypt(&row.kid, &row.private_key)
+                .and_then(|pem| SigningKey::from_pem(row.kid.clone(), alg, state, &pem));
+            match key {
+                Ok(key) => keys.push(Arc::new(key)),
+                Err(err) => warn!(row.kid, err, "failed to load signing key"),
+            }
+        }
+
+        self.signing_keys.store(Arc::new(keys));
+
+        Ok(())
+    }
+
+    /// Create, promote, retire and delete signing keys as needed, then reload them
+    ///
+    /// Safe to run concurrently from multiple replicas.
+    ///
+    /// # Errors
+    ///
+    /// - If a key can't be generated
+    /// - Miscellaneous database errors
+    pub async fn rotate_signing_keys(&self) -> Result<(), Status> {
+        let mut tx = self.db.begin().await.map_err(Status::db)?;
+
+        sqlx::query!("lock table auth_oauth_provider.signing_keys in exclusive mode")
+            .execute(&mut *tx)
+            .await
+            .map_err(Status::db)?;
+
+        self.encrypt_plaintext_signing_keys(&mut tx).await?;
+
+        let now = Utc::now();
+
+        sqlx::query!(
+            "delete from auth_oauth_provider.signing_keys
+             where state = $1 and retired_at < $2",
+            KeyState::Retired.to_string(),
+            now - RETIRED_KEY_RETENTION,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(Status::db)?;
+
+        for alg in KeyAlgorithm::ALL {
+            let keys = sqlx::query!(
+                "select id, kid, state, created_at, activated_at
+                 from auth_oauth_provider.signing_keys
+                 where alg = $1 and state <> $2",
+                alg.to_string(),
+                KeyState::Retired.to_string(),
+            )
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(Status::db)?;
+
+            let active = keys
+                .iter()
+                .find(|key| key.state == KeyState::Active.to_string());
+            let next = keys
+                .iter()
+                .find(|key| key.state == KeyState::Next.to_string());
+
+            let next_is_published =
+                next.is_some_and(|next| next.created_at + NEXT_KEY_PUBLISH_PERIOD < now);
+            let active_is_due = active.is_none_or(|active| {
+                active
+                    .activated_at
+                    .is_none_or(|activated_at: DateTime<Utc>| {
+                        activated_at + KEY_ROTATION_INTERVAL < now
+                    })
+            });
+
+            let mut has_next = next.is_some();
+
+            // without an active key, the next key is used right away (or a new one is made)
+            if active.is_none() || (active_is_due && next_is_published) {
+                if let Some(active) = active {
+                    sqlx::query!(
+                        "update auth_oauth_provider.signing_keys
+                         set state = $1, retired_at = now()
+                         where id = $2",
+                        KeyState::Retired.to_string(),
+                        active.id,
+                    )
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(Status::db)?;
+                }
+
+                if let Some(next) = next {
+                    sqlx::query!(
+                        "update auth_oauth_provider.signing_keys
+                         set state = $1, activated_at = now()
+                         where id = $2",
+                        KeyState::Active.to_string(),
+                        next.id,
+                    )
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(Status::db)?;
+
+                    info!(kid = next.kid, %alg, "activated signing key");
+                    has_next = false;
+                } else {
+                    let kid = self
+                        .insert_signing_key(&mut tx, alg, KeyState::Active)
+                        .await?;
+                    info!(kid, %alg, "generated active signing key");
+                }
+            }
+
+            if !has_next {
+                let kid = self
+                    .insert_signing_key(&mut tx, alg, KeyState::Next)
+                    .await?;
+                info!(kid, %alg, "generated next signing key");
+            }
+        }
+
+        tx.commit().await.map_err(Status::db)?;
+
+        self.load_signing_keys().await
+    }
+
+    /// Generate a new signing key and insert it
+    ///
+    /// Returns the `kid` of the new key.
+    ///
+    /// # Errors
+    ///
+    /// - If the key can't be generated
+    /// - Miscellaneous database errors
+    pub async fn insert_signing_key(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        alg: KeyAlgorithm,
+        state: KeyState,
+    ) -> Result<String, Status> {
+        let internal = |err: String| {
+            Status::coded(Code::Internal, ErrorCode::Internal)
+                .with_details(&format!("failed to generate {alg} key: {err}"))
+        };
+
+        let pem = SigningKey::generate_pem(alg).map_err(internal)?;
+        let kid = nanoid!(16);
+        let private_key = self
+            .signing_key_encryptor
+            .encrypt(&kid, &pem)
+            .map_err(internal)?;
+
+        sqlx::query!(
+            "insert into auth_oauth_provider.signing_keys
+             (kid, alg, state, private_key, activated_at)
+             values ($1, $2, $3, $4, $5)",
+            kid,
+            alg.to_string(),
+            state.to_string(),
+            private_key,
+            (state == KeyState::Active).then(Utc::now),
+        )
+        .execute(&mut **tx)
+        .await
+        .map_err(Status::db)?;
+
+        Ok(kid)
+    }
+
+    /// Encrypt the keys that were stored in plaintext before keys were encrypted
+    async fn encrypt_plaintext_signing_keys(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), Status> {
+        let keys = sqlx::query!(
+            "select id, kid, private_key from auth_oauth_provider.signing_keys
+             where starts_with(private_key, $1)",
+            PLAINTEXT_KEY_PREFIX,
+        )
+        .fetch_all(&mut **tx)
+        .await
+        .map_err(Status::db)?;
+
+        for key in keys {
+            let private_key = self
+                .signing_key_encryptor
+                .encrypt(&key.kid, &key.private_key)
+                .map_err(|err| {
+                    Status::coded(Code::Internal, ErrorCode::Internal)
+                        .with_details(&format!("failed to encrypt signing key: {err}"))
+                })?;
+
+            sqlx::query!(
+                "update auth_oauth_provider.signing_keys set private_key = $1 where id = $2",
+                private_key,
+                key.id,
+            )
+            .execute(&mut **tx)
+            .await
+            .map_err(Status::db)?;
+
+            info!(key.kid, "encrypted plaintext signing key");
+        }
+
+        Ok(())
+    }
+
+    /// Periodically rotate the signing keys (and pick up keys rotated by other replicas)
+    pub fn start_signing_key_rotator(self) {
+        tokio::spawn(async move {
+            loop {
+                sleep(KEY_ROTATION_CHECK_INTERVAL).await;
+                if let Err(err) = self.rotate_signing_keys().await {
+                    warn!(?err, "failed to rotate signing keys");
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bfx_core::service::database::Db;
+
+    async fn stored_private_key(service: &AuthOAuthProviderService) -> String {
+        sqlx::query_scalar("select private_key from auth_oauth_provider.signing_keys")
+            .fetch_one(&service.db)
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn key_encryption() {
+        let encryptor = SigningKeyEncryptor::from_key("secret").unwrap();
+        let pem = SigningKey::generate_pem(KeyAlgorithm::Es256).unwrap();
+
+        let encrypted = encryptor.encrypt("key-1", &pem).unwrap();
+        assert!(!encrypted.contains("PRIVATE KEY"));
+        assert_ne!(encryptor.encrypt("key-1", &pem).unwrap(), encrypted);
+        assert_eq!(encryptor.decrypt("key-1", &encrypted).unwrap(), pem);
+
+        // bound to the kid and the secret key
+        assert!(encryptor.decrypt("key-2", &encrypted).is_err());
+        let other = SigningKeyEncryptor::from_key("other secret").unwrap();
+        assert!(other.decrypt("key-1", &encrypted).is_err());
+        assert!(encryptor.decrypt("key-1", &pem).is_err());
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn stored_keys_are_encrypted(db: Db) {
+        let service = AuthOAuthProviderService::for_tests(db);
+        service.add_test_signing_key().await;
+
+        assert!(
+            !stored_private_key(&service)
+                .await
+                .starts_with(PLAINTEXT_KEY_PREFIX)
+        );
+        assert!(service.get_active_signing_key(KeyAlgorithm::Es256).is_ok());
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn plaintext_keys_get_encrypted(db: Db) {
+        let service = AuthOAuthProviderService::for_tests(db);
+        let pem = SigningKey::generate_pem(KeyAlgorithm::Es256).unwrap();
+        sqlx::query(
+            "insert into auth_oauth_provider.signing_keys
+             (kid, alg, state, private_key, activated_at)
+             values ('old', $1, $2, $3, now())",
+        )
+        .bind(KeyAlgorithm::Es256.to_string())
+        .bind(KeyState::Active.to_string())
+        .bind(&pem)
+        .execute(&service.db)
+        .await
+        .unwrap();
+
+        let mut tx = service.db.begin().await.unwrap();
+        service
+            .encrypt_plaintext_signing_keys(&mut tx)
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+        service.load_signing_keys().await.unwrap();
+
+        let private_key = stored_private_key(&service).await;
+        assert!(!private_key.starts_with(PLAINTEXT_KEY_PREFIX));
+        assert_eq!(
+            service.signing_key_encryptor.decrypt("old", &private_key),
+            Ok(pem)
+        );
+        assert!(service.get_signing_key("old").is_some());
+    }
+}
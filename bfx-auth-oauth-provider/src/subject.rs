@@ -0,0 +1,201 @@
+use crate::AuthOAuthProviderService;
+use crate::models::client_info::ClientInfo;
+use bfx_core::service::id_encryption::IdType;
+use openidconnect::url::Url;
+use std::str::FromStr;
+use strum::{Display, EnumString};
+
+/// How users are identified to a client (the `sub` claim)
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Display, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum SubjectType {
+    /// The same `sub` for every client (the encrypted user ID)
+    Public,
+    /// A different `sub` for every sector, so clients can't correlate users
+    Pairwise,
+}
+
+/// Get the sector that a new client's pairwise subjects are derived for
+///
+/// This is the host of the redirect URIs if they all share one, as recommended by the OIDC spec.
+/// Otherwise, the client gets its own sector.
+#[must_use]
+pub fn sector_identifier_for(redirect_uris: &[String]) -> Option<String> {
+    let mut hosts = redirect_uris
+        .iter()
+        .map(|uri| Url::parse(uri).ok()?.host_str().map(String::from));
+
+    let first = hosts.next()??;
+    hosts
+        .all(|host| host.as_ref() == Some(&first))
+        .then_some(first)
+}
+
+impl ClientInfo {
+    /// Get the effective subject type of the client
+    ///
+    /// Official clients always get public subjects.
+    #[must_use]
+    pub fn subject_type(&self) -> SubjectType {
+        if self.official {
+            return SubjectType::Public;
+        }
+
+        SubjectType::from_str(&self.subject_type).unwrap_or(SubjectType::Public)
+    }
+
+    fn sector(&self) -> &str {
+        self.sector_identifier.as_deref().unwrap_or(&self.client_id)
+    }
+}
+
+impl AuthOAuthProviderService {
+    /// Get the `sub` of a user as seen by a client
+    #[must_use]
+    pub fn get_subject(&self, client: &ClientInfo, user_id: i64) -> String {
+        match client.subject_type() {
+            SubjectType::Public => self.id_encryptor.encrypt_id(IdType::User, user_id),
+            SubjectType::Pairwise => {
+                self.id_encryptor
+                    .encrypt_sector_id(client.sector(), IdType::User, user_id)
+            }
+        }
+    }
+
+    /// Get the user ID from a `sub` given to a client
+    #[must_use]
+    pub fn parse_subject(&self, client: &ClientInfo, sub: &str) -> Option<i64> {
+        match client.subject_type() {
+            SubjectType::Public => self.id_encryptor.decrypt_id(IdType::User, sub),
+            SubjectType::Pairwise => {
+                self.id_encryptor
+                    .decrypt_sector_id(client.sector(), IdType::User, sub)
+            }
+        }
+        .ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::test_client_details;
+    use bfx_core::service::database::Db;
+    use bfx_proto::auth::ClientDetails;
+
+    fn client_details(redirect_uris: &[&str], subject_type: SubjectType) -> ClientDetails {
+        ClientDetails {
+            redirect_uris: redirect_uris.iter().map(|uri| (*uri).into()).collect(),
+            subject_type: Some(subject_type.to_string()),
+            ..test_client_details()
+        }
+    }
+
+    #[test]
+    fn sector_identifier() {
+        assert_eq!(
+            sector_identifier_for(&[
+                "https://app.example.com/callback".into(),
+                "https://app.example.com/other".into(),
+            ]),
+            Some("app.example.com".into())
+        );
+        assert_eq!(
+            sector_identifier_for(&[
+                "https://app.example.com/callback".into(),
+                "https://other.example.com/callback".into(),
+            ]),
+            None
+        );
+        assert_eq!(sector_identifier_for(&["not a url".into()]), None);
+        assert_eq!(sector_identifier_for(&[]), None);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn pairwise_subjects(db: Db) {
+        let service = AuthOAuthProviderService::for_tests(db);
+        let (client, _) = service
+            .create_test_client(client_details(
+                &["https://app.example.com/callback"],
+                SubjectType::Pairwise,
+            ))
+            .await;
+        let (same_sector, _) = service
+            .create_test_client(client_details(
+                &["https://app.example.com/other"],
+                SubjectType::Pairwise,
+            ))
+            .await;
+        let (other_sector, _) = service
+            .create_test_client(client_details(
+                &["https://other.example.com/callback"],
+                SubjectType::Pairwise,
+            ))
+            .await;
+        let (public, _) = service
+            .create_test_client(client_details(
+                &["https://app.example.com/callback"],
+                SubjectType::Public,
+            ))
+            .await;
+
+        let sub = service.get_subject(&client, 42);
+        assert_eq!(service.parse_subject(&client, &sub), Some(42));
+        assert_ne!(service.get_subject(&client, 43), sub);
+
+        // the sector decides, not the client
+        assert_eq!(service.get_subject(&same_sector, 42), sub);
+        assert_ne!(service.get_subject(&other_sector, 42), sub);
+        assert_eq!(service.parse_subject(&other_sector, &sub), None);
+
+        // pairwise subjects are not the public user ID
+        let public_sub = service.get_subject(&public, 42);
+        assert_eq!(
+            public_sub,
+            service.id_encryptor.encrypt_id(IdType::User, 42)
+        );
+        assert_ne!(public_sub, sub);
+        assert_eq!(service.parse_subject(&client, &public_sub), None);
+        assert_eq!(service.parse_subject(&public, &public_sub), Some(42));
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn clients_without_common_host(db: Db) {
+        let service = AuthOAuthProviderService::for_tests(db);
+        let redirect_uris = [
+            "https://app.example.com/callback",
+            "https://other.example.com/callback",
+        ];
+        let (client, _) = service
+            .create_test_client(client_details(&redirect_uris, SubjectType::Pairwise))
+            .await;
+        let (other_client, _) = service
+            .create_test_client(client_details(&redirect_uris, SubjectType::Pairwise))
+            .await;
+
+        // each client is its own sector
+        assert_eq!(client.sector_identifier, None);
+        assert_ne!(
+            service.get_subject(&client, 42),
+            service.get_subject(&other_client, 42)
+        );
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn official_clients_get_public_subjects(db: Db) {
+        let service = AuthOAuthProviderService::for_tests(db);
+        let (mut client, _) = service
+            .create_test_client(client_details(
+                &["https://app.example.com/callback"],
+                SubjectType::Pairwise,
+            ))
+            .await;
+        client.official = true;
+
+        assert_eq!(client.subject_type(), SubjectType::Public);
+        assert_eq!(
+            service.get_subject(&client, 42),
+            service.id_encryptor.encrypt_id(IdType::User, 42)
+        );
+    }
+}
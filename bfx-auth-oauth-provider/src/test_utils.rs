@@ -0,0 +1,141 @@
+use crate::AuthOAuthProviderService;
+use crate::client_auth::TokenEndpointAuthMethod;
+use crate::models::client_info::ClientInfo;
+use crate::signing_keys::{KeyAlgorithm, KeyState, SigningKeyEncryptor};
+use bfx_core::service::database::Db;
+use bfx_core::service::id_encryption::IdEncryptor;
+use bfx_proto::auth::{ClientDetails, CreateClientRequest};
+use jsonwebtoken::jwk::{Jwk, JwkSet};
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use nanoid::nanoid;
+use openidconnect::core::{CoreJsonCurveType, CoreJsonWebKey};
+use openidconnect::{IssuerUrl, JsonWebKeyId};
+use p256::pkcs8::{EncodePrivateKey, LineEnding};
+use rand_core::OsRng;
+use serde_json::{Value, json};
+use sqlx::types::chrono::Utc;
+use std::sync::Arc;
+use tonic::Request;
+use tonic::transport::Channel;
+
+pub const TOKEN_ENDPOINT: &str = "https://bfx.example.com/openid/token";
+
+impl AuthOAuthProviderService {
+    /// Make a service for tests, with placeholders for everything but the database
+    pub(crate) fn for_tests(db: Db) -> Self {
+        Self {
+            db,
+            router: Channel::from_static("http://localhost:1").connect_lazy(),
+            id_encryptor: IdEncryptor::from_key("test").unwrap(),
+            frontend_root: "https://bfx.example.com".into(),
+            issuer: IssuerUrl::new("https://bfx.example.com".into()).unwrap(),
+            signing_keys: Arc::default(),
+            signing_key_encryptor: SigningKeyEncryptor::from_key("test").unwrap(),
+            http_client: reqwest::Client::new(),
+        }
+    }
+
+    /// Register a client for tests
+    ///
+    /// Returns the client and its secret.
+    pub(crate) async fn create_test_client(&self, details: ClientDetails) -> (ClientInfo, String) {
+        let reply = self
+            .create_client(Request::new(CreateClientRequest {
+                owner_id: 1,
+                details: Some(details),
+                official: false,
+                resource_server: false,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        let client = self
+            .get_client_by_id(reply.client.unwrap().id)
+            .await
+            .unwrap();
+
+        (client, reply.client_secret)
+    }
+
+    /// Add an active ES256 signing key (generating RSA keys is slow in debug builds)
+    pub(crate) async fn add_test_signing_key(&self) {
+        let mut tx = self.db.begin().await.unwrap();
+        self.insert_signing_key(&mut tx, KeyAlgorithm::Es256, KeyState::Active)
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+
+        self.load_signing_keys().await.unwrap();
+    }
+}
+
+/// Get valid client details, to be extended with struct update syntax
+///
+/// ID tokens are signed with ES256, see [`AuthOAuthProviderService::add_test_signing_key`].
+pub fn test_client_details() -> ClientDetails {
+    ClientDetails {
+        redirect_uris: vec!["https://client.example.com/callback".into()],
+        display_name: "Test client".into(),
+        allowed_scopes: vec!["openid".into(), "offline_access".into()],
+        id_token_signed_response_alg: Some(KeyAlgorithm::Es256.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Get valid details of a public client, which only authenticates with its `client_id`
+pub fn public_client_details() -> ClientDetails {
+    ClientDetails {
+        token_endpoint_auth_method: Some(TokenEndpointAuthMethod::None.to_string()),
+        ..test_client_details()
+    }
+}
+
+/// Generate an ES256 key pair, for acting as a client
+pub fn generate_es256(kid: &str) -> (EncodingKey, Jwk) {
+    let key = p256::ecdsa::SigningKey::random(&mut OsRng);
+    let pem = key.to_pkcs8_pem(LineEnding::LF).unwrap();
+
+    let point = key.verifying_key().to_encoded_point(false);
+    let jwk = CoreJsonWebKey::new_ec(
+        point.x().unwrap().to_vec(),
+        point.y().unwrap().to_vec(),
+        CoreJsonCurveType::P256,
+        Some(JsonWebKeyId::new(kid.into())),
+    );
+
+    (
+        EncodingKey::from_ec_pem(pem.as_bytes()).unwrap(),
+        serde_json::from_value(serde_json::to_value(jwk).unwrap()).unwrap(),
+    )
+}
+
+/// Serialize a JWKS with a single key, as sent by clients
+pub fn jwks(jwk: &Jwk) -> String {
+    serde_json::to_string(&JwkSet {
+        keys: vec![jwk.clone()],
+    })
+    .unwrap()
+}
+
+/// Make a `DPoP` proof JWT with any `typ` and claims
+pub fn make_dpop_proof_with(key: &EncodingKey, jwk: &Jwk, typ: &str, claims: &Value) -> String {
+    let mut header = Header::new(Algorithm::ES256);
+    header.typ = Some(typ.into());
+    header.jwk = Some(jwk.clone());
+    jsonwebtoken::encode(&header, claims, key).unwrap()
+}
+
+/// Get the claims of a valid `DPoP` proof for a request to the token endpoint
+pub fn dpop_proof_claims() -> Value {
+    json!({
+        "jti": nanoid!(),
+        "htm": "POST",
+        "htu": TOKEN_ENDPOINT,
+        "iat": Utc::now().timestamp(),
+    })
+}
+
+/// Make a valid `DPoP` proof for a request to the token endpoint
+pub fn make_dpop_proof(key: &EncodingKey, jwk: &Jwk) -> String {
+    make_dpop_proof_with(key, jwk, "dpop+jwt", &dpop_proof_claims())
+}
@@ -1,16 +1,19 @@
 mod client;
 mod methods;
 pub mod models;
+mod saml;
 
 use crate::client::OAuthClients;
+use crate::saml::SamlServiceProvider;
 use bfx_core::logging::setup_logging;
 use bfx_core::service::client::require_router;
 use bfx_core::service::database::{Db, require_db};
 use bfx_core::service::start_service;
 use bfx_proto::auth::auth_o_auth_server::{AuthOAuth, AuthOAuthServer};
 use bfx_proto::auth::{
-    BindOAuthReply, BindOAuthRequest, FinishOAuthFlowReply, FinishOAuthFlowRequest,
-    GetAuthSourcesReply, GetAuthSourcesRequest, StartOAuthFlowReply, StartOAuthFlowRequest,
+    BindOAuthReply, BindOAuthRequest, ConsumeSamlResponseReply, ConsumeSamlResponseRequest,
+    FinishOAuthFlowReply, FinishOAuthFlowRequest, GetAuthSourcesReply, GetAuthSourcesRequest,
+    GetSamlMetadataReply, GetSamlMetadataRequest, StartOAuthFlowReply, StartOAuthFlowRequest,
     UnbindAuthSourceReply, UnbindAuthSourceRequest,
 };
 use tonic::transport::Channel;
@@ -24,6 +27,7 @@ async fn main() -> anyhow::Result<()> {
         db: require_db().await?,
         router: require_router()?,
         clients: OAuthClients::new().await?,
+        saml: SamlServiceProvider::new()?,
     };
 
     start_service(AuthOAuthServer::new(service)).await?;
@@ -35,6 +39,7 @@ pub struct AuthOAuthService {
     db: Db,
     router: Channel,
     clients: OAuthClients,
+    saml: SamlServiceProvider,
 }
 
 #[tonic::async_trait]
@@ -73,4 +78,18 @@ impl AuthOAuth for AuthOAuthService {
     ) -> Result<Response<UnbindAuthSourceReply>, Status> {
         self.unbind_auth_source(request).await
     }
+
+    async fn get_saml_metadata(
+        &self,
+        request: Request<GetSamlMetadataRequest>,
+    ) -> Result<Response<GetSamlMetadataReply>, Status> {
+        self.get_saml_metadata(request)
+    }
+
+    async fn consume_saml_response(
+        &self,
+        request: Request<ConsumeSamlResponseRequest>,
+    ) -> Result<Response<ConsumeSamlResponseReply>, Status> {
+        self.consume_saml_response(request).await
+    }
 }
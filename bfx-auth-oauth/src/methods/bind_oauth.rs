@@ -16,7 +16,7 @@ impl AuthOAuthService {
     ///
     /// # Errors
     ///
-    /// - If [`AuthOAuthService::get_id_token_claims`] fails
+    /// - If [`AuthOAuthService::get_external_identity`] fails
     /// - If an account from the issuer has already been bound
     /// - Miscellaneous internal errors
     pub async fn bind_oauth(
@@ -33,8 +33,8 @@ impl AuthOAuthService {
             .user_context
             .ok_or_else(|| Status::coded(Code::InvalidArgument, ErrorCode::Internal))?;
 
-        let claims = self
-            .get_id_token_claims(
+        let identity = self
+            .get_external_identity(
                 &finish_request.issuer,
                 finish_request.state,
                 finish_request.code,
@@ -48,7 +48,7 @@ impl AuthOAuthService {
              returning *",
             request.user_id,
             finish_request.issuer,
-            claims.subject().as_str(),
+            identity.subject,
         )
         .fetch_one(&self.db)
         .await;
@@ -64,12 +64,13 @@ impl AuthOAuthService {
             .send_notification(SendNotificationRequest {
                 user_id: request.user_id,
                 user_override: None,
-                definition: include_str!("../../notifications/oauth_bound.yml").to_string(),
+                definition_id: "oauth_bound".to_string(),
                 params: param_map! {
                     "audit_ip" => user_context.ip,
                     "audit_time" => Utc::now().to_rfc3339(),
                     "provider" => OAuthClients::get_provider_name(&finish_request.issuer),
                 },
+                send_at: None,
             })
             .await
             .log_if_error("sending oauth bound notification");
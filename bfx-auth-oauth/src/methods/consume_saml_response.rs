@@ -0,0 +1,77 @@
+use crate::AuthOAuthService;
+use crate::saml::get_saml_identity;
+use bfx_core::status::{ErrorCode, StatusExt};
+use bfx_proto::auth::{ConsumeSamlResponseReply, ConsumeSamlResponseRequest};
+use openidconnect::CsrfToken;
+use openidconnect::url::Url;
+use tonic::{Code, Request, Response, Status};
+
+impl AuthOAuthService {
+    /// `/saml/acs` endpoint
+    ///
+    /// Verifies the `SAMLResponse` and stores the identity in the flow,
+    /// so that it can be finished with a code like a regular OAuth flow.
+    ///
+    /// # Errors
+    ///
+    /// - If the flow corresponding to the relay state is not found
+    /// - If the response is invalid or isn't for this flow
+    /// - Miscellaneous internal errors
+    pub async fn consume_saml_response(
+        &self,
+        request: Request<ConsumeSamlResponseRequest>,
+    ) -> Result<Response<ConsumeSamlResponseReply>, Status> {
+        let request = request.into_inner();
+
+        let flow = sqlx::query!(
+            "select id, issuer, nonce from auth_oauth.flows
+             where state = $1 and pkce_verifier is null and saml_code is null
+                   and created_at > now() - interval '10 minutes'",
+            request.relay_state,
+        )
+        .fetch_optional(&self.db)
+        .await
+        .map_err(Status::db)?
+        .ok_or_else(|| Status::coded(Code::NotFound, ErrorCode::FlowNotFound))?;
+
+        let provider = self
+            .get_saml_provider(&flow.issuer)
+            .await?
+            .ok_or_else(|| Status::coded(Code::InvalidArgument, ErrorCode::UnknownProvider))?;
+
+        let identity = {
+            let sp = self.saml.for_idp(&provider)?;
+            get_saml_identity(&sp, &provider, &request.saml_response, &flow.nonce)?
+        };
+
+        let code = CsrfToken::new_random().into_secret();
+        let result = sqlx::query!(
+            "update auth_oauth.flows
+             set saml_code = $1, saml_subject = $2, saml_email = $3, saml_email_verified = $4
+             where id = $5 and saml_code is null",
+            code,
+            identity.subject,
+            identity.email,
+            identity.email_verified,
+            flow.id,
+        )
+        .execute(&self.db)
+        .await
+        .map_err(Status::db)?;
+        if result.rows_affected() == 0 {
+            return Err(Status::coded(Code::NotFound, ErrorCode::FlowNotFound));
+        }
+
+        let mut redirect_to = Url::parse(&self.saml.callback_url)
+            .map_err(|err| Status::coded(Code::Internal, ErrorCode::Internal).with_source(err))?;
+        redirect_to
+            .query_pairs_mut()
+            .append_pair("issuer", &flow.issuer)
+            .append_pair("state", &request.relay_state)
+            .append_pair("code", &code);
+
+        Ok(Response::new(ConsumeSamlResponseReply {
+            redirect_to: redirect_to.to_string(),
+        }))
+    }
+}
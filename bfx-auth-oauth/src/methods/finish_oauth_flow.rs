@@ -1,4 +1,5 @@
 use crate::AuthOAuthService;
+use crate::models::external_identity::ExternalIdentity;
 use bfx_core::status::{ErrorCode, StatusExt};
 use bfx_proto::UserContext;
 use bfx_proto::auth::auth_core_client::AuthCoreClient;
@@ -27,8 +28,8 @@ impl AuthOAuthService {
     ///
     /// # Errors
     ///
-    /// - If [`AuthOAuthService::get_id_token_claims`] fails
-    /// - If the ID token doesn't contain an email, or it's not verified
+    /// - If [`AuthOAuthService::get_external_identity`] fails
+    /// - If the identity doesn't contain an email, or it's not verified
     /// - If calling [`AuthCoreClient::login_external`] fails
     /// - If `AuthOAuthService::register_user` fails (if [`AuthCoreClient::create_user`] fails)
     /// - Miscellaneous internal errors
@@ -42,16 +43,16 @@ impl AuthOAuthService {
             .user_context
             .ok_or_else(|| Status::coded(Code::InvalidArgument, ErrorCode::Internal))?;
 
-        // 1. exchange code and get id token claims
-        let claims = self
-            .get_id_token_claims(&request.issuer, request.state, request.code)
+        // 1. exchange code and get the identity
+        let identity = self
+            .get_external_identity(&request.issuer, request.state, request.code)
             .await?;
 
-        let Some(email) = claims.email() else {
+        let Some(email) = identity.email else {
             return Err(Status::coded(Code::Internal, ErrorCode::ProviderEmailError)
                 .with_details("no email"));
         };
-        if !claims.email_verified().unwrap_or(false) {
+        if !identity.email_verified {
             return Err(Status::coded(Code::Internal, ErrorCode::ProviderEmailError)
                 .with_details("email is not verified"));
         }
@@ -60,7 +61,7 @@ impl AuthOAuthService {
         let auth_source = sqlx::query!(
             "select * from auth_oauth.auth_sources where issuer = $1 and issuer_user_id = $2",
             &request.issuer,
-            claims.subject().as_str(),
+            &identity.subject,
         )
         .fetch_optional(&self.db)
         .await
@@ -94,12 +95,7 @@ impl AuthOAuthService {
         } else {
             // if no auth source, try to register a new user
             let tokens = self
-                .register_user(
-                    email.as_str().to_string(),
-                    user_context,
-                    &request.issuer,
-                    claims.subject().as_str(),
-                )
+                .register_user(email, user_context, &request.issuer, &identity.subject)
                 .await?;
 
             if let Some(tokens) = tokens {
@@ -118,6 +114,65 @@ impl AuthOAuthService {
         }))
     }
 
+    /// Get the user identity from an external provider using an authorization code
+    ///
+    /// # Errors
+    ///
+    /// - If [`AuthOAuthService::get_id_token_claims`] fails
+    /// - If the SAML flow corresponding to the issuer, the state and the code is not found
+    /// - Miscellaneous internal errors
+    pub async fn get_external_identity(
+        &self,
+        issuer: &str,
+        state: String,
+        code: String,
+    ) -> Result<ExternalIdentity, Status> {
+        if self.clients.get_provider(issuer).is_err() {
+            return self.get_saml_flow_identity(issuer, state, code).await;
+        }
+
+        let claims = self.get_id_token_claims(issuer, state, code).await?;
+
+        Ok(ExternalIdentity {
+            subject: claims.subject().to_string(),
+            email: claims.email().map(|email| email.to_string()),
+            email_verified: claims.email_verified().unwrap_or(false),
+        })
+    }
+
+    /// Get the identity stored by [`AuthOAuthService::consume_saml_response`]
+    ///
+    /// # Errors
+    ///
+    /// - If the flow corresponding to the issuer, the state and the code is not found
+    /// - Miscellaneous internal errors
+    async fn get_saml_flow_identity(
+        &self,
+        issuer: &str,
+        state: String,
+        code: String,
+    ) -> Result<ExternalIdentity, Status> {
+        let flow = sqlx::query!(
+            r#"delete from auth_oauth.flows
+               where issuer = $1 and state = $2 and saml_code = $3
+                     and created_at > now() - interval '10 minutes'
+               returning saml_subject as "saml_subject!", saml_email, saml_email_verified"#,
+            issuer,
+            state,
+            code,
+        )
+        .fetch_optional(&self.db)
+        .await
+        .map_err(Status::db)?
+        .ok_or_else(|| Status::coded(Code::NotFound, ErrorCode::FlowNotFound))?;
+
+        Ok(ExternalIdentity {
+            subject: flow.saml_subject,
+            email: flow.saml_email,
+            email_verified: flow.saml_email_verified,
+        })
+    }
+
     /// Get ID token claims from an OAuth provider using an authorization code
     ///
     /// # Errors
@@ -135,7 +190,9 @@ impl AuthOAuthService {
         let client = self.clients.get_provider(issuer)?;
 
         let flow = sqlx::query!(
-            "delete from auth_oauth.flows where issuer = $1 and state = $2 returning *",
+            "delete from auth_oauth.flows
+             where issuer = $1 and state = $2 and pkce_verifier is not null
+             returning nonce, pkce_verifier as \"pkce_verifier!\"",
             issuer,
             state,
         )
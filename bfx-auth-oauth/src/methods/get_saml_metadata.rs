@@ -0,0 +1,19 @@
+use crate::AuthOAuthService;
+use bfx_proto::auth::{GetSamlMetadataReply, GetSamlMetadataRequest};
+use tonic::{Request, Response, Status};
+
+impl AuthOAuthService {
+    /// `/saml/metadata` endpoint
+    ///
+    /// # Errors
+    ///
+    /// - Miscellaneous internal errors
+    pub fn get_saml_metadata(
+        &self,
+        _request: Request<GetSamlMetadataRequest>,
+    ) -> Result<Response<GetSamlMetadataReply>, Status> {
+        Ok(Response::new(GetSamlMetadataReply {
+            xml: self.saml.metadata()?,
+        }))
+    }
+}
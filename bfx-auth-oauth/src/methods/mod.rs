@@ -1,5 +1,7 @@
 mod bind_oauth;
+mod consume_saml_response;
 mod finish_oauth_flow;
 mod get_auth_sources;
+mod get_saml_metadata;
 mod start_oauth_flow;
 mod unbind_auth_source;
@@ -1,4 +1,6 @@
 use crate::AuthOAuthService;
+use crate::models::saml_provider::SamlProvider;
+use crate::saml::make_authn_redirect;
 use bfx_core::status::{ErrorCode, StatusExt};
 use bfx_proto::auth::{StartOAuthFlowReply, StartOAuthFlowRequest};
 use openidconnect::core::CoreAuthenticationFlow;
@@ -18,7 +20,15 @@ impl AuthOAuthService {
     ) -> Result<Response<StartOAuthFlowReply>, Status> {
         let request = request.into_inner();
 
-        let client = self.clients.get_provider(&request.issuer)?;
+        let client = match self.clients.get_provider(&request.issuer) {
+            Ok(client) => client,
+            Err(err) => {
+                return match self.get_saml_provider(&request.issuer).await? {
+                    Some(provider) => self.start_saml_flow(&provider).await,
+                    None => Err(err),
+                };
+            }
+        };
 
         let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
         let req = client
@@ -60,4 +70,43 @@ impl AuthOAuthService {
             code_challenge_method: Some(pkce_challenge.method().to_string()),
         }))
     }
+
+    /// Get the URL to start a SAML login
+    ///
+    /// # Errors
+    ///
+    /// - If the identity provider metadata is unusable
+    /// - Miscellaneous internal errors
+    async fn start_saml_flow(
+        &self,
+        provider: &SamlProvider,
+    ) -> Result<Response<StartOAuthFlowReply>, Status> {
+        let relay_state = CsrfToken::new_random();
+        let (request_id, url) = {
+            let sp = self.saml.for_idp(provider)?;
+            make_authn_redirect(&sp, relay_state.secret())?
+        };
+
+        // the AuthnRequest ID is stored as the nonce, SAML doesn't use PKCE
+        sqlx::query!(
+            "insert into auth_oauth.flows
+             (issuer, state, nonce)
+             values ($1, $2, $3)",
+            provider.entity_id,
+            relay_state.secret(),
+            request_id,
+        )
+        .execute(&self.db)
+        .await
+        .map_err(Status::db)?;
+
+        Ok(Response::new(StartOAuthFlowReply {
+            scope: String::new(),
+            state: relay_state.into_secret(),
+            nonce: request_id,
+            url,
+            code_challenge: None,
+            code_challenge_method: None,
+        }))
+    }
 }
@@ -44,12 +44,13 @@ impl AuthOAuthService {
             .send_notification(SendNotificationRequest {
                 user_id: auth_source.user_id,
                 user_override: None,
-                definition: include_str!("../../notifications/oauth_unbound.yml").to_string(),
+                definition_id: "oauth_unbound".to_string(),
                 params: param_map! {
-                    "audit_ip" => user_context.ip.to_string(),
+                    "audit_ip" => user_context.ip.clone(),
                     "audit_time" => user_context.user_agent,
                     "provider" => OAuthClients::get_provider_name(&auth_source.issuer),
                 },
+                send_at: None,
             })
             .await
             .log_if_error("sending oauth unbound notification");
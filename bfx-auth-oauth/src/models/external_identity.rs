@@ -0,0 +1,7 @@
+/// User identity asserted by an external provider (OIDC or SAML)
+#[derive(Debug, Clone)]
+pub struct ExternalIdentity {
+    pub subject: String,
+    pub email: Option<String>,
+    pub email_verified: bool,
+}
@@ -1 +1,3 @@
+pub mod external_identity;
 pub mod raw_auth_source;
+pub mod saml_provider;
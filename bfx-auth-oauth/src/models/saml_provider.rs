@@ -0,0 +1,25 @@
+use sqlx::types::chrono::{DateTime, Utc};
+
+#[derive(Debug, Clone)]
+pub struct SamlProvider {
+    pub id: i64,
+    pub entity_id: String,
+    pub metadata: String,
+    pub email_attribute: String,
+    pub subject_attribute: Option<String>,
+    pub email_domains: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl SamlProvider {
+    /// Whether the identity provider is trusted to assert the email,
+    /// i.e. it's in one of its `email_domains`
+    #[must_use]
+    pub fn vouches_for(&self, email: &str) -> bool {
+        email.rsplit_once('@').is_some_and(|(_, domain)| {
+            self.email_domains
+                .iter()
+                .any(|trusted| trusted.eq_ignore_ascii_case(domain))
+        })
+    }
+}
@@ -0,0 +1,424 @@
+use crate::AuthOAuthService;
+use crate::models::external_identity::ExternalIdentity;
+use crate::models::saml_provider::SamlProvider;
+use bfx_core::service::environment::require_env;
+use bfx_core::status::{ErrorCode, StatusExt};
+use samael::crypto::AllowedSignatureAlgorithm;
+use samael::metadata::{EntityDescriptor, HTTP_REDIRECT_BINDING};
+use samael::schema::Assertion;
+use samael::service_provider::{ServiceProvider, ServiceProviderBuilder};
+use samael::traits::ToXml;
+use std::fmt::Display;
+use tonic::{Code, Status};
+
+const ALLOWED_SIGNATURE_ALGORITHMS: &[AllowedSignatureAlgorithm] = &[
+    AllowedSignatureAlgorithm::RsaSha256,
+    AllowedSignatureAlgorithm::RsaSha384,
+    AllowedSignatureAlgorithm::RsaSha512,
+    AllowedSignatureAlgorithm::EcdsaSha256,
+    AllowedSignatureAlgorithm::EcdsaSha384,
+    AllowedSignatureAlgorithm::EcdsaSha512,
+];
+
+fn provider_err(err: impl Display) -> Status {
+    Status::coded(Code::Internal, ErrorCode::ProviderError).with_details(&err.to_string())
+}
+
+/// The `SAMLResponse` is invalid, forged or not meant for us
+fn response_err(err: impl Display) -> Status {
+    Status::coded(Code::InvalidArgument, ErrorCode::ProviderError).with_details(&err.to_string())
+}
+
+/// Our side of SAML 2.0 logins
+pub struct SamlServiceProvider {
+    pub entity_id: String,
+    pub acs_url: String,
+    pub callback_url: String,
+}
+
+impl SamlServiceProvider {
+    /// Create the service provider configuration from `FRONTEND_ROOT`
+    ///
+    /// # Errors
+    ///
+    /// - If `FRONTEND_ROOT` is not set
+    pub fn new() -> anyhow::Result<Self> {
+        let frontend_root = require_env("FRONTEND_ROOT")?;
+
+        Ok(Self {
+            entity_id: format!("{frontend_root}/saml/metadata"),
+            acs_url: format!("{frontend_root}/saml/acs"),
+            callback_url: format!("{frontend_root}/oauth/saml/callback"),
+        })
+    }
+
+    /// Get a [`ServiceProvider`] for talking to an identity provider
+    ///
+    /// # Errors
+    ///
+    /// - If the identity provider metadata is invalid
+    /// - If the identity provider metadata doesn't have a signing certificate
+    pub fn for_idp(&self, provider: &SamlProvider) -> Result<ServiceProvider, Status> {
+        let idp_metadata: EntityDescriptor = provider.metadata.parse().map_err(provider_err)?;
+
+        let sp = self
+            .builder()
+            .idp_metadata(idp_metadata)
+            .build()
+            .map_err(provider_err)?;
+
+        // samael accepts unsigned responses if the IdP has no certificates
+        if sp.idp_signing_certs().map_err(provider_err)?.is_none() {
+            return Err(provider_err("IdP metadata has no signing certificate"));
+        }
+
+        Ok(sp)
+    }
+
+    /// Render our metadata XML
+    ///
+    /// # Errors
+    ///
+    /// - Miscellaneous internal errors
+    pub fn metadata(&self) -> Result<String, Status> {
+        let sp = self
+            .builder()
+            .build()
+            .map_err(|err| Status::coded(Code::Internal, ErrorCode::Internal).with_source(err))?;
+
+        let internal_err = |err: Box<dyn std::error::Error>| -> Status {
+            Status::coded(Code::Internal, ErrorCode::Internal).with_details(&err.to_string())
+        };
+
+        let mut metadata = sp.metadata().map_err(internal_err)?;
+        // single logout is not supported
+        for descriptor in metadata.sp_sso_descriptors.iter_mut().flatten() {
+            descriptor.single_logout_services = None;
+        }
+
+        metadata.to_string().map_err(internal_err)
+    }
+
+    fn builder(&self) -> ServiceProviderBuilder {
+        let mut builder = ServiceProviderBuilder::default();
+        builder
+            .entity_id(self.entity_id.clone())
+            .metadata_url(self.entity_id.clone())
+            .acs_url(self.acs_url.clone())
+            .allow_idp_initiated(false)
+            .allowed_signature_algorithms(ALLOWED_SIGNATURE_ALGORITHMS.to_vec());
+        builder
+    }
+}
+
+/// Get the identity provider URL for starting a login with the `HTTP-Redirect` binding
+///
+/// Returns the `AuthnRequest` ID and the URL.
+///
+/// # Errors
+///
+/// - If the identity provider doesn't support the binding
+/// - If the request can't be encoded
+pub fn make_authn_redirect(
+    sp: &ServiceProvider,
+    relay_state: &str,
+) -> Result<(String, String), Status> {
+    let sso_url = sp
+        .sso_binding_location(HTTP_REDIRECT_BINDING)
+        .ok_or_else(|| provider_err("IdP does not support the HTTP-Redirect binding"))?;
+
+    let authn_request = sp
+        .make_authentication_request(&sso_url)
+        .map_err(provider_err)?;
+    let url = authn_request
+        .redirect(relay_state)
+        .map_err(provider_err)?
+        .ok_or_else(|| provider_err("no destination in AuthnRequest"))?;
+
+    Ok((authn_request.id, url.to_string()))
+}
+
+/// Verify a `SAMLResponse` and get the identity from its assertion
+///
+/// # Errors
+///
+/// - If the response or the assertion is invalid, unsigned or not for `request_id`
+/// - If the subject can't be found in the assertion
+///
+/// The email is only verified if it's in one of the `email_domains` of the provider.
+pub fn get_saml_identity(
+    sp: &ServiceProvider,
+    provider: &SamlProvider,
+    saml_response: &str,
+    request_id: &str,
+) -> Result<ExternalIdentity, Status> {
+    let assertion = sp
+        .parse_base64_response(saml_response, Some(&[request_id]))
+        .map_err(response_err)?;
+
+    let subject = if let Some(subject_attribute) = &provider.subject_attribute {
+        get_attribute(&assertion, subject_attribute)
+    } else {
+        assertion
+            .subject
+            .as_ref()
+            .and_then(|subject| subject.name_id.as_ref())
+            .map(|name_id| name_id.value.clone())
+    };
+    let Some(subject) = subject.filter(|subject| !subject.is_empty()) else {
+        return Err(response_err("no subject in assertion"));
+    };
+
+    let email = get_attribute(&assertion, &provider.email_attribute);
+
+    Ok(ExternalIdentity {
+        subject,
+        // any IdP can assert any address, only trust the domains configured for it
+        email_verified: email
+            .as_deref()
+            .is_some_and(|email| provider.vouches_for(email)),
+        email,
+    })
+}
+
+fn get_attribute(assertion: &Assertion, name: &str) -> Option<String> {
+    assertion
+        .attribute_statements
+        .iter()
+        .flatten()
+        .flat_map(|statement| &statement.attributes)
+        .filter(|attribute| {
+            attribute.name.as_deref() == Some(name)
+                || attribute.friendly_name.as_deref() == Some(name)
+        })
+        .flat_map(|attribute| &attribute.values)
+        .find_map(|value| value.value.clone())
+}
+
+impl AuthOAuthService {
+    /// Get a SAML identity provider by its entity ID
+    ///
+    /// # Errors
+    ///
+    /// - Miscellaneous internal errors
+    pub async fn get_saml_provider(&self, entity_id: &str) -> Result<Option<SamlProvider>, Status> {
+        sqlx::query_as!(
+            SamlProvider,
+            "select * from auth_oauth.saml_providers where entity_id = $1",
+            entity_id,
+        )
+        .fetch_optional(&self.db)
+        .await
+        .map_err(Status::db)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::Engine;
+    use base64::engine::general_purpose::STANDARD;
+    use chrono::{DateTime, TimeDelta, Utc};
+    use samael::crypto::{CertificateDer, Crypto, CryptoProvider};
+    use samael::idp::response_builder::{ResponseAttribute, build_response_template};
+    use samael::idp::sp_extractor::RequiredAttribute;
+
+    // a local IdP, signing with `idp.key.pem`
+    const IDP_ENTITY_ID: &str = "https://idp.example.com/saml";
+    const IDP_METADATA: &str = include_str!("../fixtures/saml/idp-metadata.xml");
+    const IDP_CERT: &str = include_str!("../fixtures/saml/idp.crt.pem");
+    const IDP_KEY: &str = include_str!("../fixtures/saml/idp.key.pem");
+    const OTHER_KEY: &str = include_str!("../fixtures/saml/other.key.pem");
+
+    const REQUEST_ID: &str = "id-8a6bd3d4e0c54b1f";
+    const EMAIL_ATTRIBUTE: &str = "urn:oid:0.9.2342.19200300.100.1.3";
+
+    struct TestResponse<'a> {
+        email: &'a str,
+        audience: &'a str,
+        in_response_to: &'a str,
+        issued_at: DateTime<Utc>,
+        signing_key: Option<&'a str>,
+    }
+
+    impl Default for TestResponse<'_> {
+        fn default() -> Self {
+            Self {
+                email: "alice@example.com",
+                audience: "https://bonfire.test/saml/metadata",
+                in_response_to: REQUEST_ID,
+                issued_at: Utc::now(),
+                signing_key: Some(IDP_KEY),
+            }
+        }
+    }
+
+    impl TestResponse<'_> {
+        /// Build the base64 encoded `SAMLResponse` like it's posted to the ACS
+        fn encode(&self) -> String {
+            let cert = CertificateDer::from(pem_to_der(IDP_CERT));
+            let mut response = build_response_template(
+                &cert,
+                "alice",
+                self.audience,
+                IDP_ENTITY_ID,
+                &service_provider().acs_url,
+                self.in_response_to,
+                &[ResponseAttribute {
+                    required_attribute: RequiredAttribute {
+                        name: EMAIL_ATTRIBUTE.to_string(),
+                        format: None,
+                    },
+                    value: self.email,
+                }],
+            );
+
+            response.issue_instant = self.issued_at;
+            let assertion = response.assertion.as_mut().unwrap();
+            assertion.issue_instant = self.issued_at;
+            for confirmation in assertion
+                .subject
+                .iter_mut()
+                .flat_map(|subject| subject.subject_confirmations.iter_mut().flatten())
+            {
+                if let Some(data) = &mut confirmation.subject_confirmation_data {
+                    data.not_on_or_after = Some(self.issued_at + TimeDelta::minutes(5));
+                }
+            }
+
+            let xml = if let Some(key) = self.signing_key {
+                Crypto::sign_xml(response.to_string().unwrap(), &pem_to_der(key)).unwrap()
+            } else {
+                response.signature = None;
+                response.to_string().unwrap()
+            };
+
+            STANDARD.encode(xml)
+        }
+    }
+
+    fn pem_to_der(pem: &str) -> Vec<u8> {
+        let base64 = pem
+            .lines()
+            .filter(|line| !line.starts_with("-----"))
+            .collect::<String>();
+        STANDARD.decode(base64).unwrap()
+    }
+
+    fn service_provider() -> SamlServiceProvider {
+        SamlServiceProvider {
+            entity_id: "https://bonfire.test/saml/metadata".to_string(),
+            acs_url: "https://bonfire.test/saml/acs".to_string(),
+            callback_url: "https://bonfire.test/oauth/saml/callback".to_string(),
+        }
+    }
+
+    fn provider(email_domains: &[&str]) -> SamlProvider {
+        SamlProvider {
+            id: 1,
+            entity_id: IDP_ENTITY_ID.to_string(),
+            metadata: IDP_METADATA.to_string(),
+            email_attribute: EMAIL_ATTRIBUTE.to_string(),
+            subject_attribute: None,
+            email_domains: email_domains.iter().map(ToString::to_string).collect(),
+            created_at: Utc::now(),
+        }
+    }
+
+    fn identity(
+        provider: &SamlProvider,
+        response: &TestResponse,
+    ) -> Result<ExternalIdentity, Status> {
+        let sp = service_provider().for_idp(provider).unwrap();
+        get_saml_identity(&sp, provider, &response.encode(), REQUEST_ID)
+    }
+
+    #[test]
+    fn signed_response() {
+        let identity = identity(&provider(&["example.com"]), &TestResponse::default()).unwrap();
+
+        assert_eq!(identity.subject, "alice");
+        assert_eq!(identity.email.as_deref(), Some("alice@example.com"));
+        assert!(identity.email_verified);
+    }
+
+    #[test]
+    fn email_unverified_by_default() {
+        let identity = identity(&provider(&[]), &TestResponse::default()).unwrap();
+
+        assert_eq!(identity.email.as_deref(), Some("alice@example.com"));
+        assert!(!identity.email_verified);
+    }
+
+    #[test]
+    fn email_of_other_domain_unverified() {
+        let provider = provider(&["example.com"]);
+
+        for email in [
+            "mallory@example.org",
+            "mallory@evil.example.com",
+            "example.com",
+        ] {
+            let response = TestResponse {
+                email,
+                ..Default::default()
+            };
+            assert!(!identity(&provider, &response).unwrap().email_verified);
+        }
+    }
+
+    #[test]
+    fn unsigned_response() {
+        let response = TestResponse {
+            signing_key: None,
+            ..Default::default()
+        };
+        let err = identity(&provider(&["example.com"]), &response).unwrap_err();
+
+        assert_eq!(err.code(), Code::InvalidArgument);
+    }
+
+    #[test]
+    fn response_signed_by_other_key() {
+        let response = TestResponse {
+            signing_key: Some(OTHER_KEY),
+            ..Default::default()
+        };
+        let err = identity(&provider(&["example.com"]), &response).unwrap_err();
+
+        assert_eq!(err.code(), Code::InvalidArgument);
+    }
+
+    #[test]
+    fn expired_response() {
+        let response = TestResponse {
+            issued_at: Utc::now() - TimeDelta::hours(1),
+            ..Default::default()
+        };
+        let err = identity(&provider(&["example.com"]), &response).unwrap_err();
+
+        assert_eq!(err.code(), Code::InvalidArgument);
+    }
+
+    #[test]
+    fn wrong_audience() {
+        let response = TestResponse {
+            audience: "https://other-sp.example.com/saml/metadata",
+            ..Default::default()
+        };
+        let err = identity(&provider(&["example.com"]), &response).unwrap_err();
+
+        assert_eq!(err.code(), Code::InvalidArgument);
+    }
+
+    #[test]
+    fn response_to_other_request() {
+        let response = TestResponse {
+            in_response_to: "id-0000000000000000",
+            ..Default::default()
+        };
+        let err = identity(&provider(&["example.com"]), &response).unwrap_err();
+
+        assert_eq!(err.code(), Code::InvalidArgument);
+    }
+}
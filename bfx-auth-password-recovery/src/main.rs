@@ -1,4 +1,7 @@
 mod methods;
+#[cfg(test)]
+mod test_utils;
+mod token;
 
 use bfx_core::logging::setup_logging;
 use bfx_core::service::client::require_router;
@@ -7,9 +10,11 @@ use bfx_core::service::environment::require_env;
 use bfx_core::service::start_service;
 use bfx_proto::auth::password_recovery_server::{PasswordRecovery, PasswordRecoveryServer};
 use bfx_proto::auth::{
-    CheckPasswordResetReply, CheckPasswordResetTokenRequest, RequestPasswordRecoveryReply,
+    CheckPasswordResetReply, CheckPasswordResetTokenRequest, InvalidatePasswordResetRequestsReply,
+    InvalidatePasswordResetRequestsRequest, RequestPasswordRecoveryReply,
     RequestPasswordRecoveryRequest, ResetPasswordReply, ResetPasswordRequest,
 };
+use std::time::Duration;
 use tonic::transport::Channel;
 use tonic::{Request, Response, Status};
 
@@ -21,6 +26,12 @@ async fn main() -> anyhow::Result<()> {
         db: require_db().await?,
         router: require_router()?,
         frontend_root: require_env("FRONTEND_ROOT")?,
+        reset_token_lifetime: Duration::from_hours(
+            require_env("PASSWORD_RESET_TOKEN_LIFETIME_HOURS")
+                .map_or(Ok(DEFAULT_RESET_TOKEN_LIFETIME_HOURS), |hours| {
+                    hours.parse()
+                })?,
+        ),
     };
 
     start_service(PasswordRecoveryServer::new(service)).await?;
@@ -28,10 +39,14 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
+const DEFAULT_RESET_TOKEN_LIFETIME_HOURS: u64 = 24;
+
+#[derive(Clone)]
 struct PasswordRecoveryService {
     db: Db,
     router: Channel,
     frontend_root: String,
+    reset_token_lifetime: Duration,
 }
 
 #[tonic::async_trait]
@@ -56,4 +71,11 @@ impl PasswordRecovery for PasswordRecoveryService {
     ) -> Result<Response<ResetPasswordReply>, Status> {
         self.reset_password(request).await
     }
+
+    async fn invalidate_password_reset_requests(
+        &self,
+        request: Request<InvalidatePasswordResetRequestsRequest>,
+    ) -> Result<Response<InvalidatePasswordResetRequestsReply>, Status> {
+        self.invalidate_password_reset_requests(request).await
+    }
 }
@@ -1,8 +1,9 @@
 use crate::PasswordRecoveryService;
+use crate::token::hash_reset_token;
 use bfx_core::status::{ErrorCode, StatusExt};
 use bfx_proto::auth::{CheckPasswordResetReply, CheckPasswordResetTokenRequest};
 use bfx_proto::profile::profile_client::ProfileClient;
-use chrono::{TimeDelta, Utc};
+use chrono::Utc;
 use tonic::{Code, Request, Response, Status};
 
 impl PasswordRecoveryService {
@@ -12,16 +13,27 @@ impl PasswordRecoveryService {
     ) -> Result<Response<CheckPasswordResetReply>, Status> {
         let request = request.into_inner();
 
-        let mut tx = self.db.begin().await.map_err(Status::db)?;
+        let user_id = self.find_valid_reset_request(&request.token).await?;
 
+        // get the profile to return user's username
+        let mut profile = ProfileClient::new(self.router.clone());
+        let username = profile
+            .get_profile_by_id(user_id)
+            .await?
+            .map(|profile| profile.username);
+
+        Ok(Response::new(CheckPasswordResetReply { username }))
+    }
+
+    /// Find the user of a password reset token, if it's still usable
+    pub(crate) async fn find_valid_reset_request(&self, token: &str) -> Result<i64, Status> {
         let request = sqlx::query!(
             "select *
              from auth_password_recovery.password_reset_requests
-             where token = $1
-             for update",
-            request.token
+             where token_hash = $1",
+            hash_reset_token(token)
         )
-        .fetch_optional(&mut *tx)
+        .fetch_optional(&self.db)
         .await
         .map_err(Status::db)?
         .ok_or_else(|| Status::coded(Code::NotFound, ErrorCode::RecoveryTokenNotFound))?;
@@ -34,23 +46,83 @@ impl PasswordRecoveryService {
             ));
         }
 
-        // check expiration
-        if Utc::now().signed_duration_since(request.created_at) > TimeDelta::hours(24) {
+        // check expiration, and if a newer request or a password change replaced it
+        if request.expires_at < Utc::now() || request.invalidated_at.is_some() {
             return Err(Status::coded(
                 Code::InvalidArgument,
                 ErrorCode::RecoveryTokenExpired,
             ));
         }
 
-        // get the profile to return user's username
-        let mut profile = ProfileClient::new(self.router.clone());
-        let username = profile
-            .get_profile_by_id(request.user_id)
-            .await?
-            .map(|profile| profile.username);
+        Ok(request.user_id)
+    }
+}
 
-        tx.commit().await.map_err(Status::db)?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bfx_core::service::database::Db;
 
-        Ok(Response::new(CheckPasswordResetReply { username }))
+    #[sqlx::test(migrations = "../migrations")]
+    async fn find_by_token(db: Db) {
+        let service = PasswordRecoveryService::for_tests(db);
+        let token = service.create_test_reset_request(1).await;
+
+        assert_eq!(service.find_valid_reset_request(&token).await.unwrap(), 1);
+
+        // only the hash is stored
+        let stored = sqlx::query_scalar::<_, Vec<u8>>(
+            "select token_hash from auth_password_recovery.password_reset_requests",
+        )
+        .fetch_one(&service.db)
+        .await
+        .unwrap();
+        assert_eq!(stored, hash_reset_token(&token));
+        assert_ne!(stored, token.as_bytes());
+
+        for wrong_token in ["", "unknown", &token.to_uppercase()] {
+            let status = service
+                .find_valid_reset_request(wrong_token)
+                .await
+                .unwrap_err();
+            assert_eq!(
+                status.to_error_code(),
+                Some(ErrorCode::RecoveryTokenNotFound)
+            );
+        }
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn used_and_expired_tokens(db: Db) {
+        let service = PasswordRecoveryService::for_tests(db);
+        let used = service.create_test_reset_request(1).await;
+        let expired = service.create_test_reset_request(2).await;
+
+        for (token, change) in [
+            (&used, "used_at = now()"),
+            (&expired, "expires_at = now() - interval '1 second'"),
+        ] {
+            sqlx::query(&format!(
+                "update auth_password_recovery.password_reset_requests
+                 set {change}
+                 where token_hash = $1"
+            ))
+            .bind(hash_reset_token(token))
+            .execute(&service.db)
+            .await
+            .unwrap();
+        }
+
+        let status = service.find_valid_reset_request(&used).await.unwrap_err();
+        assert_eq!(status.to_error_code(), Some(ErrorCode::RecoveryTokenUsed));
+
+        let status = service
+            .find_valid_reset_request(&expired)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            status.to_error_code(),
+            Some(ErrorCode::RecoveryTokenExpired)
+        );
     }
 }
@@ -0,0 +1,127 @@
+use crate::PasswordRecoveryService;
+use bfx_core::status::StatusExt;
+use bfx_proto::auth::{
+    InvalidatePasswordResetRequestsReply, InvalidatePasswordResetRequestsRequest,
+};
+use tonic::{Request, Response, Status};
+
+impl PasswordRecoveryService {
+    /// Invalidate all pending password reset requests of a user
+    ///
+    /// # Errors
+    ///
+    /// - Miscellaneous internal errors
+    pub async fn invalidate_password_reset_requests(
+        &self,
+        request: Request<InvalidatePasswordResetRequestsRequest>,
+    ) -> Result<Response<InvalidatePasswordResetRequestsReply>, Status> {
+        let request = request.into_inner();
+
+        // `reset_password` keeps the request it uses locked while changing the password,
+        // which ends up here. it's marked as used anyway, so skip it instead of deadlocking
+        sqlx::query!(
+            "update auth_password_recovery.password_reset_requests
+             set invalidated_at = now()
+             where id in (
+                 select id
+                 from auth_password_recovery.password_reset_requests
+                 where user_id = $1 and used_at is null and invalidated_at is null
+                 for update skip locked
+             )",
+            request.user_id
+        )
+        .execute(&self.db)
+        .await
+        .map_err(Status::db)?;
+
+        Ok(Response::new(InvalidatePasswordResetRequestsReply {}))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token::hash_reset_token;
+    use bfx_core::service::database::Db;
+    use bfx_proto::UserContext;
+    use bfx_proto::auth::{CheckPasswordResetTokenRequest, ResetPasswordRequest};
+    use tonic::Code;
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn invalidated_tokens_cant_be_used(db: Db) {
+        let service = PasswordRecoveryService::for_tests(db);
+        let token = service.create_test_reset_request(1).await;
+        let other_user_token = service.create_test_reset_request(2).await;
+
+        // what `change_password` does
+        service
+            .invalidate_password_reset_requests(Request::new(
+                InvalidatePasswordResetRequestsRequest { user_id: 1 },
+            ))
+            .await
+            .unwrap();
+
+        let status = service
+            .check_password_reset_token(Request::new(CheckPasswordResetTokenRequest {
+                token: token.clone(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+
+        let result = service
+            .reset_password(Request::new(ResetPasswordRequest {
+                token,
+                new_password: "correct horse battery staple".into(),
+                user_context: Some(UserContext::default()),
+            }))
+            .await;
+        assert!(result.is_err());
+
+        let used = sqlx::query_scalar::<_, i64>(
+            "select count(*) from auth_password_recovery.password_reset_requests
+             where used_at is not null",
+        )
+        .fetch_one(&service.db)
+        .await
+        .unwrap();
+        assert_eq!(used, 0);
+
+        // other users' requests stay valid
+        let valid = sqlx::query_scalar::<_, i64>(
+            "select count(*) from auth_password_recovery.password_reset_requests
+             where token_hash = $1 and invalidated_at is null",
+        )
+        .bind(hash_reset_token(&other_user_token))
+        .fetch_one(&service.db)
+        .await
+        .unwrap();
+        assert_eq!(valid, 1);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn skip_request_being_used(db: Db) {
+        let service = PasswordRecoveryService::for_tests(db);
+        service.create_test_reset_request(1).await;
+
+        // `reset_password` holds this lock while changing the password
+        let mut tx = service.db.begin().await.unwrap();
+        sqlx::query(
+            "select id from auth_password_recovery.password_reset_requests
+             where user_id = 1
+             for update",
+        )
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+
+        service
+            .invalidate_password_reset_requests(Request::new(
+                InvalidatePasswordResetRequestsRequest { user_id: 1 },
+            ))
+            .await
+            .unwrap();
+
+        tx.rollback().await.unwrap();
+    }
+}
@@ -1,3 +1,4 @@
 mod check_password_reset_token;
+mod invalidate_password_reset_requests;
 mod request_password_recovery;
 mod reset_password;
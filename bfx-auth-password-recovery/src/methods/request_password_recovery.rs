@@ -1,88 +1,424 @@
 use crate::PasswordRecoveryService;
+use crate::token::generate_reset_token;
+use bfx_core::log_if_error::LogIfErrorExt;
 use bfx_core::status::{ErrorCode, StatusExt};
 use bfx_proto::auth::auth_core_client::AuthCoreClient;
 use bfx_proto::auth::{
     GetUserByEmailRequest, RequestPasswordRecoveryReply, RequestPasswordRecoveryRequest,
 };
 use bfx_proto::notification::SendNotificationRequest;
+use bfx_proto::notification::email::notification_email_client::NotificationEmailClient;
+use bfx_proto::notification::email::{CheckValidEmailRequest, SendEmailRequest};
 use bfx_proto::notification::notification_client::NotificationClient;
 use bfx_proto::param_map;
+use bfx_proto::translation::translation_client::TranslationClient;
+use chrono::Utc;
+use std::collections::HashMap;
 use tonic::{Code, Request, Response, Status};
+use tracing::info;
+
+// attempts are counted whether an email is sent or not
+const MAX_ATTEMPTS_PER_IP_PER_HOUR: i64 = 10;
+const MAX_ATTEMPTS_PER_EMAIL_PER_HOUR: i64 = 3;
 
 impl PasswordRecoveryService {
+    /// Request a password reset link for an email address
+    ///
+    /// This always succeeds, so it can't be used to check if an account exists.
+    /// The reset link is sent if the address belongs to a user, otherwise the address
+    /// gets a notice (at most once a day) that someone tried to reset a password for it.
+    /// Throttled requests are silently dropped.
+    ///
+    /// # Errors
+    ///
+    /// - Miscellaneous internal errors
     pub async fn request_password_recovery(
         &self,
         request: Request<RequestPasswordRecoveryRequest>,
     ) -> Result<Response<RequestPasswordRecoveryReply>, Status> {
         let request = request.into_inner();
 
-        let RequestPasswordRecoveryRequest { email, .. } = request;
+        let RequestPasswordRecoveryRequest {
+            email,
+            user_context,
+        } = request;
+        let user_context = user_context
+            .ok_or_else(|| Status::coded(Code::InvalidArgument, ErrorCode::Internal))?;
+
+        let normalized_email = email.trim().to_lowercase();
+
+        let Some(attempt_id) = self
+            .record_recovery_attempt(&normalized_email, &user_context.ip)
+            .await?
+        else {
+            info!(ip = user_context.ip, "password recovery throttled");
+            return Ok(Response::new(RequestPasswordRecoveryReply {}));
+        };
+
+        // finish in the background, so the response time doesn't depend on the account existing
+        let service = self.clone();
+        tokio::spawn(async move {
+            service
+                .send_password_recovery_email(
+                    attempt_id,
+                    email,
+                    normalized_email,
+                    user_context.lang_id,
+                )
+                .await
+                .log_if_error("sending password recovery email");
+        });
+
+        Ok(Response::new(RequestPasswordRecoveryReply {}))
+    }
+
+    /// Record an attempt, returns its id or `None` if the IP or email are throttled
+    async fn record_recovery_attempt(
+        &self,
+        normalized_email: &str,
+        ip: &str,
+    ) -> Result<Option<i64>, Status> {
+        let attempts = sqlx::query!(
+            "select count(*) filter (where ip = $1) as \"by_ip!\",
+                    count(*) filter (where email = $2) as \"by_email!\"
+             from auth_password_recovery.recovery_attempts
+             where created_at > (now() - interval '1 hour')",
+            ip,
+            normalized_email
+        )
+        .fetch_one(&self.db)
+        .await
+        .map_err(Status::db)?;
+
+        sqlx::query!(
+            "delete from auth_password_recovery.recovery_attempts
+             where created_at < (now() - interval '1 day')"
+        )
+        .execute(&self.db)
+        .await
+        .map_err(Status::db)?;
+
+        let attempt_id = sqlx::query_scalar!(
+            "insert into auth_password_recovery.recovery_attempts (email, ip)
+             values ($1, $2)
+             returning id",
+            normalized_email,
+            ip
+        )
+        .fetch_one(&self.db)
+        .await
+        .map_err(Status::db)?;
+
+        if attempts.by_ip >= MAX_ATTEMPTS_PER_IP_PER_HOUR
+            || attempts.by_email >= MAX_ATTEMPTS_PER_EMAIL_PER_HOUR
+        {
+            return Ok(None);
+        }
+
+        Ok(Some(attempt_id))
+    }
 
+    async fn send_password_recovery_email(
+        &self,
+        attempt_id: i64,
+        email: String,
+        normalized_email: String,
+        lang_id: String,
+    ) -> Result<(), Status> {
         let mut auth_core = AuthCoreClient::new(self.router.clone());
 
         let user = auth_core
-            .get_user_by_email(GetUserByEmailRequest { email })
+            .get_user_by_email(GetUserByEmailRequest {
+                email: email.clone(),
+            })
             .await?
             .into_inner()
-            .user
-            .ok_or_else(|| Status::coded(Code::NotFound, ErrorCode::UserNotFound))?;
+            .user;
+
+        let Some(user) = user else {
+            return self
+                .send_unknown_address_notice(attempt_id, email, normalized_email, lang_id)
+                .await;
+        };
 
         let mut tx = self.db.begin().await.map_err(Status::db)?;
 
+        let Some(token) = self.replace_reset_request(&mut tx, user.id).await? else {
+            tx.rollback().await.map_err(Status::db)?;
+            return Ok(());
+        };
+
+        // send the email
+        let mut notification = NotificationClient::new(self.router.clone());
+
+        notification
+            .send_notification(SendNotificationRequest {
+                user_id: user.id,
+                user_override: None,
+                definition_id: "password_reset".to_string(),
+                params: param_map! {
+                    "reset_url" => format!(
+                        "{}/auth/reset-password?token={token}",
+                        self.frontend_root
+                    ),
+                    "expires_in_hours" => u32::try_from(self.reset_token_lifetime.as_secs() / 3600)
+                        .unwrap_or(u32::MAX),
+                },
+                send_at: None,
+            })
+            .await?;
+
+        Self::mark_email_sent(&mut tx, attempt_id).await?;
+
+        tx.commit().await.map_err(Status::db)?;
+
+        Ok(())
+    }
+
+    /// Create a password reset request for a user, replacing the pending ones
+    ///
+    /// Returns its token, or `None` if the user requested one in the last hour.
+    async fn replace_reset_request(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: i64,
+    ) -> Result<Option<String>, Status> {
         // check if the user is already requesting a password reset
         // (1-hour timeout)
         let already_requested = sqlx::query_scalar!(
             "select 1 as \"found!\"
              from auth_password_recovery.password_reset_requests
-             where user_id = $1 and used_at is null
+             where user_id = $1 and used_at is null and invalidated_at is null
                    and created_at > (now() - interval '1 hour')",
-            user.id
+            user_id
         )
-        .fetch_optional(&mut *tx)
+        .fetch_optional(&mut **tx)
         .await
         .map_err(Status::db)?
         .is_some();
 
         if already_requested {
-            tx.rollback().await.map_err(Status::db)?;
-            return Err(Status::coded(
-                Code::ResourceExhausted,
-                ErrorCode::TooManyRequests,
-            ));
+            return Ok(None);
         }
 
+        // a newer request replaces the pending ones
+        sqlx::query!(
+            "update auth_password_recovery.password_reset_requests
+             set invalidated_at = now()
+             where user_id = $1 and used_at is null and invalidated_at is null",
+            user_id
+        )
+        .execute(&mut **tx)
+        .await
+        .map_err(Status::db)?;
+
         // create a new password reset request
-        let password_reset_request = sqlx::query!(
+        let (token, token_hash) = generate_reset_token();
+
+        sqlx::query!(
             "insert into auth_password_recovery.password_reset_requests
-             (user_id, token)
-             values ($1, $2)
-             returning *",
-            user.id,
-            nanoid::nanoid!(32)
+             (user_id, token_hash, expires_at)
+             values ($1, $2, $3)",
+            user_id,
+            token_hash,
+            Utc::now() + self.reset_token_lifetime,
         )
-        .fetch_one(&mut *tx)
+        .execute(&mut **tx)
         .await
         .map_err(Status::db)?;
 
-        // send the email
-        let mut notification = NotificationClient::new(self.router.clone());
+        Ok(Some(token))
+    }
 
-        notification
-            .send_notification(SendNotificationRequest {
-                user_id: user.id,
-                user_override: None,
-                definition: include_str!("../../notifications/password_reset.yml").to_string(),
-                params: param_map! {
-                    "reset_url" => format!(
-                        "{}/auth/reset-password?token={}",
-                        self.frontend_root, password_reset_request.token
-                    )
-                },
+    /// Tell the owner of an address without an account that someone tried to reset a password
+    async fn send_unknown_address_notice(
+        &self,
+        attempt_id: i64,
+        email: String,
+        normalized_email: String,
+        lang_id: String,
+    ) -> Result<(), Status> {
+        let mut tx = self.db.begin().await.map_err(Status::db)?;
+
+        // at most one notice per day, these are unsolicited
+        let already_sent = sqlx::query_scalar!(
+            "select 1 as \"found!\"
+             from auth_password_recovery.recovery_attempts
+             where email = $1 and email_sent
+                   and created_at > (now() - interval '1 day')",
+            normalized_email
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(Status::db)?
+        .is_some();
+
+        if already_sent {
+            tx.rollback().await.map_err(Status::db)?;
+            return Ok(());
+        }
+
+        let mut notification_email = NotificationEmailClient::new(self.router.clone());
+
+        // don't send to blocked or undeliverable addresses
+        let valid = notification_email
+            .check_valid_email(CheckValidEmailRequest {
+                email: email.clone(),
+            })
+            .await?
+            .into_inner()
+            .valid;
+        if !valid {
+            tx.rollback().await.map_err(Status::db)?;
+            return Ok(());
+        }
+
+        let mut translation = TranslationClient::new(self.router.clone());
+
+        let subject = translation
+            .render_template_ext(
+                "{{ t(\"email-reset-password-unknown-subject\") }}",
+                lang_id.clone(),
+                HashMap::new(),
+            )
+            .await?;
+        let body = translation
+            .render_template_ext(
+                include_str!("../../templates/password_reset_unknown_address.html"),
+                lang_id,
+                HashMap::new(),
+            )
+            .await?;
+
+        notification_email
+            .send_email(SendEmailRequest {
+                to: email,
+                to_name: None,
+                subject,
+                body_html: body,
             })
             .await?;
 
+        Self::mark_email_sent(&mut tx, attempt_id).await?;
+
         tx.commit().await.map_err(Status::db)?;
 
-        Ok(Response::new(RequestPasswordRecoveryReply {}))
+        Ok(())
+    }
+
+    async fn mark_email_sent(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        attempt_id: i64,
+    ) -> Result<(), Status> {
+        sqlx::query!(
+            "update auth_password_recovery.recovery_attempts
+             set email_sent = true
+             where id = $1",
+            attempt_id
+        )
+        .execute(&mut **tx)
+        .await
+        .map_err(Status::db)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bfx_core::service::database::Db;
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn throttle_by_email(db: Db) {
+        let service = PasswordRecoveryService::for_tests(db);
+
+        for i in 0..MAX_ATTEMPTS_PER_EMAIL_PER_HOUR {
+            let ip = format!("192.0.2.{i}");
+            let attempt = service.record_recovery_attempt("a@example.com", &ip).await;
+            assert!(attempt.unwrap().is_some());
+        }
+
+        let attempt = service
+            .record_recovery_attempt("a@example.com", "192.0.2.100")
+            .await;
+        assert!(attempt.unwrap().is_none());
+
+        // other addresses aren't affected
+        let attempt = service
+            .record_recovery_attempt("b@example.com", "192.0.2.100")
+            .await;
+        assert!(attempt.unwrap().is_some());
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn throttle_by_ip(db: Db) {
+        let service = PasswordRecoveryService::for_tests(db);
+
+        for i in 0..MAX_ATTEMPTS_PER_IP_PER_HOUR {
+            let email = format!("{i}@example.com");
+            let attempt = service.record_recovery_attempt(&email, "192.0.2.1").await;
+            assert!(attempt.unwrap().is_some());
+        }
+
+        let attempt = service
+            .record_recovery_attempt("a@example.com", "192.0.2.1")
+            .await;
+        assert!(attempt.unwrap().is_none());
+
+        let attempt = service
+            .record_recovery_attempt("a@example.com", "192.0.2.2")
+            .await;
+        assert!(attempt.unwrap().is_some());
+
+        // throttled attempts count too, and old ones don't
+        sqlx::query(
+            "update auth_password_recovery.recovery_attempts
+             set created_at = now() - interval '2 hours'
+             where ip = '192.0.2.1' and email <> 'a@example.com'",
+        )
+        .execute(&service.db)
+        .await
+        .unwrap();
+
+        let attempt = service
+            .record_recovery_attempt("c@example.com", "192.0.2.1")
+            .await;
+        assert!(attempt.unwrap().is_some());
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn replace_older_reset_requests(db: Db) {
+        let service = PasswordRecoveryService::for_tests(db);
+        let old_token = service.create_test_reset_request(1).await;
+
+        // a pending request from the last hour is kept
+        let mut tx = service.db.begin().await.unwrap();
+        let token = service.replace_reset_request(&mut tx, 1).await.unwrap();
+        tx.commit().await.unwrap();
+        assert!(token.is_none());
+
+        sqlx::query(
+            "update auth_password_recovery.password_reset_requests
+             set created_at = now() - interval '2 hours'",
+        )
+        .execute(&service.db)
+        .await
+        .unwrap();
+
+        let mut tx = service.db.begin().await.unwrap();
+        let token = service.replace_reset_request(&mut tx, 1).await.unwrap();
+        tx.commit().await.unwrap();
+        let token = token.unwrap();
+
+        let status = service
+            .find_valid_reset_request(&old_token)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            status.to_error_code(),
+            Some(ErrorCode::RecoveryTokenExpired)
+        );
+        assert_eq!(service.find_valid_reset_request(&token).await.unwrap(), 1);
     }
 }
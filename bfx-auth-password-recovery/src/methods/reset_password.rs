@@ -1,4 +1,5 @@
 use crate::PasswordRecoveryService;
+use crate::token::hash_reset_token;
 use bfx_core::status::{ErrorCode, StatusExt};
 use bfx_proto::auth::auth_core_client::AuthCoreClient;
 use bfx_proto::auth::{ChangePasswordRequest, ResetPasswordReply, ResetPasswordRequest};
@@ -20,9 +21,10 @@ impl PasswordRecoveryService {
         let token = sqlx::query!(
             "update auth_password_recovery.password_reset_requests
              set used_at = now()
-             where token = $1 and used_at is null and created_at > (now() - interval '24 hours')
+             where token_hash = $1 and used_at is null and invalidated_at is null
+                   and expires_at > now()
              returning user_id",
-            request.token
+            hash_reset_token(&request.token)
         )
         .fetch_one(&mut *tx)
         .await
@@ -0,0 +1,37 @@
+use crate::token::generate_reset_token;
+use crate::{DEFAULT_RESET_TOKEN_LIFETIME_HOURS, PasswordRecoveryService};
+use bfx_core::service::database::Db;
+use chrono::Utc;
+use std::time::Duration;
+use tonic::transport::Channel;
+
+impl PasswordRecoveryService {
+    /// Make a service for tests, with placeholders for everything but the database
+    pub(crate) fn for_tests(db: Db) -> Self {
+        Self {
+            db,
+            router: Channel::from_static("http://localhost:1").connect_lazy(),
+            frontend_root: "https://bfx.example.com".into(),
+            reset_token_lifetime: Duration::from_hours(DEFAULT_RESET_TOKEN_LIFETIME_HOURS),
+        }
+    }
+
+    /// Add a pending password reset request, returns its token
+    pub(crate) async fn create_test_reset_request(&self, user_id: i64) -> String {
+        let (token, token_hash) = generate_reset_token();
+
+        sqlx::query(
+            "insert into auth_password_recovery.password_reset_requests
+             (user_id, token_hash, expires_at)
+             values ($1, $2, $3)",
+        )
+        .bind(user_id)
+        .bind(token_hash)
+        .bind(Utc::now() + self.reset_token_lifetime)
+        .execute(&self.db)
+        .await
+        .unwrap();
+
+        token
+    }
+}
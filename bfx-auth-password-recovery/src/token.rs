@@ -0,0 +1,14 @@
+use sha2::{Digest, Sha256};
+
+/// Generate a new password reset token and its hash
+///
+/// Only the hash is stored, the token itself is only sent to the user.
+pub fn generate_reset_token() -> (String, Vec<u8>) {
+    let token = nanoid::nanoid!(32);
+    let hash = hash_reset_token(&token);
+    (token, hash)
+}
+
+pub fn hash_reset_token(token: &str) -> Vec<u8> {
+    Sha256::digest(token.as_bytes()).to_vec()
+}
@@ -18,6 +18,8 @@ pub enum IdType {
     OAuthClient,
     Image,
     AuthSource,
+    OAuthGrant,
+    Notification,
 }
 
 #[derive(Clone)]
@@ -135,6 +137,43 @@ impl IdEncryptor {
 
         Ok(id)
     }
+
+    /// Encrypt an ID for a sector (e.g. the host of a third-party service)
+    ///
+    /// The same ID encrypts to unrelated values in different sectors,
+    /// so IDs given to one sector can't be correlated with other sectors.
+    #[must_use]
+    pub fn encrypt_sector_id(&self, sector: &str, id_type: IdType, id: i64) -> String {
+        self.for_sector(sector).encrypt_id(id_type, id)
+    }
+
+    /// Decrypt an ID encrypted with [`IdEncryptor::encrypt_sector_id`]
+    ///
+    /// # Errors
+    ///
+    /// See [`IdEncryptor::decrypt_id`]
+    pub fn decrypt_sector_id(
+        &self,
+        sector: &str,
+        id_type: IdType,
+        id: &str,
+    ) -> Result<i64, Box<Status>> {
+        self.for_sector(sector).decrypt_id(id_type, id)
+    }
+
+    /// Derive an encryptor with a separate key for a sector
+    #[allow(clippy::missing_panics_doc)]
+    fn for_sector(&self, sector: &str) -> Self {
+        let hk = Hkdf::<Sha256>::new(Some(sector.as_bytes()), &self.id_encryption_key);
+        let mut key = [0u8; 32];
+        // this will only panic if key.len() is *very* big, so .unwrap() is fine
+        hk.expand(b"sector-id-encryption-key", &mut key).unwrap();
+
+        Self {
+            id_encryption_key: key.into(),
+            nonce_salt: self.nonce_salt,
+        }
+    }
 }
 
 // utilities
@@ -146,24 +185,29 @@ impl IdEncryptor {
 /// - If the environment variable is not set
 /// - Miscellaneous internal errors
 pub fn require_id_encryptor() -> anyhow::Result<IdEncryptor> {
-    let id_encryption_key = require_id_encryption_key()?;
-    let nonce_salt = require_nonce_salt()?;
+    IdEncryptor::from_key(&require_env("ID_ENCRYPTION_KEY")?)
+}
 
-    Ok(IdEncryptor {
-        id_encryption_key,
-        nonce_salt,
-    })
+impl IdEncryptor {
+    /// Construct an [`IdEncryptor`] from a secret key
+    ///
+    /// # Errors
+    ///
+    /// - Miscellaneous internal errors
+    pub fn from_key(key: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            id_encryption_key: derive_id_encryption_key(key)?,
+            nonce_salt: derive_nonce_salt(key)?,
+        })
+    }
 }
 
-/// Derive the ID encryption key from the `ID_ENCRYPTION_KEY` environment variable
+/// Derive the ID encryption key from the secret key
 ///
 /// # Errors
 ///
-/// - If the environment variable is not set
 /// - Miscellaneous internal errors
-fn require_id_encryption_key() -> anyhow::Result<aes_gcm_siv::Key<Aes256GcmSiv>> {
-    let key = require_env("ID_ENCRYPTION_KEY")?;
-
+fn derive_id_encryption_key(key: &str) -> anyhow::Result<aes_gcm_siv::Key<Aes256GcmSiv>> {
     let hk = Hkdf::<Sha256>::new(None, key.as_bytes());
     let mut key = [0u8; 32];
     hk.expand(b"id-encryption-key", &mut key)
@@ -172,16 +216,13 @@ fn require_id_encryption_key() -> anyhow::Result<aes_gcm_siv::Key<Aes256GcmSiv>>
     Ok(key.into())
 }
 
-/// Derive the nonce salt used for ID encryption from the `ID_ENCRYPTION_KEY` environment variable
+/// Derive the nonce salt used for ID encryption from the secret key
 ///
 /// # Errors
 ///
-/// - If the environment variable is not set
 /// - Miscellaneous internal errors
-fn require_nonce_salt() -> anyhow::Result<[u8; 16]> {
-    let salt = require_env("ID_ENCRYPTION_KEY")?;
-
-    let hk = Hkdf::<Sha256>::new(None, salt.as_bytes());
+fn derive_nonce_salt(key: &str) -> anyhow::Result<[u8; 16]> {
+    let hk = Hkdf::<Sha256>::new(None, key.as_bytes());
     let mut nonce_salt = [0u8; 16];
     hk.expand(b"nonce-salt", &mut nonce_salt)
         .map_err(|err| anyhow::anyhow!("invalid ID_ENCRYPTION_KEY: {err:?}"))?;
@@ -102,6 +102,10 @@ pub enum ErrorCode {
     RecoveryTokenExpired,
     InvalidId,
     InvalidNotificationDefinition,
+    InvalidNotificationParams,
+    NotificationNotFound,
+    MandatoryNotificationCategory,
+    InvalidTimezone,
     NoTemplateMatched,
     UnknownProvider,
     FlowNotFound,
@@ -114,4 +118,8 @@ pub enum ErrorCode {
     ClientNotFound,
     InvalidRedirectUri,
     InvalidScope,
+    UserCodeNotFound,
+    GrantNotFound,
+    InvalidDpopProof,
+    InsufficientScope,
 }
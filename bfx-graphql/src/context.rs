@@ -3,7 +3,7 @@ use async_graphql::Context;
 use bfx_core::service::id_encryption::IdEncryptor;
 use bfx_core::status::{ErrorCode, StatusExt};
 use bfx_proto::UserContext;
-use bfx_proto::auth::{GetUserByTokenReply, PermissionLevel, User};
+use bfx_proto::auth::{GetUserByTokenReply, PermissionLevel, Session, User};
 use bfx_proto::factory::BuildableService;
 use std::sync::Arc;
 use tonic::transport::Channel;
@@ -19,6 +19,18 @@ pub struct LocalContext {
     pub user_context: UserContext,
     // basically user+session
     pub user: Option<GetUserByTokenReply>,
+    // set instead of `user` if the request is authorized by a third-party OAuth token
+    pub oauth: Option<OAuthAccess>,
+}
+
+/// Access granted to a third-party OAuth client by an access token
+pub struct OAuthAccess {
+    /// Not set for client-only tokens (`client_credentials` grant)
+    pub user: Option<User>,
+    pub client_id: i64,
+    pub scopes: Vec<String>,
+    /// `sub` of the user as seen by the client, which must be used instead of the user's ID
+    pub subject: Option<String>,
 }
 
 pub trait ContextExt {
@@ -32,6 +44,37 @@ pub trait ContextExt {
     /// Get the user that authorized the request
     fn user(&self) -> Option<&User>;
 
+    /// Get the session that authorized the request
+    fn session(&self) -> Option<&Session>;
+
+    /// Get the user that authorized the request, also via a third-party OAuth token
+    ///
+    /// Only use this in fields guarded by [`ScopeGuard`](crate::guards::ScopeGuard),
+    /// everywhere else OAuth tokens must not act as the user.
+    fn scoped_user(&self) -> Option<&User>;
+
+    /// Get the user that authorized the request, also via a third-party OAuth token,
+    /// or throw an error
+    ///
+    /// Only use this in fields guarded by [`ScopeGuard`](crate::guards::ScopeGuard).
+    ///
+    /// # Errors
+    ///
+    /// - If the user is not logged in
+    fn require_scoped_user(&self) -> Result<&User, Box<Status>>;
+
+    /// Check if the request is allowed to use `scope`
+    ///
+    /// First-party sessions and unauthenticated requests have every scope,
+    /// third-party OAuth tokens only the ones the user granted.
+    fn has_scope(&self, scope: &str) -> bool;
+
+    /// Get the `sub` that the third-party OAuth client authorizing the request knows
+    /// the user `user_id` by
+    ///
+    /// `None` if the request isn't authorized by an OAuth token of that user.
+    fn oauth_subject(&self, user_id: i64) -> Option<&str>;
+
     /// Get request metadata (IP, user agent, etc.)
     fn user_context(&self) -> &UserContext;
 
@@ -42,6 +85,27 @@ pub trait ContextExt {
     /// - If the user is not logged in
     /// - If the user is not an admin and `user_id` doesn't match the logged-in user's ID
     fn require_self_or_admin(&self, user_id: i64) -> Result<(), RespError>;
+
+    /// Like [`ContextExt::require_self_or_admin`], but the user may also be authorized
+    /// by a third-party OAuth token
+    ///
+    /// Only use this in fields guarded by [`ScopeGuard`](crate::guards::ScopeGuard).
+    ///
+    /// # Errors
+    ///
+    /// - If the user is not logged in
+    /// - If the user is not an admin and `user_id` doesn't match the authorized user's ID
+    fn require_scoped_self_or_admin(&self, user_id: i64) -> Result<(), RespError>;
+
+    /// Check if the user is an admin
+    fn is_admin(&self) -> bool;
+
+    /// Check if the user is an admin or throw an error
+    ///
+    /// # Errors
+    ///
+    /// - If the user is not logged in or not an admin
+    fn require_admin(&self) -> Result<(), RespError>;
 }
 
 impl ContextExt for Context<'_> {
@@ -56,6 +120,41 @@ impl ContextExt for Context<'_> {
         req.user.as_ref().and_then(|user| user.user.as_ref())
     }
 
+    fn session(&self) -> Option<&Session> {
+        let req = self.data_unchecked::<LocalContext>();
+
+        req.user.as_ref().and_then(|user| user.session.as_ref())
+    }
+
+    fn scoped_user(&self) -> Option<&User> {
+        let req = self.data_unchecked::<LocalContext>();
+
+        self.user()
+            .or_else(|| req.oauth.as_ref().and_then(|oauth| oauth.user.as_ref()))
+    }
+
+    fn require_scoped_user(&self) -> Result<&User, Box<Status>> {
+        self.scoped_user()
+            .ok_or_else(|| Status::coded(Code::Unauthenticated, ErrorCode::AccessDenied).into())
+    }
+
+    fn has_scope(&self, scope: &str) -> bool {
+        let req = self.data_unchecked::<LocalContext>();
+
+        req.oauth
+            .as_ref()
+            .is_none_or(|oauth| oauth.scopes.iter().any(|s| s == scope))
+    }
+
+    fn oauth_subject(&self, user_id: i64) -> Option<&str> {
+        let req = self.data_unchecked::<LocalContext>();
+
+        req.oauth
+            .as_ref()
+            .filter(|oauth| oauth.user.as_ref().is_some_and(|user| user.id == user_id))
+            .and_then(|oauth| oauth.subject.as_deref())
+    }
+
     fn user_context(&self) -> &UserContext {
         let req = self.data_unchecked::<LocalContext>();
 
@@ -68,10 +167,28 @@ impl ContextExt for Context<'_> {
             return Ok(());
         }
 
+        self.require_admin()
+    }
+
+    fn require_scoped_self_or_admin(&self, user_id: i64) -> Result<(), RespError> {
+        let auth_user_id = self.scoped_user().map(|user| user.id);
+        if auth_user_id == Some(user_id) {
+            return Ok(());
+        }
+
+        self.require_admin()
+    }
+
+    fn is_admin(&self) -> bool {
         let permission_level = self
             .user()
             .map_or(PermissionLevel::User as i32, |user| user.permission_level);
-        if permission_level >= PermissionLevel::Admin.into() {
+
+        permission_level >= PermissionLevel::Admin.into()
+    }
+
+    fn require_admin(&self) -> Result<(), RespError> {
+        if self.is_admin() {
             return Ok(());
         }
 
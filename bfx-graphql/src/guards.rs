@@ -0,0 +1,34 @@
+use crate::context::ContextExt;
+use crate::error::RespError;
+use async_graphql::{Context, Guard};
+use bfx_core::status::{ErrorCode, StatusExt};
+use tonic::{Code, Status};
+
+/// Limit a field to third-party OAuth tokens with a scope
+///
+/// First-party sessions are not limited. Fields without this guard can't be used as the user
+/// with OAuth tokens, since [`ContextExt::user`] is only set for first-party sessions.
+pub struct ScopeGuard {
+    scope: &'static str,
+}
+
+impl ScopeGuard {
+    #[must_use]
+    pub const fn new(scope: &'static str) -> Self {
+        Self { scope }
+    }
+}
+
+impl Guard for ScopeGuard {
+    async fn check(&self, ctx: &Context<'_>) -> async_graphql::Result<()> {
+        if ctx.has_scope(self.scope) {
+            return Ok(());
+        }
+
+        Err(RespError::from(
+            Status::coded(Code::PermissionDenied, ErrorCode::InsufficientScope)
+                .with_details(&format!("missing scope `{}`", self.scope)),
+        )
+        .into())
+    }
+}
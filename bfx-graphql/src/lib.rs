@@ -1,6 +1,7 @@
 pub mod context;
 mod data_loaders;
 pub mod error;
+pub mod guards;
 pub mod id_encryption;
 pub mod language;
 pub mod models;
@@ -1,23 +1,30 @@
-use async_graphql::http::GraphiQLSource;
-use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
+use async_graphql::Data;
+use async_graphql::http::{ALL_WEBSOCKET_PROTOCOLS, GraphiQLSource};
+use async_graphql_axum::{GraphQLProtocol, GraphQLRequest, GraphQLResponse, GraphQLWebSocket};
+use axum::extract::WebSocketUpgrade;
 use axum::response::{Html, IntoResponse};
 use axum::routing::{get, post};
 use axum::{Extension, Router};
 use axum_client_ip::{ClientIp, ClientIpSource};
 use axum_extra::TypedHeader;
-use axum_extra::headers::authorization::Bearer;
-use axum_extra::headers::{Authorization, UserAgent};
+use axum_extra::headers::UserAgent;
 use bfx_core::logging::setup_logging;
 use bfx_core::service::client::require_router;
 use bfx_core::service::environment::require_env;
 use bfx_core::service::get_tcp_listener;
 use bfx_core::service::id_encryption::require_id_encryptor;
-use bfx_graphql::context::{GlobalContext, LocalContext};
+use bfx_graphql::context::{GlobalContext, LocalContext, OAuthAccess};
 use bfx_graphql::language::{AcceptLanguage, DEFAULT_LANGUAGE};
 use bfx_graphql::schema::GSchema;
 use bfx_graphql::services::auth_core::data_loaders::UserLoader;
+use bfx_graphql::services::auth_oauth::saml::{get_saml_metadata, saml_acs};
+use bfx_graphql::services::auth_oauth_provider::bearer_authorization::OAuthBearerToken;
+use bfx_graphql::services::auth_oauth_provider::device_authorization_endpoint::device_authorization_endpoint;
 use bfx_graphql::services::auth_oauth_provider::get_jwk_set::get_jwk_set;
 use bfx_graphql::services::auth_oauth_provider::get_openid_metadata::get_openid_metadata;
+use bfx_graphql::services::auth_oauth_provider::introspection_endpoint::introspection_endpoint;
+use bfx_graphql::services::auth_oauth_provider::pushed_authorization_request_endpoint::pushed_authorization_request_endpoint;
+use bfx_graphql::services::auth_oauth_provider::revocation_endpoint::revocation_endpoint;
 use bfx_graphql::services::auth_oauth_provider::token_endpoint::token_endpoint;
 use bfx_graphql::services::auth_oauth_provider::userinfo_endpoint::{
     userinfo_endpoint_get, userinfo_endpoint_post,
@@ -25,16 +32,24 @@ use bfx_graphql::services::auth_oauth_provider::userinfo_endpoint::{
 use bfx_graphql::services::image::data_loaders::ImageLoader;
 use bfx_graphql::services::profile::data_loaders::ProfileLoader;
 use bfx_proto::UserContext;
-use bfx_proto::auth::GetUserByTokenRequest;
 use bfx_proto::auth::auth_core_client::AuthCoreClient;
+use bfx_proto::auth::auth_o_auth_provider_client::AuthOAuthProviderClient;
+use bfx_proto::auth::{
+    GetAccessTokenRequest, GetUserByTokenReply, GetUserByTokenRequest, GetUsersByIdsRequest,
+};
 use ipnet::IpNet;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::str::FromStr;
 use std::sync::Arc;
 use tonic::Response;
 
 async fn graphiql() -> impl IntoResponse {
-    Html(GraphiQLSource::build().endpoint("/").finish())
+    Html(
+        GraphiQLSource::build()
+            .endpoint("/")
+            .subscription_endpoint("/ws")
+            .finish(),
+    )
 }
 
 async fn graphql_handler(
@@ -43,35 +58,16 @@ async fn graphql_handler(
     ClientIp(ip): ClientIp,
     TypedHeader(user_agent): TypedHeader<UserAgent>,
     accept_language: Option<TypedHeader<AcceptLanguage>>,
-    authorization: Option<TypedHeader<Authorization<Bearer>>>,
+    authorization: Option<OAuthBearerToken>,
     req: GraphQLRequest,
 ) -> GraphQLResponse {
     // authenticate the request
-    let user = if let Some(authorization) = authorization {
-        let token = authorization.token().to_string();
-
-        let mut auth_core = AuthCoreClient::new(context.router.clone());
-        auth_core
-            .get_user_by_token(GetUserByTokenRequest {
-                access_token: token,
-            })
-            .await
-            .ok()
-            .map(Response::into_inner)
-    } else {
-        None
-    };
+    let (user, oauth) = authenticate(&context, authorization).await;
 
     let local_context = LocalContext {
-        user_context: UserContext {
-            ip: IpNet::from(ip).to_string(),
-            user_agent: user_agent.to_string(),
-            lang_id: accept_language.map_or_else(
-                || DEFAULT_LANGUAGE.to_string(),
-                |al| al.best_match().to_string(),
-            ),
-        },
+        user_context: build_user_context(ip, &user_agent, accept_language),
         user,
+        oauth,
     };
 
     // execution
@@ -79,6 +75,154 @@ async fn graphql_handler(
     schema.execute(req).await.into()
 }
 
+async fn graphql_ws_handler(
+    Extension(context): Extension<GlobalContext>,
+    Extension(schema): Extension<GSchema>,
+    ClientIp(ip): ClientIp,
+    TypedHeader(user_agent): TypedHeader<UserAgent>,
+    accept_language: Option<TypedHeader<AcceptLanguage>>,
+    protocol: GraphQLProtocol,
+    upgrade: WebSocketUpgrade,
+) -> impl IntoResponse {
+    let user_context = build_user_context(ip, &user_agent, accept_language);
+
+    upgrade
+        .protocols(ALL_WEBSOCKET_PROTOCOLS)
+        .on_upgrade(move |socket| {
+            GraphQLWebSocket::new(socket, schema, protocol)
+                .on_connection_init(move |payload| async move {
+                    // browsers can't set headers on websockets, so the token is sent in the
+                    // `connection_init` payload instead
+                    let authorization =
+                        connection_init_token(&payload).map(|access_token| OAuthBearerToken {
+                            access_token,
+                            dpop: None,
+                        });
+                    let (user, oauth) = authenticate(&context, authorization).await;
+
+                    let mut data = Data::default();
+                    data.insert(LocalContext {
+                        user_context,
+                        user,
+                        oauth,
+                    });
+
+                    Ok(data)
+                })
+                .serve()
+        })
+}
+
+/// Get the access token from the `connection_init` payload of a websocket
+///
+/// Accepts either `{"Authorization": "Bearer <token>"}` or `{"token": "<token>"}`.
+fn connection_init_token(payload: &serde_json::Value) -> Option<String> {
+    let authorization = payload
+        .get("Authorization")
+        .or_else(|| payload.get("authorization"))
+        .and_then(serde_json::Value::as_str);
+
+    if let Some(authorization) = authorization {
+        let (scheme, token) = authorization.split_once(' ')?;
+        return scheme
+            .eq_ignore_ascii_case("bearer")
+            .then(|| token.trim().to_string());
+    }
+
+    payload
+        .get("token")
+        .and_then(serde_json::Value::as_str)
+        .map(ToString::to_string)
+}
+
+fn build_user_context(
+    ip: IpAddr,
+    user_agent: &UserAgent,
+    accept_language: Option<TypedHeader<AcceptLanguage>>,
+) -> UserContext {
+    UserContext {
+        ip: IpNet::from(ip).to_string(),
+        user_agent: user_agent.to_string(),
+        lang_id: accept_language.map_or_else(
+            || DEFAULT_LANGUAGE.to_string(),
+            |al| al.best_match().to_string(),
+        ),
+    }
+}
+
+/// Resolve an access token to either a first-party session or a third-party OAuth access
+///
+/// Invalid tokens are treated like unauthenticated requests.
+async fn authenticate(
+    context: &GlobalContext,
+    authorization: Option<OAuthBearerToken>,
+) -> (Option<GetUserByTokenReply>, Option<OAuthAccess>) {
+    match authorization {
+        Some(authorization) if is_oauth_token(&authorization.access_token) => {
+            (None, get_oauth_access(context, authorization).await)
+        }
+        Some(authorization) => {
+            let mut auth_core = AuthCoreClient::new(context.router.clone());
+            let user = auth_core
+                .get_user_by_token(GetUserByTokenRequest {
+                    access_token: authorization.access_token,
+                })
+                .await
+                .ok()
+                .map(Response::into_inner);
+
+            (user, None)
+        }
+        None => (None, None),
+    }
+}
+
+/// Check if a token was issued by the OAuth provider (opaque or JWT), not by a first-party login
+///
+/// First-party session tokens are `nanoid!(32)`, whose alphabet is `A-Za-z0-9_-`.
+/// They can't contain the `/` of opaque provider tokens (`BF/A/...`) or the `.` between
+/// the parts of a JWT, so neither kind of token can be mistaken for the other.
+fn is_oauth_token(token: &str) -> bool {
+    token.starts_with("BF/") || token.contains('.')
+}
+
+async fn get_oauth_access(
+    context: &GlobalContext,
+    authorization: OAuthBearerToken,
+) -> Option<OAuthAccess> {
+    let mut auth_oauth_provider = AuthOAuthProviderClient::new(context.router.clone());
+    let access = auth_oauth_provider
+        .get_access_token(GetAccessTokenRequest {
+            access_token: authorization.access_token,
+            dpop: authorization.dpop,
+        })
+        .await
+        .ok()?
+        .into_inner();
+
+    let user = if let Some(user_id) = access.user_id {
+        let mut auth_core = AuthCoreClient::new(context.router.clone());
+        let user = auth_core
+            .get_users_by_ids(GetUsersByIdsRequest { ids: vec![user_id] })
+            .await
+            .ok()?
+            .into_inner()
+            .users
+            .pop()?;
+
+        Some(user)
+    } else {
+        None
+    };
+
+    Some(OAuthAccess {
+        user,
+        client_id: access.client_id,
+        scopes: access.scope,
+        subject: access.subject,
+    })
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     setup_logging();
@@ -98,16 +242,26 @@ async fn main() -> anyhow::Result<()> {
 
     let app = Router::new()
         .route("/", get(graphiql).post(graphql_handler))
+        .route("/ws", get(graphql_ws_handler))
         .route(
             "/.well-known/openid-configuration",
             get(get_openid_metadata),
         )
         .route("/openid/jwks", get(get_jwk_set))
         .route("/openid/token", post(token_endpoint))
+        .route("/openid/revoke", post(revocation_endpoint))
+        .route("/openid/introspect", post(introspection_endpoint))
+        .route(
+            "/openid/device_authorization",
+            post(device_authorization_endpoint),
+        )
+        .route("/openid/par", post(pushed_authorization_request_endpoint))
         .route(
             "/openid/userinfo",
             get(userinfo_endpoint_get).post(userinfo_endpoint_post),
         )
+        .route("/saml/metadata", get(get_saml_metadata))
+        .route("/saml/acs", post(saml_acs))
         .layer(Extension(context))
         .layer(Extension(schema))
         .layer(ClientIpSource::from_str(&require_env("CLIENT_IP_SOURCE")?)?.into_extension());
@@ -2,12 +2,19 @@ mod permission_level;
 
 use crate::context::ContextExt;
 use crate::error::RespError;
+use crate::guards::ScopeGuard;
+use crate::id_encryption::IdEncryptor;
 use crate::models::user::permission_level::GPermissionLevel;
 use crate::services::auth_core::data_loaders::UserLoader;
 use crate::services::auth_oauth::auth_sources::GAuthSource;
+use crate::services::auth_oauth_provider::oauth_clients::GOAuthClient;
+use crate::services::auth_oauth_provider::oauth_grants::GOAuthGrant;
+use crate::services::notification::notification_preferences::GNotificationPreferences;
+use crate::services::notification::notifications::{GNotificationCategory, GNotificationPage};
 use crate::services::profile::user_profile::GProfile;
 use async_graphql::dataloader::DataLoader;
-use async_graphql::{Context, SimpleObject};
+use async_graphql::{Context, ID, SimpleObject};
+use bfx_core::service::id_encryption::IdType;
 use bfx_graphql_derive::complex_object_ext;
 use bfx_proto::auth::User;
 use o2o::o2o;
@@ -48,8 +55,16 @@ impl GUser {
 #[complex_object_ext]
 impl GUser {
     /// Unique ID of the user
-    #[graphql(cache_control(max_age = 86400))]
-    id!(_id => id, User);
+    ///
+    /// Third-party OAuth clients get the same ID as the `sub` claim of their ID tokens
+    /// for the user that authorized them.
+    #[graphql(cache_control(max_age = 86400, private))]
+    async fn id(&self, ctx: &Context<'_>) -> ID {
+        ctx.oauth_subject(self._id).map_or_else(
+            || ctx.encrypt_id(IdType::User, self._id),
+            |subject| ID(subject.into()),
+        )
+    }
 
     /// The user's email address (only visible to admins)
     #[graphql(cache_control(max_age = 60, private))]
@@ -70,4 +85,57 @@ impl GUser {
     async fn auth_sources(&self, ctx: &Context<'_>) -> Result<Vec<GAuthSource>, RespError> {
         self._auth_sources(ctx).await
     }
+
+    /// OAuth clients registered by this user
+    #[graphql(cache_control(max_age = 60, private))]
+    async fn oauth_clients(&self, ctx: &Context<'_>) -> Result<Vec<GOAuthClient>, RespError> {
+        self._oauth_clients(ctx).await
+    }
+
+    /// OAuth clients this user has authorized to access their account
+    #[graphql(cache_control(max_age = 60, private))]
+    async fn oauth_grants(&self, ctx: &Context<'_>) -> Result<Vec<GOAuthGrant>, RespError> {
+        self._oauth_grants(ctx).await
+    }
+
+    /// How this user wants to receive notifications
+    #[graphql(cache_control(no_cache))]
+    async fn notification_preferences(
+        &self,
+        ctx: &Context<'_>,
+    ) -> Result<GNotificationPreferences, RespError> {
+        self._notification_preferences(ctx).await
+    }
+
+    /// In-app notifications of this user, newest first
+    ///
+    /// At most 100 notifications are returned per page, 20 by default
+    #[graphql(
+        cache_control(no_cache),
+        guard = "ScopeGuard::new(\"notifications:read\")"
+    )]
+    async fn notifications(
+        &self,
+        ctx: &Context<'_>,
+        after: Option<ID>,
+        first: Option<u32>,
+        category: Option<GNotificationCategory>,
+        #[graphql(default)] unread_only: bool,
+    ) -> Result<GNotificationPage, RespError> {
+        self._notifications(ctx, after, first, category, unread_only)
+            .await
+    }
+
+    /// Number of unread in-app notifications of this user
+    #[graphql(
+        cache_control(no_cache),
+        guard = "ScopeGuard::new(\"notifications:read\")"
+    )]
+    async fn unread_notification_count(
+        &self,
+        ctx: &Context<'_>,
+        category: Option<GNotificationCategory>,
+    ) -> Result<i64, RespError> {
+        self._unread_notification_count(ctx, category).await
+    }
 }
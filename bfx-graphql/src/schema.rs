@@ -3,9 +3,10 @@ use crate::services::auth_oauth_provider::{AuthOAuthProviderMutation, AuthOAuthP
 use crate::services::auth_password_recovery::{
     AuthPasswordRecoveryMutation, AuthPasswordRecoveryQuery,
 };
-use async_graphql::{EmptySubscription, MergedObject, Schema};
+use crate::services::notification::{NotificationMutation, NotificationSubscription};
+use async_graphql::{MergedObject, MergedSubscription, Schema};
 
-pub type GSchema = Schema<GlobalQuery, GlobalMutation, EmptySubscription>;
+pub type GSchema = Schema<GlobalQuery, GlobalMutation, GlobalSubscription>;
 
 #[derive(MergedObject, Default)]
 #[graphql(name = "Query")]
@@ -21,4 +22,9 @@ pub struct GlobalMutation(
     AuthCoreMutation,
     AuthOAuthProviderMutation,
     AuthPasswordRecoveryMutation,
+    NotificationMutation,
 );
+
+#[derive(MergedSubscription, Default)]
+#[graphql(name = "Subscription")]
+pub struct GlobalSubscription(NotificationSubscription);
@@ -10,7 +10,7 @@ use o2o::o2o;
 #[derive(Default)]
 pub struct LoginEmailMutation;
 
-///
+/// Result of an email login attempt
 #[derive(Union, o2o)]
 #[graphql(name = "LoginResult")]
 #[from_owned(login_email_reply::LoginResult)]
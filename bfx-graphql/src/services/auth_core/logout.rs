@@ -0,0 +1,33 @@
+use crate::context::{ContextExt, ServiceFactory};
+use crate::error::RespError;
+use crate::models::ok::OkResp;
+use async_graphql::{Context, Object};
+use bfx_core::status::{ErrorCode, StatusExt};
+use bfx_proto::auth::LogoutRequest;
+use bfx_proto::auth::auth_core_client::AuthCoreClient;
+use tonic::{Code, Status};
+
+#[derive(Default)]
+pub struct LogoutMutation;
+
+#[Object]
+impl LogoutMutation {
+    /// End the current session
+    ///
+    /// OAuth clients that support back-channel logout are notified
+    async fn logout(&self, ctx: &Context<'_>) -> Result<OkResp, RespError> {
+        let mut auth_core: AuthCoreClient<_> = ctx.service();
+
+        let session = ctx
+            .session()
+            .ok_or_else(|| Status::coded(Code::Unauthenticated, ErrorCode::AccessDenied))?;
+
+        auth_core
+            .logout(LogoutRequest {
+                session_id: session.id,
+            })
+            .await?;
+
+        Ok(OkResp)
+    }
+}
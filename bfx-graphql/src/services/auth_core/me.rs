@@ -1,4 +1,5 @@
 use crate::context::ContextExt;
+use crate::guards::ScopeGuard;
 use crate::models::user::GUser;
 use async_graphql::{Context, Object};
 
@@ -8,8 +9,11 @@ pub struct MeQuery;
 #[Object]
 impl MeQuery {
     /// Get the currently logged-in user
-    #[graphql(cache_control(max_age = 3600, private))]
+    #[graphql(
+        cache_control(max_age = 3600, private),
+        guard = "ScopeGuard::new(\"profile\")"
+    )]
     async fn me(&self, ctx: &Context<'_>) -> Option<GUser> {
-        ctx.user().cloned().map(From::from)
+        ctx.scoped_user().cloned().map(From::from)
     }
 }
@@ -1,5 +1,6 @@
 use crate::services::auth_core::change_password::ChangePasswordMutation;
 use crate::services::auth_core::login_email::LoginEmailMutation;
+use crate::services::auth_core::logout::LogoutMutation;
 use crate::services::auth_core::me::MeQuery;
 use crate::services::auth_core::register_email::RegisterEmailMutation;
 use crate::services::auth_core::send_verification_email::SendVerificationEmailMutation;
@@ -10,6 +11,7 @@ use async_graphql::MergedObject;
 mod change_password;
 pub mod data_loaders;
 pub mod login_email;
+mod logout;
 mod me;
 mod register_email;
 mod send_verification_email;
@@ -23,6 +25,7 @@ pub struct AuthCoreQuery(MeQuery, UserByIdQuery);
 pub struct AuthCoreMutation(
     ChangePasswordMutation,
     LoginEmailMutation,
+    LogoutMutation,
     RegisterEmailMutation,
     SendVerificationEmailMutation,
     VerifyEmailMutation,
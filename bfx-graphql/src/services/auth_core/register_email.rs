@@ -41,6 +41,7 @@ impl RegisterEmailMutation {
                 user_id: user.id,
                 preferences: Some(NotificationPreferences {
                     lang_id: ctx.user_context().lang_id.clone(),
+                    ..Default::default()
                 }),
             })
             .await?;
@@ -37,18 +37,24 @@ impl GAuthSource {
 }
 
 impl GUser {
+    /// Get the external accounts bound to the user
+    ///
+    /// # Errors
+    ///
+    /// - If the user is not the same as the requester and not an admin
+    /// - If the underlying RPC call fails
     pub async fn _auth_sources(&self, ctx: &Context<'_>) -> Result<Vec<GAuthSource>, RespError> {
         ctx.require_self_or_admin(self._id)?;
 
         let mut auth_oauth: AuthOAuthClient<_> = ctx.service();
 
-        Ok(auth_oauth
+        auth_oauth
             .get_auth_sources(GetAuthSourcesRequest { user_id: self._id })
             .await?
             .into_inner()
             .auth_sources
             .into_iter()
             .map(TryFrom::try_from)
-            .try_collect()?)
+            .try_collect()
     }
 }
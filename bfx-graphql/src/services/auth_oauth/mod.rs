@@ -1,6 +1,7 @@
 pub mod auth_sources;
 mod bind_oauth;
 pub mod finish_oauth_flow;
+pub mod saml;
 mod start_oauth_flow;
 mod unbind_auth_source;
 
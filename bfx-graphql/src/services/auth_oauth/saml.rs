@@ -0,0 +1,80 @@
+use crate::context::{GlobalContext, ServiceFactory};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Redirect};
+use axum::{Extension, Form};
+use bfx_proto::auth::auth_o_auth_client::AuthOAuthClient;
+use bfx_proto::auth::{ConsumeSamlResponseRequest, GetSamlMetadataRequest};
+use serde::Deserialize;
+use tonic::Code;
+use tracing::error;
+
+/// GET /saml/metadata
+///
+/// # Errors
+///
+/// - If the underlying RPC call fails.
+///   See [`AuthOAuthClient::get_saml_metadata`]
+pub async fn get_saml_metadata(
+    Extension(context): Extension<GlobalContext>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let mut auth_oauth: AuthOAuthClient<_> = context.service();
+
+    let metadata = auth_oauth
+        .get_saml_metadata(GetSamlMetadataRequest {})
+        .await
+        .map_err(|err| {
+            error!(?err, "failed to get saml metadata");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .into_inner()
+        .xml;
+
+    Ok((
+        [
+            ("content-type", "application/samlmetadata+xml"),
+            ("cache-control", "max-age=3600"),
+        ],
+        metadata,
+    ))
+}
+
+#[derive(Deserialize)]
+pub struct SamlAcsForm {
+    #[serde(rename = "SAMLResponse")]
+    saml_response: String,
+    #[serde(rename = "RelayState", default)]
+    relay_state: String,
+}
+
+/// POST /saml/acs
+///
+/// Redirects to the frontend to finish the flow.
+///
+/// # Errors
+///
+/// - If the underlying RPC call fails.
+///   See [`AuthOAuthClient::consume_saml_response`]
+pub async fn saml_acs(
+    Extension(context): Extension<GlobalContext>,
+    Form(form): Form<SamlAcsForm>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let mut auth_oauth: AuthOAuthClient<_> = context.service();
+
+    let redirect_to = auth_oauth
+        .consume_saml_response(ConsumeSamlResponseRequest {
+            saml_response: form.saml_response,
+            relay_state: form.relay_state,
+        })
+        .await
+        .map_err(|err| match err.code() {
+            Code::NotFound | Code::InvalidArgument => StatusCode::BAD_REQUEST,
+            _ => {
+                error!(?err, "failed to consume saml response");
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        })?
+        .into_inner()
+        .redirect_to;
+
+    Ok(Redirect::to(&redirect_to))
+}
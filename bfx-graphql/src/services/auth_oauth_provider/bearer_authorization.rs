@@ -1,19 +1,36 @@
-//! RFC 6750 authorization
+//! RFC 6750 authorization, and RFC 9449 (`DPoP`) sender-constrained authorization
 
 use axum::Form;
-use axum::extract::{FromRequest, FromRequestParts, Request};
-use axum::http::StatusCode;
+use axum::extract::{FromRequest, FromRequestParts, OptionalFromRequestParts, Request};
 use axum::http::request::Parts;
-use axum_extra::TypedHeader;
-use axum_extra::headers::Authorization;
-use axum_extra::headers::authorization::Bearer;
-use o2o::o2o;
+use axum::http::{HeaderMap, StatusCode, header};
+use bfx_proto::auth::DpopProof;
 use serde::Deserialize;
 
-const REJECTION_RESP: (StatusCode, [(&str, &str); 1]) =
-    (StatusCode::UNAUTHORIZED, [("WWW-Authenticate", "Bearer")]);
+const REJECTION_RESP: (StatusCode, [(&str, &str); 1]) = (
+    StatusCode::UNAUTHORIZED,
+    [("WWW-Authenticate", "Bearer, DPoP")],
+);
 
-pub struct OAuthBearerToken(pub String);
+/// Get the `DPoP` header of a request
+///
+/// If the header is sent multiple times, the values are joined, which makes the proof invalid.
+#[must_use]
+pub fn dpop_header(headers: &HeaderMap) -> Option<String> {
+    let values = headers
+        .get_all("dpop")
+        .iter()
+        .map(|value| value.to_str().ok())
+        .collect::<Option<Vec<_>>>()?;
+
+    (!values.is_empty()).then(|| values.join(", "))
+}
+
+pub struct OAuthBearerToken {
+    pub access_token: String,
+    /// Only set if the token was sent with the `DPoP` scheme
+    pub dpop: Option<DpopProof>,
+}
 
 impl<S> FromRequestParts<S> for OAuthBearerToken
 where
@@ -21,15 +38,36 @@ where
 {
     type Rejection = (StatusCode, [(&'static str, &'static str); 1]);
 
-    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
         // by header
-        let by_header =
-            Option::<TypedHeader<Authorization<Bearer>>>::from_request_parts(parts, state)
-                .await
-                .map_err(|_| REJECTION_RESP)?;
+        if let Some(authorization) = parts.headers.get(header::AUTHORIZATION) {
+            let (scheme, access_token) = authorization
+                .to_str()
+                .ok()
+                .and_then(|authorization| authorization.split_once(' '))
+                .ok_or(REJECTION_RESP)?;
+            let access_token = access_token.trim().to_string();
+
+            if scheme.eq_ignore_ascii_case("Bearer") {
+                return Ok(Self {
+                    access_token,
+                    dpop: None,
+                });
+            }
+            if scheme.eq_ignore_ascii_case("DPoP") {
+                let proof = dpop_header(&parts.headers).ok_or(REJECTION_RESP)?;
 
-        if let Some(header) = by_header {
-            return Ok(Self(header.token().into()));
+                return Ok(Self {
+                    access_token,
+                    dpop: Some(DpopProof {
+                        proof,
+                        http_method: parts.method.to_string(),
+                        http_path: parts.uri.path().to_string(),
+                    }),
+                });
+            }
+
+            return Err(REJECTION_RESP);
         }
 
         // by query param
@@ -37,16 +75,46 @@ where
             .find(|(k, _)| k == "access_token");
 
         if let Some((_, token)) = by_query {
-            return Ok(Self(token.into_owned()));
+            return Ok(Self {
+                access_token: token.into_owned(),
+                dpop: None,
+            });
         }
 
         Err(REJECTION_RESP)
     }
 }
 
-#[derive(o2o)]
-#[map_owned(OAuthBearerToken)]
-pub struct OAuthBearerTokenForm(pub String);
+impl<S> OptionalFromRequestParts<S> for OAuthBearerToken
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, [(&'static str, &'static str); 1]);
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &S,
+    ) -> Result<Option<Self>, Self::Rejection> {
+        let has_query_param =
+            form_urlencoded::parse(parts.uri.query().unwrap_or_default().as_bytes())
+                .any(|(k, _)| k == "access_token");
+        if !parts.headers.contains_key(header::AUTHORIZATION) && !has_query_param {
+            return Ok(None);
+        }
+
+        <Self as FromRequestParts<S>>::from_request_parts(parts, state)
+            .await
+            .map(Some)
+    }
+}
+
+pub struct OAuthBearerTokenForm(pub OAuthBearerToken);
+
+impl From<OAuthBearerTokenForm> for OAuthBearerToken {
+    fn from(value: OAuthBearerTokenForm) -> Self {
+        value.0
+    }
+}
 
 impl<S> FromRequest<S> for OAuthBearerTokenForm
 where
@@ -62,9 +130,10 @@ where
 
         // by header or query
         let (mut parts, body) = req.into_parts();
-        let by_parts = OAuthBearerToken::from_request_parts(&mut parts, state).await;
+        let by_parts =
+            <OAuthBearerToken as FromRequestParts<S>>::from_request_parts(&mut parts, state).await;
         if let Ok(token) = by_parts {
-            return Ok(Self(token.0));
+            return Ok(Self(token));
         }
 
         // by form
@@ -72,6 +141,9 @@ where
             .await
             .map_err(|_| REJECTION_RESP)?;
 
-        Ok(Self(form.0.access_token))
+        Ok(Self(OAuthBearerToken {
+            access_token: form.0.access_token,
+            dpop: None,
+        }))
     }
 }
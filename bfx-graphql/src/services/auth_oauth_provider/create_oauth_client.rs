@@ -0,0 +1,59 @@
+use crate::context::{ContextExt, ServiceFactory};
+use crate::error::RespError;
+use crate::services::auth_oauth_provider::oauth_clients::{GOAuthClient, GOAuthClientInput};
+use async_graphql::{Context, Object, SimpleObject};
+use bfx_proto::auth::CreateClientRequest;
+use bfx_proto::auth::auth_o_auth_provider_client::AuthOAuthProviderClient;
+
+#[derive(Default)]
+pub struct CreateOAuthClientMutation;
+
+/// Response from `create_oauth_client`
+#[derive(SimpleObject)]
+struct CreateOAuthClientResponse {
+    /// The new client
+    client: GOAuthClient,
+    /// Secret of the client
+    ///
+    /// This is the only time it's shown, use `rotate_oauth_client_secret` if it's lost
+    client_secret: String,
+}
+
+#[Object]
+impl CreateOAuthClientMutation {
+    /// Register a new OAuth client owned by the current user
+    ///
+    /// Only admins can set `official` or `resourceServer`
+    async fn create_oauth_client(
+        &self,
+        ctx: &Context<'_>,
+        input: GOAuthClientInput,
+        #[graphql(default)] official: bool,
+        #[graphql(default)] resource_server: bool,
+    ) -> Result<CreateOAuthClientResponse, RespError> {
+        let mut auth_oauth_provider: AuthOAuthProviderClient<_> = ctx.service();
+
+        let user = ctx.require_user()?;
+        if official || resource_server {
+            ctx.require_admin()?;
+        }
+
+        let resp = auth_oauth_provider
+            .create_client(CreateClientRequest {
+                owner_id: user.id,
+                details: Some(input.into()),
+                official,
+                resource_server,
+            })
+            .await?
+            .into_inner();
+
+        Ok(CreateOAuthClientResponse {
+            client: resp
+                .client
+                .ok_or_else(RespError::missing_field)?
+                .try_into()?,
+            client_secret: resp.client_secret,
+        })
+    }
+}
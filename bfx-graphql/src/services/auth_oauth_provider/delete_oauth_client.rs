@@ -0,0 +1,32 @@
+use crate::context::{ContextExt, ServiceFactory};
+use crate::error::RespError;
+use crate::id_encryption::IdEncryptor;
+use async_graphql::{Context, ID, Object};
+use bfx_core::service::id_encryption::IdType;
+use bfx_proto::auth::DeleteClientRequest;
+use bfx_proto::auth::auth_o_auth_provider_client::AuthOAuthProviderClient;
+
+#[derive(Default)]
+pub struct DeleteOAuthClientMutation;
+
+#[Object]
+impl DeleteOAuthClientMutation {
+    /// Delete an OAuth client, revoking all of its grants
+    ///
+    /// Returns the ID of the deleted client
+    async fn delete_oauth_client(&self, ctx: &Context<'_>, id: ID) -> Result<ID, RespError> {
+        let mut auth_oauth_provider: AuthOAuthProviderClient<_> = ctx.service();
+
+        let user = ctx.require_user()?;
+        let client_id = ctx.decrypt_id(IdType::OAuthClient, &id)?;
+
+        auth_oauth_provider
+            .delete_client(DeleteClientRequest {
+                id: client_id,
+                owner_id: (!ctx.is_admin()).then_some(user.id),
+            })
+            .await?;
+
+        Ok(id)
+    }
+}
@@ -0,0 +1,73 @@
+use crate::context::{ContextExt, ServiceFactory};
+use crate::error::RespError;
+use crate::models::ok::OkResp;
+use crate::services::auth_oauth_provider::get_authorization_info::GRpInfo;
+use async_graphql::{Context, Object, SimpleObject};
+use bfx_proto::auth::auth_o_auth_provider_client::AuthOAuthProviderClient;
+use bfx_proto::auth::{
+    AcceptDeviceAuthorizationRequest, GetDeviceAuthorizationInfoReply,
+    GetDeviceAuthorizationInfoRequest,
+};
+use o2o::o2o;
+
+#[derive(Default)]
+pub struct DeviceAuthorizationQuery;
+
+#[derive(Default)]
+pub struct DeviceAuthorizationMutation;
+
+/// Information used to show the authorization prompt screen for a device
+#[derive(SimpleObject, o2o)]
+#[try_from_owned(GetDeviceAuthorizationInfoReply, RespError)]
+pub struct DeviceAuthorizationInfo {
+    /// Information about the OAuth client
+    #[try_from(~.ok_or_else(RespError::missing_field)?.into())]
+    pub rp_info: GRpInfo,
+    /// Requested scopes
+    pub scopes: Vec<String>,
+}
+
+#[Object]
+impl DeviceAuthorizationQuery {
+    /// Get information for authorizing a device with the code shown on it
+    #[graphql(cache_control(private))]
+    async fn get_device_authorization_info(
+        &self,
+        ctx: &Context<'_>,
+        user_code: String,
+    ) -> Result<DeviceAuthorizationInfo, RespError> {
+        let mut auth_oauth_provider: AuthOAuthProviderClient<_> = ctx.service();
+
+        let resp = auth_oauth_provider
+            .get_device_authorization_info(GetDeviceAuthorizationInfoRequest { user_code })
+            .await?
+            .into_inner();
+
+        resp.try_into()
+    }
+}
+
+#[Object]
+impl DeviceAuthorizationMutation {
+    /// Approve (or deny) a device with the code shown on it
+    async fn accept_device_authorization(
+        &self,
+        ctx: &Context<'_>,
+        user_code: String,
+        #[graphql(default = true)] accept: bool,
+    ) -> Result<OkResp, RespError> {
+        let mut auth_oauth_provider: AuthOAuthProviderClient<_> = ctx.service();
+
+        let user = ctx.require_user()?;
+
+        auth_oauth_provider
+            .accept_device_authorization(AcceptDeviceAuthorizationRequest {
+                user_code,
+                user_id: user.id,
+                accept,
+            })
+            .await?;
+
+        Ok(OkResp)
+    }
+}
@@ -0,0 +1,89 @@
+use crate::context::{ContextExt, ServiceFactory};
+use crate::error::RespError;
+use crate::services::auth_oauth_provider::get_authorization_info::GRpInfo;
+use async_graphql::{Context, Object, SimpleObject};
+use bfx_core::status::{ErrorCode, StatusExt};
+use bfx_proto::auth::auth_core_client::AuthCoreClient;
+use bfx_proto::auth::auth_o_auth_provider_client::AuthOAuthProviderClient;
+use bfx_proto::auth::{GetEndSessionInfoReply, GetEndSessionInfoRequest, LogoutRequest};
+use o2o::o2o;
+use std::collections::HashMap;
+use tonic::{Code, Status};
+
+#[derive(Default)]
+pub struct EndSessionQuery;
+
+#[derive(Default)]
+pub struct EndSessionMutation;
+
+/// Information used to show the logout prompt for an OAuth client (RP-initiated logout)
+#[derive(SimpleObject, o2o)]
+#[from_owned(GetEndSessionInfoReply)]
+pub struct EndSessionInfo {
+    /// Information about the OAuth client that initiated the logout, if known
+    #[from(~.map(Into::into))]
+    pub rp_info: Option<GRpInfo>,
+    /// URL to redirect to after logging out, if requested by the client
+    pub redirect_to: Option<String>,
+}
+
+#[Object]
+impl EndSessionQuery {
+    /// Get information for logging out at the request of an OAuth client
+    ///
+    /// `query` is a map of the query parameters passed to the `/logout` endpoint
+    #[graphql(cache_control(private))]
+    async fn get_end_session_info(
+        &self,
+        ctx: &Context<'_>,
+        query: HashMap<String, String>,
+    ) -> Result<EndSessionInfo, RespError> {
+        let mut auth_oauth_provider: AuthOAuthProviderClient<_> = ctx.service();
+
+        Ok(auth_oauth_provider
+            .get_end_session_info(GetEndSessionInfoRequest {
+                user_id: ctx.user().map(|user| user.id),
+                query,
+            })
+            .await?
+            .into_inner()
+            .into())
+    }
+}
+
+#[Object]
+impl EndSessionMutation {
+    /// End the current session at the request of an OAuth client
+    ///
+    /// `query` is a map of the query parameters passed to the `/logout` endpoint
+    async fn end_session(
+        &self,
+        ctx: &Context<'_>,
+        query: HashMap<String, String>,
+    ) -> Result<EndSessionInfo, RespError> {
+        let mut auth_oauth_provider: AuthOAuthProviderClient<_> = ctx.service();
+        let mut auth_core: AuthCoreClient<_> = ctx.service();
+
+        let user = ctx.require_user()?;
+        let session = ctx
+            .session()
+            .ok_or_else(|| Status::coded(Code::Unauthenticated, ErrorCode::AccessDenied))?;
+
+        // validate the request before logging out
+        let info = auth_oauth_provider
+            .get_end_session_info(GetEndSessionInfoRequest {
+                user_id: Some(user.id),
+                query,
+            })
+            .await?
+            .into_inner();
+
+        auth_core
+            .logout(LogoutRequest {
+                session_id: session.id,
+            })
+            .await?;
+
+        Ok(info.into())
+    }
+}
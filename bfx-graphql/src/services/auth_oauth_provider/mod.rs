@@ -1,17 +1,52 @@
 use crate::services::auth_oauth_provider::accept_authorization::AcceptAuthorizationMutation;
+use crate::services::auth_oauth_provider::create_oauth_client::CreateOAuthClientMutation;
+use crate::services::auth_oauth_provider::delete_oauth_client::DeleteOAuthClientMutation;
+use crate::services::auth_oauth_provider::device_authorization::{
+    DeviceAuthorizationMutation, DeviceAuthorizationQuery,
+};
+use crate::services::auth_oauth_provider::end_session::{EndSessionMutation, EndSessionQuery};
 use crate::services::auth_oauth_provider::get_authorization_info::GetAuthorizationInfoQuery;
+use crate::services::auth_oauth_provider::revoke_oauth_grant::RevokeOAuthGrantMutation;
+use crate::services::auth_oauth_provider::rotate_oauth_client_secret::RotateOAuthClientSecretMutation;
+use crate::services::auth_oauth_provider::update_oauth_client::UpdateOAuthClientMutation;
 use async_graphql::MergedObject;
 
 mod accept_authorization;
-mod bearer_authorization;
+pub mod bearer_authorization;
+mod create_oauth_client;
+mod delete_oauth_client;
+mod device_authorization;
+pub mod device_authorization_endpoint;
+mod end_session;
 mod get_authorization_info;
 pub mod get_jwk_set;
 pub mod get_openid_metadata;
+pub mod introspection_endpoint;
+pub mod oauth_clients;
+pub mod oauth_grants;
+pub mod pushed_authorization_request_endpoint;
+pub mod revocation_endpoint;
+mod revoke_oauth_grant;
+mod rotate_oauth_client_secret;
 pub mod token_endpoint;
+mod update_oauth_client;
 pub mod userinfo_endpoint;
 
 #[derive(Default, MergedObject)]
-pub struct AuthOAuthProviderQuery(GetAuthorizationInfoQuery);
+pub struct AuthOAuthProviderQuery(
+    GetAuthorizationInfoQuery,
+    DeviceAuthorizationQuery,
+    EndSessionQuery,
+);
 
 #[derive(Default, MergedObject)]
-pub struct AuthOAuthProviderMutation(AcceptAuthorizationMutation);
+pub struct AuthOAuthProviderMutation(
+    AcceptAuthorizationMutation,
+    CreateOAuthClientMutation,
+    UpdateOAuthClientMutation,
+    RotateOAuthClientSecretMutation,
+    DeleteOAuthClientMutation,
+    DeviceAuthorizationMutation,
+    RevokeOAuthGrantMutation,
+    EndSessionMutation,
+);
@@ -0,0 +1,143 @@
+use crate::context::{ContextExt, ServiceFactory};
+use crate::error::RespError;
+use crate::models::user::GUser;
+use async_graphql::{Context, InputObject, SimpleObject};
+use bfx_graphql_derive::complex_object_ext;
+use bfx_proto::auth::auth_o_auth_provider_client::AuthOAuthProviderClient;
+use bfx_proto::auth::{ClientDetails, GetClientsRequest, OAuthClient};
+use chrono::{DateTime, Utc};
+use itertools::Itertools;
+use o2o::o2o;
+
+/// An OAuth client registered by a developer
+#[allow(clippy::struct_excessive_bools)]
+#[derive(SimpleObject, o2o)]
+#[graphql(complex, name = "OAuthClient")]
+#[try_from_owned(OAuthClient, RespError)]
+pub struct GOAuthClient {
+    #[graphql(skip)]
+    id: i64,
+    #[graphql(skip)]
+    owner_id: i64,
+    /// `client_id` used in OAuth requests
+    client_id: String,
+    /// Redirect URIs that are allowed in authorization requests
+    redirect_uris: Vec<String>,
+    /// Name of the service
+    display_name: String,
+    /// Privacy policy URL of the service
+    privacy_url: Option<String>,
+    /// Terms of service URL of the service
+    tos_url: Option<String>,
+    /// Whether the service is marked as official
+    official: bool,
+    /// Scopes that the client is allowed to request
+    allowed_scopes: Vec<String>,
+    /// Whether PKCE is required in authorization requests
+    enforce_code_challenge: bool,
+    /// When the client was registered
+    #[try_from(~.ok_or_else(RespError::missing_field)?.try_into()?)]
+    created_at: DateTime<Utc>,
+    /// JSON Web Key Set used for `private_key_jwt` authentication
+    jwks: Option<String>,
+    /// Algorithm used to sign ID tokens (`RS256`, `ES256` or `EdDSA`)
+    id_token_signed_response_alg: String,
+    /// Whether access tokens are issued as short-lived JWTs (RFC 9068) instead of opaque tokens
+    jwt_access_tokens: bool,
+    /// URIs that are allowed as `post_logout_redirect_uri` in RP-initiated logout
+    post_logout_redirect_uris: Vec<String>,
+    /// URI that logout tokens are sent to when the user logs out (back-channel logout)
+    backchannel_logout_uri: Option<String>,
+    /// How users are identified to the client (`public` or `pairwise`)
+    ///
+    /// Official clients always get `public` subjects
+    subject_type: String,
+    /// Sector that pairwise subjects are derived for
+    sector_identifier: Option<String>,
+    /// Whether authorization requests must be pushed to `/openid/par` first (RFC 9126)
+    require_pushed_authorization_requests: bool,
+    /// Whether the client can introspect tokens issued to other clients
+    resource_server: bool,
+    /// How the client authenticates (`client_secret_basic`, `private_key_jwt` or `none`)
+    token_endpoint_auth_method: String,
+}
+
+#[complex_object_ext]
+impl GOAuthClient {
+    /// Unique ID of the OAuth client (does not equal the `client_id`)
+    id!(id => id, OAuthClient);
+
+    /// User that registered this client
+    user!(owner_id => owner);
+}
+
+/// Editable details of an OAuth client
+#[derive(InputObject, o2o)]
+#[graphql(name = "OAuthClientInput")]
+#[owned_into(ClientDetails)]
+pub struct GOAuthClientInput {
+    /// Redirect URIs that are allowed in authorization requests
+    redirect_uris: Vec<String>,
+    /// Name of the service, as plain text without markup or URLs
+    display_name: String,
+    /// Privacy policy URL of the service
+    privacy_url: Option<String>,
+    /// Terms of service URL of the service
+    tos_url: Option<String>,
+    /// Scopes that the client is allowed to request
+    allowed_scopes: Vec<String>,
+    /// Whether PKCE is required in authorization requests
+    enforce_code_challenge: bool,
+    /// JSON Web Key Set used for `private_key_jwt` authentication
+    ///
+    /// If set, the client secret can't be used to authenticate
+    jwks: Option<String>,
+    /// Algorithm used to sign ID tokens (`RS256`, `ES256` or `EdDSA`)
+    ///
+    /// Defaults to `RS256`
+    id_token_signed_response_alg: Option<String>,
+    /// Whether access tokens are issued as short-lived JWTs (RFC 9068) instead of opaque tokens
+    #[graphql(default)]
+    jwt_access_tokens: bool,
+    /// URIs that are allowed as `post_logout_redirect_uri` in RP-initiated logout
+    #[graphql(default)]
+    post_logout_redirect_uris: Vec<String>,
+    /// URI that logout tokens are sent to when the user logs out (back-channel logout)
+    backchannel_logout_uri: Option<String>,
+    /// How users are identified to the client (`public` or `pairwise`)
+    ///
+    /// New clients default to `pairwise`, updates keep the current value if not set
+    subject_type: Option<String>,
+    /// Whether authorization requests must be pushed to `/openid/par` first (RFC 9126)
+    #[graphql(default)]
+    require_pushed_authorization_requests: bool,
+    /// How the client authenticates
+    ///
+    /// `client_secret_basic` or `private_key_jwt` (requires `jwks`) for confidential clients,
+    /// `none` for public clients, which must use PKCE and `DPoP`.
+    /// New clients default to `client_secret_basic`, updates keep the current value if not set
+    token_endpoint_auth_method: Option<String>,
+}
+
+impl GUser {
+    /// Get the OAuth clients registered by the user
+    ///
+    /// # Errors
+    ///
+    /// - If the user is not the same as the requester and not an admin
+    /// - If the underlying RPC call fails
+    pub async fn _oauth_clients(&self, ctx: &Context<'_>) -> Result<Vec<GOAuthClient>, RespError> {
+        ctx.require_self_or_admin(self._id)?;
+
+        let mut auth_oauth_provider: AuthOAuthProviderClient<_> = ctx.service();
+
+        auth_oauth_provider
+            .get_clients(GetClientsRequest { owner_id: self._id })
+            .await?
+            .into_inner()
+            .clients
+            .into_iter()
+            .map(TryFrom::try_from)
+            .try_collect()
+    }
+}
@@ -0,0 +1,57 @@
+use crate::context::{ContextExt, ServiceFactory};
+use crate::error::RespError;
+use crate::models::user::GUser;
+use crate::services::auth_oauth_provider::get_authorization_info::GRpInfo;
+use async_graphql::{Context, SimpleObject};
+use bfx_graphql_derive::complex_object_ext;
+use bfx_proto::auth::auth_o_auth_provider_client::AuthOAuthProviderClient;
+use bfx_proto::auth::{ListGrantsRequest, OAuthGrant};
+use chrono::{DateTime, Utc};
+use itertools::Itertools;
+use o2o::o2o;
+
+/// An OAuth client authorized by the user to access their account
+#[derive(SimpleObject, o2o)]
+#[graphql(complex, name = "OAuthGrant")]
+#[try_from_owned(OAuthGrant, RespError)]
+pub struct GOAuthGrant {
+    #[graphql(skip)]
+    id: i64,
+    /// The authorized client
+    #[try_from(~.ok_or_else(RespError::missing_field)?.into())]
+    client: GRpInfo,
+    /// Scopes the user has granted to the client
+    scopes: Vec<String>,
+    /// When the user first authorized the client
+    #[try_from(~.ok_or_else(RespError::missing_field)?.try_into()?)]
+    created_at: DateTime<Utc>,
+}
+
+#[complex_object_ext]
+impl GOAuthGrant {
+    /// Unique ID of the grant
+    id!(id => id, OAuthGrant);
+}
+
+impl GUser {
+    /// Get the OAuth clients authorized by the user
+    ///
+    /// # Errors
+    ///
+    /// - If the user is not the same as the requester and not an admin
+    /// - If the underlying RPC call fails
+    pub async fn _oauth_grants(&self, ctx: &Context<'_>) -> Result<Vec<GOAuthGrant>, RespError> {
+        ctx.require_self_or_admin(self._id)?;
+
+        let mut auth_oauth_provider: AuthOAuthProviderClient<_> = ctx.service();
+
+        auth_oauth_provider
+            .list_grants(ListGrantsRequest { user_id: self._id })
+            .await?
+            .into_inner()
+            .grants
+            .into_iter()
+            .map(TryFrom::try_from)
+            .try_collect()
+    }
+}
@@ -0,0 +1,60 @@
+use crate::context::{GlobalContext, ServiceFactory};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::{Extension, Form};
+use axum_extra::TypedHeader;
+use axum_extra::headers::Authorization;
+use axum_extra::headers::authorization::Basic;
+use bfx_proto::auth::auth_o_auth_provider_client::AuthOAuthProviderClient;
+use bfx_proto::auth::{BasicAuthorization, RevocationEndpointRequest};
+use std::collections::HashMap;
+use tracing::error;
+
+/// POST /openid/revoke
+///
+/// # Errors
+///
+/// - If the underlying RPC call fails.
+///   See [`AuthOAuthProviderClient::revocation_endpoint`]
+#[allow(clippy::implicit_hasher)]
+pub async fn revocation_endpoint(
+    Extension(context): Extension<GlobalContext>,
+    authorization: Option<TypedHeader<Authorization<Basic>>>,
+    Form(params): Form<HashMap<String, String>>,
+) -> Result<Response, StatusCode> {
+    let mut oauth_provider: AuthOAuthProviderClient<_> = context.service();
+
+    let resp = oauth_provider
+        .revocation_endpoint(RevocationEndpointRequest {
+            query: params,
+            authorization: authorization.map(|auth| BasicAuthorization {
+                username: auth.username().to_string(),
+                password: auth.password().to_string(),
+            }),
+        })
+        .await
+        .map_err(|err| {
+            error!(?err, "revocation endpoint returned error");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .into_inner();
+
+    #[allow(clippy::cast_possible_truncation)]
+    let status =
+        StatusCode::from_u16(resp.status as u16).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    // successful revocations have an empty body
+    if resp.json.is_empty() {
+        return Ok(status.into_response());
+    }
+
+    Ok((
+        status,
+        [
+            ("content-type", "application/json"),
+            ("cache-control", "no-store"),
+        ],
+        resp.json,
+    )
+        .into_response())
+}
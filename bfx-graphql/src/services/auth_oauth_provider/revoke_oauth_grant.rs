@@ -0,0 +1,33 @@
+use crate::context::{ContextExt, ServiceFactory};
+use crate::error::RespError;
+use crate::id_encryption::IdEncryptor;
+use async_graphql::{Context, ID, Object};
+use bfx_core::service::id_encryption::IdType;
+use bfx_proto::auth::RevokeGrantRequest;
+use bfx_proto::auth::auth_o_auth_provider_client::AuthOAuthProviderClient;
+
+#[derive(Default)]
+pub struct RevokeOAuthGrantMutation;
+
+#[Object]
+impl RevokeOAuthGrantMutation {
+    /// Revoke the access of an OAuth client to the current user's account
+    ///
+    /// Returns the ID of the revoked grant
+    async fn revoke_oauth_grant(&self, ctx: &Context<'_>, id: ID) -> Result<ID, RespError> {
+        let mut auth_oauth_provider: AuthOAuthProviderClient<_> = ctx.service();
+
+        let user = ctx.require_user()?;
+        let grant_id = ctx.decrypt_id(IdType::OAuthGrant, &id)?;
+
+        auth_oauth_provider
+            .revoke_grant(RevokeGrantRequest {
+                id: grant_id,
+                user_id: user.id,
+                user_context: Some(ctx.user_context().clone()),
+            })
+            .await?;
+
+        Ok(id)
+    }
+}
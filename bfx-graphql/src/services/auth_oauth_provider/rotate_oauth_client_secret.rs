@@ -0,0 +1,37 @@
+use crate::context::{ContextExt, ServiceFactory};
+use crate::error::RespError;
+use crate::id_encryption::IdEncryptor;
+use async_graphql::{Context, ID, Object};
+use bfx_core::service::id_encryption::IdType;
+use bfx_proto::auth::RotateClientSecretRequest;
+use bfx_proto::auth::auth_o_auth_provider_client::AuthOAuthProviderClient;
+
+#[derive(Default)]
+pub struct RotateOAuthClientSecretMutation;
+
+#[Object]
+impl RotateOAuthClientSecretMutation {
+    /// Generate a new secret for an OAuth client, invalidating the old one
+    ///
+    /// Returns the new secret
+    async fn rotate_oauth_client_secret(
+        &self,
+        ctx: &Context<'_>,
+        id: ID,
+    ) -> Result<String, RespError> {
+        let mut auth_oauth_provider: AuthOAuthProviderClient<_> = ctx.service();
+
+        let user = ctx.require_user()?;
+        let client_id = ctx.decrypt_id(IdType::OAuthClient, &id)?;
+
+        let resp = auth_oauth_provider
+            .rotate_client_secret(RotateClientSecretRequest {
+                id: client_id,
+                owner_id: (!ctx.is_admin()).then_some(user.id),
+            })
+            .await?
+            .into_inner();
+
+        Ok(resp.client_secret)
+    }
+}
@@ -1,5 +1,6 @@
 use crate::context::{GlobalContext, ServiceFactory};
-use axum::http::StatusCode;
+use crate::services::auth_oauth_provider::bearer_authorization::dpop_header;
+use axum::http::{HeaderMap, StatusCode};
 use axum::response::IntoResponse;
 use axum::{Extension, Form};
 use axum_extra::TypedHeader;
@@ -20,6 +21,7 @@ use tracing::error;
 pub async fn token_endpoint(
     Extension(context): Extension<GlobalContext>,
     authorization: Option<TypedHeader<Authorization<Basic>>>,
+    headers: HeaderMap,
     Form(params): Form<HashMap<String, String>>,
 ) -> Result<impl IntoResponse, StatusCode> {
     let mut oauth_provider: AuthOAuthProviderClient<_> = context.service();
@@ -31,6 +33,7 @@ pub async fn token_endpoint(
                 username: auth.username().to_string(),
                 password: auth.password().to_string(),
             }),
+            dpop: dpop_header(&headers),
         })
         .await
         .map_err(|err| {
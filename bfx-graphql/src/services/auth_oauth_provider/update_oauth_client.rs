@@ -0,0 +1,49 @@
+use crate::context::{ContextExt, ServiceFactory};
+use crate::error::RespError;
+use crate::id_encryption::IdEncryptor;
+use crate::services::auth_oauth_provider::oauth_clients::{GOAuthClient, GOAuthClientInput};
+use async_graphql::{Context, ID, Object};
+use bfx_core::service::id_encryption::IdType;
+use bfx_proto::auth::UpdateClientRequest;
+use bfx_proto::auth::auth_o_auth_provider_client::AuthOAuthProviderClient;
+
+#[derive(Default)]
+pub struct UpdateOAuthClientMutation;
+
+#[Object]
+impl UpdateOAuthClientMutation {
+    /// Update the details of an OAuth client
+    ///
+    /// Only admins can set `official` or `resourceServer`, or update clients of other users.
+    /// Changing the redirect URIs of an official client makes it non-official,
+    /// unless `official` is set again.
+    async fn update_oauth_client(
+        &self,
+        ctx: &Context<'_>,
+        id: ID,
+        input: GOAuthClientInput,
+        official: Option<bool>,
+        resource_server: Option<bool>,
+    ) -> Result<GOAuthClient, RespError> {
+        let mut auth_oauth_provider: AuthOAuthProviderClient<_> = ctx.service();
+
+        let user = ctx.require_user()?;
+        let client_id = ctx.decrypt_id(IdType::OAuthClient, &id)?;
+        if official.is_some() || resource_server.is_some() {
+            ctx.require_admin()?;
+        }
+
+        let resp = auth_oauth_provider
+            .update_client(UpdateClientRequest {
+                id: client_id,
+                owner_id: (!ctx.is_admin()).then_some(user.id),
+                details: Some(input.into()),
+                official,
+                resource_server,
+            })
+            .await?
+            .into_inner();
+
+        resp.client.ok_or_else(RespError::missing_field)?.try_into()
+    }
+}
@@ -17,12 +17,12 @@ use tracing::error;
 ///   See [`AuthOAuthProviderClient::userinfo_endpoint`]
 pub async fn userinfo_endpoint_get(
     Extension(context): Extension<GlobalContext>,
-    OAuthBearerToken(access_token): OAuthBearerToken,
+    OAuthBearerToken { access_token, dpop }: OAuthBearerToken,
 ) -> Result<impl IntoResponse, StatusCode> {
     let mut oauth_provider: AuthOAuthProviderClient<_> = context.service();
 
     let resp = oauth_provider
-        .userinfo_endpoint(UserinfoEndpointRequest { access_token })
+        .userinfo_endpoint(UserinfoEndpointRequest { access_token, dpop })
         .await
         .map_err(|err| {
             error!(?err, "failed to get userinfo");
@@ -4,4 +4,5 @@ pub mod auth_oauth_provider;
 pub mod auth_password_recovery;
 pub mod image;
 pub mod markdown;
+pub mod notification;
 pub mod profile;
@@ -0,0 +1,60 @@
+use crate::context::{ContextExt, ServiceFactory};
+use crate::error::RespError;
+use crate::id_encryption::IdEncryptor;
+use crate::models::ok::OkResp;
+use crate::services::notification::notifications::GNotificationCategory;
+use async_graphql::{Context, ID, Object};
+use bfx_core::service::id_encryption::IdType;
+use bfx_proto::notification::notification_client::NotificationClient;
+use bfx_proto::notification::{MarkAllReadRequest, MarkReadRequest};
+use itertools::Itertools;
+
+#[derive(Default)]
+pub struct MarkNotificationsReadMutation;
+
+#[Object]
+impl MarkNotificationsReadMutation {
+    /// Mark notifications of the current user as read
+    async fn mark_notifications_read(
+        &self,
+        ctx: &Context<'_>,
+        ids: Vec<ID>,
+    ) -> Result<OkResp, RespError> {
+        let mut notification: NotificationClient<_> = ctx.service();
+
+        let user = ctx.require_user()?;
+        let ids = ids
+            .iter()
+            .map(|id| ctx.decrypt_id(IdType::Notification, id))
+            .try_collect()?;
+
+        notification
+            .mark_read(MarkReadRequest {
+                user_id: user.id,
+                ids,
+            })
+            .await?;
+
+        Ok(OkResp)
+    }
+
+    /// Mark all notifications of the current user as read, optionally only in one category
+    async fn mark_all_notifications_read(
+        &self,
+        ctx: &Context<'_>,
+        category: Option<GNotificationCategory>,
+    ) -> Result<OkResp, RespError> {
+        let mut notification: NotificationClient<_> = ctx.service();
+
+        let user = ctx.require_user()?;
+
+        notification
+            .mark_all_read(MarkAllReadRequest {
+                user_id: user.id,
+                category: category.map(Into::into),
+            })
+            .await?;
+
+        Ok(OkResp)
+    }
+}
@@ -0,0 +1,18 @@
+mod mark_notifications_read;
+pub mod notification_preferences;
+mod notification_received;
+pub mod notifications;
+
+use crate::services::notification::mark_notifications_read::MarkNotificationsReadMutation;
+use crate::services::notification::notification_preferences::SetNotificationPreferencesMutation;
+use crate::services::notification::notification_received::NotificationReceivedSubscription;
+use async_graphql::{MergedObject, MergedSubscription};
+
+#[derive(Default, MergedObject)]
+pub struct NotificationMutation(
+    MarkNotificationsReadMutation,
+    SetNotificationPreferencesMutation,
+);
+
+#[derive(Default, MergedSubscription)]
+pub struct NotificationSubscription(NotificationReceivedSubscription);
@@ -0,0 +1,216 @@
+use crate::context::{ContextExt, ServiceFactory};
+use crate::error::RespError;
+use crate::models::user::GUser;
+use crate::services::notification::notifications::GNotificationCategory;
+use async_graphql::{Context, Enum, InputObject, Object, SimpleObject};
+use bfx_proto::notification::notification_client::NotificationClient;
+use bfx_proto::notification::{
+    CategoryPreferences, DigestFrequency, GetNotificationPreferencesRequest,
+    NotificationPreferences, QuietHours, SetCategoryPreferencesRequest, SetDigestFrequencyRequest,
+    SetQuietHoursRequest,
+};
+use itertools::Itertools;
+use o2o::o2o;
+
+/// How a user wants to receive notifications
+#[derive(SimpleObject, o2o)]
+#[graphql(name = "NotificationPreferences")]
+#[try_from_owned(NotificationPreferences, RespError)]
+pub struct GNotificationPreferences {
+    /// Language notifications are sent in
+    lang_id: String,
+    /// Channels of every category
+    #[try_from(~.into_iter().map(TryFrom::try_from).try_collect()?)]
+    categories: Vec<GCategoryPreferences>,
+    /// How often emails of non-mandatory categories are sent
+    #[try_from(~.try_into()?)]
+    digest_frequency: GDigestFrequency,
+    /// IANA timezone the quiet hours are in, for example `Europe/Berlin`
+    timezone: String,
+    /// Daily window in which non-mandatory notifications are held back
+    #[try_from(~.map(Into::into))]
+    quiet_hours: Option<GQuietHours>,
+}
+
+/// Daily window in which notifications are held back, in minutes after midnight
+///
+/// The window wraps around midnight if the start is after the end.
+#[derive(SimpleObject, InputObject, o2o)]
+#[graphql(name = "QuietHours", input_name = "QuietHoursInput")]
+#[map_owned(QuietHours)]
+pub struct GQuietHours {
+    /// Start of the quiet hours
+    start_minute: u32,
+    /// End of the quiet hours
+    end_minute: u32,
+}
+
+/// How often emails are sent, batched into one digest email
+#[derive(Copy, Clone, Eq, PartialEq, Enum, o2o)]
+#[graphql(name = "DigestFrequency")]
+#[from(DigestFrequency)]
+#[into(DigestFrequency)]
+pub enum GDigestFrequency {
+    /// Every notification is sent as its own email
+    Immediate,
+    /// At most one email per day
+    Daily,
+    /// At most one email per week
+    Weekly,
+}
+
+impl TryFrom<i32> for GDigestFrequency {
+    type Error = RespError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        DigestFrequency::try_from(value)
+            .map(Into::into)
+            .map_err(|_| RespError::out_of_sync())
+    }
+}
+
+/// Channels a category of notifications is delivered on
+#[derive(SimpleObject, o2o)]
+#[graphql(name = "NotificationCategoryPreferences")]
+#[try_from_owned(CategoryPreferences, RespError)]
+pub struct GCategoryPreferences {
+    /// The category
+    #[try_from(~.try_into()?)]
+    category: GNotificationCategory,
+    /// Whether notifications are shown in-app
+    in_app: bool,
+    /// Whether notifications are sent by email
+    email: bool,
+    /// Whether the category can't be disabled (for example security notifications)
+    mandatory: bool,
+}
+
+/// Channels to receive a category of notifications on
+#[derive(InputObject)]
+#[graphql(name = "NotificationCategoryPreferencesInput")]
+pub struct GCategoryPreferencesInput {
+    /// The category
+    category: GNotificationCategory,
+    /// Whether notifications should be shown in-app
+    in_app: bool,
+    /// Whether notifications should be sent by email
+    email: bool,
+}
+
+impl From<GCategoryPreferencesInput> for CategoryPreferences {
+    fn from(value: GCategoryPreferencesInput) -> Self {
+        Self {
+            category: value.category.into(),
+            in_app: value.in_app,
+            email: value.email,
+            mandatory: false,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct SetNotificationPreferencesMutation;
+
+#[Object]
+impl SetNotificationPreferencesMutation {
+    /// Choose the channels the current user receives categories of notifications on
+    ///
+    /// Categories that aren't given are left unchanged, mandatory categories can't be disabled.
+    async fn set_notification_category_preferences(
+        &self,
+        ctx: &Context<'_>,
+        categories: Vec<GCategoryPreferencesInput>,
+    ) -> Result<GNotificationPreferences, RespError> {
+        let mut notification: NotificationClient<_> = ctx.service();
+
+        let user = ctx.require_user()?;
+
+        notification
+            .set_category_preferences(SetCategoryPreferencesRequest {
+                user_id: user.id,
+                categories: categories.into_iter().map(Into::into).collect(),
+            })
+            .await?
+            .into_inner()
+            .preferences
+            .ok_or_else(RespError::missing_field)?
+            .try_into()
+    }
+
+    /// Choose how often the current user receives emails of non-mandatory categories
+    ///
+    /// Mandatory categories (for example security notifications) are always sent immediately.
+    async fn set_notification_digest_frequency(
+        &self,
+        ctx: &Context<'_>,
+        digest_frequency: GDigestFrequency,
+    ) -> Result<GNotificationPreferences, RespError> {
+        let mut notification: NotificationClient<_> = ctx.service();
+
+        let user = ctx.require_user()?;
+        let digest_frequency: DigestFrequency = digest_frequency.into();
+
+        notification
+            .set_digest_frequency(SetDigestFrequencyRequest {
+                user_id: user.id,
+                digest_frequency: digest_frequency.into(),
+            })
+            .await?
+            .into_inner()
+            .preferences
+            .ok_or_else(RespError::missing_field)?
+            .try_into()
+    }
+
+    /// Set the timezone and quiet hours of the current user
+    ///
+    /// Non-mandatory notifications arriving during the quiet hours are delivered once they end.
+    /// Omitting the quiet hours disables them.
+    async fn set_notification_quiet_hours(
+        &self,
+        ctx: &Context<'_>,
+        timezone: String,
+        quiet_hours: Option<GQuietHours>,
+    ) -> Result<GNotificationPreferences, RespError> {
+        let mut notification: NotificationClient<_> = ctx.service();
+
+        let user = ctx.require_user()?;
+
+        notification
+            .set_quiet_hours(SetQuietHoursRequest {
+                user_id: user.id,
+                timezone,
+                quiet_hours: quiet_hours.map(Into::into),
+            })
+            .await?
+            .into_inner()
+            .preferences
+            .ok_or_else(RespError::missing_field)?
+            .try_into()
+    }
+}
+
+impl GUser {
+    /// Get the notification preferences of the user
+    ///
+    /// # Errors
+    ///
+    /// - If the user is not the same as the requester and not an admin
+    /// - If the underlying RPC call fails
+    pub async fn _notification_preferences(
+        &self,
+        ctx: &Context<'_>,
+    ) -> Result<GNotificationPreferences, RespError> {
+        ctx.require_self_or_admin(self._id)?;
+
+        let mut notification: NotificationClient<_> = ctx.service();
+
+        notification
+            .get_notification_preferences(GetNotificationPreferencesRequest { user_id: self._id })
+            .await?
+            .into_inner()
+            .preferences
+            .ok_or_else(RespError::missing_field)?
+            .try_into()
+    }
+}
@@ -0,0 +1,34 @@
+use crate::context::{ContextExt, ServiceFactory};
+use crate::error::RespError;
+use crate::guards::ScopeGuard;
+use crate::services::notification::notifications::GNotification;
+use async_graphql::{Context, Subscription};
+use bfx_proto::notification::SubscribeNotificationsRequest;
+use bfx_proto::notification::notification_client::NotificationClient;
+use futures_util::{Stream, StreamExt};
+
+#[derive(Default)]
+pub struct NotificationReceivedSubscription;
+
+#[Subscription]
+impl NotificationReceivedSubscription {
+    /// New in-app notifications of the logged-in user, as they are sent
+    ///
+    /// Notifications sent while not subscribed are not replayed, query `me.notifications` to catch up.
+    #[graphql(guard = "ScopeGuard::new(\"notifications:read\")")]
+    async fn notification_received(
+        &self,
+        ctx: &Context<'_>,
+    ) -> Result<impl Stream<Item = Result<GNotification, RespError>>, RespError> {
+        let user = ctx.require_scoped_user()?;
+
+        let mut notification: NotificationClient<_> = ctx.service();
+
+        let stream = notification
+            .subscribe_notifications(SubscribeNotificationsRequest { user_id: user.id })
+            .await?
+            .into_inner();
+
+        Ok(stream.map(|notification| GNotification::try_from(notification?)))
+    }
+}
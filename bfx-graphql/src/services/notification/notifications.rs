@@ -0,0 +1,156 @@
+use crate::context::{ContextExt, ServiceFactory};
+use crate::error::RespError;
+use crate::id_encryption::IdEncryptor;
+use crate::models::user::GUser;
+use async_graphql::{Context, Enum, ID, SimpleObject};
+use bfx_core::service::id_encryption::IdType;
+use bfx_graphql_derive::complex_object_ext;
+use bfx_proto::notification::notification_client::NotificationClient;
+use bfx_proto::notification::{
+    InAppNotification, ListNotificationsRequest, NotificationCategory, UnreadCountRequest,
+};
+use chrono::{DateTime, Utc};
+use itertools::Itertools;
+use o2o::o2o;
+
+/// What a notification is about
+#[derive(Copy, Clone, Eq, PartialEq, Enum, o2o)]
+#[graphql(name = "NotificationCategory")]
+#[from(NotificationCategory)]
+#[into(NotificationCategory)]
+pub enum GNotificationCategory {
+    /// News about the platform
+    Announcements,
+    /// Account security (logins, password changes, etc.)
+    Auth,
+}
+
+impl TryFrom<i32> for GNotificationCategory {
+    type Error = RespError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        NotificationCategory::try_from(value)
+            .map(Into::into)
+            .map_err(|_| RespError::out_of_sync())
+    }
+}
+
+impl From<GNotificationCategory> for i32 {
+    fn from(value: GNotificationCategory) -> Self {
+        let category: NotificationCategory = value.into();
+        category.into()
+    }
+}
+
+/// An in-app notification, rendered in the user's language
+#[derive(SimpleObject, o2o)]
+#[graphql(complex, name = "Notification")]
+#[try_from_owned(InAppNotification, RespError)]
+pub struct GNotification {
+    #[graphql(skip)]
+    id: i64,
+    /// ID of the notification definition, for example `password_change`
+    definition_id: String,
+    /// What the notification is about
+    #[try_from(~.try_into()?)]
+    category: GNotificationCategory,
+    /// Rendered title
+    title: String,
+    /// Rendered body
+    body: String,
+    /// When the notification was marked as read
+    #[try_from(~.map(TryInto::try_into).transpose()?)]
+    read_at: Option<DateTime<Utc>>,
+    /// When the notification was sent
+    #[try_from(~.ok_or_else(RespError::missing_field)?.try_into()?)]
+    created_at: DateTime<Utc>,
+}
+
+#[complex_object_ext]
+impl GNotification {
+    /// Unique ID of the notification
+    id!(id => id, Notification);
+}
+
+/// A page of notifications
+#[derive(SimpleObject)]
+#[graphql(name = "NotificationPage")]
+pub struct GNotificationPage {
+    /// Notifications on this page, newest first
+    notifications: Vec<GNotification>,
+    /// Cursor for the next page, not set if this is the last page
+    next_cursor: Option<ID>,
+}
+
+impl GUser {
+    /// Get the in-app notifications of the user
+    ///
+    /// # Errors
+    ///
+    /// - If the user is not the same as the requester and not an admin
+    /// - If the cursor is invalid
+    /// - If the underlying RPC call fails
+    pub async fn _notifications(
+        &self,
+        ctx: &Context<'_>,
+        after: Option<ID>,
+        first: Option<u32>,
+        category: Option<GNotificationCategory>,
+        unread_only: bool,
+    ) -> Result<GNotificationPage, RespError> {
+        ctx.require_scoped_self_or_admin(self._id)?;
+
+        let cursor = after
+            .map(|after| ctx.decrypt_id(IdType::Notification, &after))
+            .transpose()?;
+
+        let mut notification: NotificationClient<_> = ctx.service();
+
+        let resp = notification
+            .list_notifications(ListNotificationsRequest {
+                user_id: self._id,
+                cursor,
+                limit: first,
+                category: category.map(Into::into),
+                unread_only,
+            })
+            .await?
+            .into_inner();
+
+        Ok(GNotificationPage {
+            notifications: resp
+                .notifications
+                .into_iter()
+                .map(TryFrom::try_from)
+                .try_collect()?,
+            next_cursor: resp
+                .next_cursor
+                .map(|cursor| ctx.encrypt_id(IdType::Notification, cursor)),
+        })
+    }
+
+    /// Count the unread in-app notifications of the user
+    ///
+    /// # Errors
+    ///
+    /// - If the user is not the same as the requester and not an admin
+    /// - If the underlying RPC call fails
+    pub async fn _unread_notification_count(
+        &self,
+        ctx: &Context<'_>,
+        category: Option<GNotificationCategory>,
+    ) -> Result<i64, RespError> {
+        ctx.require_scoped_self_or_admin(self._id)?;
+
+        let mut notification: NotificationClient<_> = ctx.service();
+
+        Ok(notification
+            .unread_count(UnreadCountRequest {
+                user_id: self._id,
+                category: category.map(Into::into),
+            })
+            .await?
+            .into_inner()
+            .count)
+    }
+}
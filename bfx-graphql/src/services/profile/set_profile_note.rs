@@ -1,5 +1,6 @@
 use crate::context::{ContextExt, ServiceFactory};
 use crate::error::RespError;
+use crate::guards::ScopeGuard;
 use crate::services::profile::user_profile::GProfile;
 use async_graphql::{Context, Object};
 use bfx_proto::profile::SetNoteRequest;
@@ -11,6 +12,7 @@ pub struct SetProfileNoteMutation;
 #[Object]
 impl SetProfileNoteMutation {
     /// Update a personal note for a user
+    #[graphql(guard = "ScopeGuard::new(\"profile:write\")")]
     async fn set_profile_note(
         &self,
         ctx: &Context<'_>,
@@ -19,7 +21,7 @@ impl SetProfileNoteMutation {
     ) -> Result<GProfile, RespError> {
         let mut profile: ProfileClient<_> = ctx.service();
 
-        let for_user = ctx.require_user()?;
+        let for_user = ctx.require_scoped_user()?;
 
         let profile = profile
             .set_note(SetNoteRequest {
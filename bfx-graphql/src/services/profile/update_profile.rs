@@ -1,5 +1,6 @@
 use crate::context::{ContextExt, ServiceFactory};
 use crate::error::RespError;
+use crate::guards::ScopeGuard;
 use crate::services::profile::user_profile::GProfile;
 use async_graphql::{Context, InputObject, Object, SimpleObject};
 use bfx_proto::profile::UpdateProfileRequest;
@@ -33,6 +34,7 @@ struct UpdateProfileResponse {
 #[Object]
 impl UpdateProfileMutation {
     /// Update any profile information except for the username and notes
+    #[graphql(guard = "ScopeGuard::new(\"profile:write\")")]
     async fn update_profile(
         &self,
         ctx: &Context<'_>,
@@ -40,7 +42,7 @@ impl UpdateProfileMutation {
     ) -> Result<UpdateProfileResponse, RespError> {
         let mut profile: ProfileClient<_> = ctx.service();
 
-        let user = ctx.require_user()?;
+        let user = ctx.require_scoped_user()?;
 
         let resp = profile
             .update_profile(UpdateProfileRequest {
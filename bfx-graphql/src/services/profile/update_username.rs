@@ -1,5 +1,6 @@
 use crate::context::{ContextExt, ServiceFactory};
 use crate::error::RespError;
+use crate::guards::ScopeGuard;
 use crate::services::profile::user_profile::GProfile;
 use async_graphql::{Context, Object};
 use bfx_proto::profile::UpdateUsernameRequest;
@@ -11,6 +12,7 @@ pub struct UpdateUsernameMutation;
 #[Object]
 impl UpdateUsernameMutation {
     /// Change the username of the current user (and create a profile if needed)
+    #[graphql(guard = "ScopeGuard::new(\"profile:write\")")]
     async fn update_username(
         &self,
         ctx: &Context<'_>,
@@ -18,7 +20,7 @@ impl UpdateUsernameMutation {
     ) -> Result<GProfile, RespError> {
         let mut profile: ProfileClient<_> = ctx.service();
 
-        let user = ctx.require_user()?;
+        let user = ctx.require_scoped_user()?;
 
         let profile = profile
             .update_username(UpdateUsernameRequest {
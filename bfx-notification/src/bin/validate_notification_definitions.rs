@@ -0,0 +1,196 @@
+//! Render every notification definition in every language with sample params
+//!
+//! Renders locally (without bfx-translation), so it can run in CI. Fails if a template is
+//! invalid, uses an undeclared param, or uses a translation key that doesn't exist.
+
+use anyhow::{Context, anyhow, bail};
+use bfx_core::logging::setup_logging;
+use bfx_notification::NotificationService;
+use bfx_notification::definition::NotificationDefinition;
+use bfx_notification::registry::DefinitionRegistry;
+use bfx_proto::param_value::ParamValue;
+use bfx_proto::translation::ConditionalString;
+use fluent::concurrent::FluentBundle;
+use fluent::{FluentArgs, FluentResource, FluentValue};
+use minijinja::value::{Kwargs, ValueKind};
+use minijinja::{Environment, ErrorKind, UndefinedBehavior, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Component, Path};
+use std::sync::Arc;
+use tracing::{error, info};
+use unic_langid::LanguageIdentifier;
+use walkdir::WalkDir;
+
+type Bundle = Arc<FluentBundle<FluentResource>>;
+
+fn main() -> anyhow::Result<()> {
+    setup_logging();
+
+    let definitions_dir = std::env::var("NOTIFICATION_DEFINITIONS_DIR")
+        .unwrap_or_else(|_| "./notifications".to_string());
+    let translations_dir =
+        std::env::var("TRANSLATIONS_DIR").unwrap_or_else(|_| "./translations".to_string());
+
+    let registry = DefinitionRegistry::load(Path::new(&definitions_dir))?;
+    let bundles = load_bundles(Path::new(&translations_dir))?;
+
+    let mut definitions = registry.iter().collect::<Vec<_>>();
+    definitions.sort_by(|a, b| a.id.cmp(&b.id));
+
+    let mut failures = 0;
+    for definition in &definitions {
+        for (lang_id, bundle) in &bundles {
+            if let Err(err) = render_definition(definition, bundle) {
+                error!(definition = definition.id, %lang_id, "{err:#}");
+                failures += 1;
+            }
+        }
+    }
+
+    if failures > 0 {
+        bail!("{failures} renders failed");
+    }
+
+    info!(
+        definitions = definitions.len(),
+        languages = bundles.len(),
+        "all notification definitions rendered"
+    );
+
+    Ok(())
+}
+
+/// Load the Fluent resources of every language, the first directory in the path is the language
+fn load_bundles(dir: &Path) -> anyhow::Result<BTreeMap<String, Bundle>> {
+    let mut resources: BTreeMap<String, Vec<FluentResource>> = BTreeMap::new();
+
+    for entry in WalkDir::new(dir) {
+        let path = entry?.into_path();
+        if path.extension().unwrap_or_default() != "ftl" {
+            continue;
+        }
+
+        let Some(Component::Normal(lang_id)) = path.strip_prefix(dir)?.components().next() else {
+            bail!("invalid resource path: {}", path.display());
+        };
+
+        let source = std::fs::read_to_string(&path)?;
+        let resource = FluentResource::try_new(source)
+            .map_err(|(_, errors)| anyhow!("failed to parse {}: {errors:?}", path.display()))?;
+
+        resources
+            .entry(lang_id.to_string_lossy().into_owned())
+            .or_default()
+            .push(resource);
+    }
+
+    resources
+        .into_iter()
+        .map(|(lang_id, resources)| {
+            let lang: LanguageIdentifier = lang_id
+                .parse()
+                .with_context(|| format!("invalid language `{lang_id}`"))?;
+
+            let mut bundle = FluentBundle::new_concurrent(vec![lang]);
+            for resource in resources {
+                bundle
+                    .add_resource(resource)
+                    .map_err(|errors| anyhow!("duplicate messages in `{lang_id}`: {errors:?}"))?;
+            }
+
+            Ok((lang_id, Arc::new(bundle)))
+        })
+        .collect()
+}
+
+/// Render every string of a definition, including every conditional branch
+fn render_definition(definition: &NotificationDefinition, bundle: &Bundle) -> anyhow::Result<()> {
+    let mut strings: Vec<(&str, Vec<ConditionalString>)> = vec![];
+
+    if let Some(in_app) = &definition.in_app {
+        strings.push(("in-app.title", in_app.title.clone().into()));
+        strings.push(("in-app.body", in_app.body.clone().into()));
+    }
+
+    if let Some(email) = &definition.email {
+        let body = email.body.clone().into();
+        let body = if email.include_template {
+            NotificationService::wrap_string_set_in_template(body)
+        } else {
+            body
+        };
+
+        strings.push(("email.subject", email.subject.clone().into()));
+        strings.push(("email.body", body));
+    }
+
+    let env = create_jinja_env(bundle.clone());
+    let context = definition
+        .sample_params()
+        .into_iter()
+        .map(|(name, value)| {
+            let value = match value.param_value {
+                Some(ParamValue::String(string)) => Value::from(string),
+                Some(ParamValue::Number(number)) => Value::from(number),
+                None => Value::from(""),
+            };
+            (name, value)
+        })
+        .collect::<HashMap<_, _>>();
+
+    for (name, conditionals) in strings {
+        for conditional in conditionals {
+            if let Some(if_) = &conditional.r#if {
+                env.compile_expression(if_)
+                    .and_then(|expr| expr.eval(&context))
+                    .with_context(|| format!("{name}: condition `{if_}`"))?;
+            }
+
+            env.render_str(&conditional.value, &context)
+                .with_context(|| format!("{name}: failed to render"))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Like the environment of bfx-translation, but undefined variables are errors
+fn create_jinja_env(bundle: Bundle) -> Environment<'static> {
+    let mut env = Environment::new();
+    env.set_undefined_behavior(UndefinedBehavior::Strict);
+
+    env.add_function("t", move |key: String, kwargs: Kwargs| {
+        let mut args = FluentArgs::new();
+        for name in kwargs.args() {
+            let value: Value = kwargs.get(name)?;
+            let value = match value.kind() {
+                ValueKind::Number => FluentValue::from(value.as_i64().unwrap_or_default()),
+                _ => FluentValue::from(value.to_string()),
+            };
+            args.set(name.to_string(), value);
+        }
+
+        let pattern = bundle
+            .get_message(&key)
+            .and_then(|message| message.value())
+            .ok_or_else(|| {
+                minijinja::Error::new(
+                    ErrorKind::InvalidOperation,
+                    format!("translation key `{key}` not found"),
+                )
+            })?;
+
+        let mut errors = vec![];
+        let text = bundle.format_pattern(pattern, Some(&args), &mut errors);
+        if !errors.is_empty() {
+            return Err(minijinja::Error::new(
+                ErrorKind::InvalidOperation,
+                format!("translation `{key}` failed: {errors:?}"),
+            ));
+        }
+
+        Ok(text.into_owned())
+    });
+
+    env
+}
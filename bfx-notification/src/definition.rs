@@ -1,31 +1,175 @@
+use bfx_proto::ParamValue;
+use bfx_proto::notification::NotificationCategory as ProtoNotificationCategory;
+use bfx_proto::param_value::ParamValue as ProtoParamValue;
 use bfx_proto::translation::ConditionalString as ProtoConditionalString;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 
 #[derive(Clone, Serialize, Deserialize, JsonSchema)]
-#[serde(rename_all = "kebab-case")]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub struct NotificationDefinition {
     pub id: String,
     pub category: NotificationCategory,
+    /// Parameters that the templates use, by name
+    #[serde(default)]
+    pub params: BTreeMap<String, ParamDefinition>,
     pub in_app: Option<InAppDefinition>,
     pub email: Option<EmailDefinition>,
 }
 
+impl NotificationDefinition {
+    /// Check that `params` match the declared parameters
+    ///
+    /// # Errors
+    ///
+    /// - If a parameter is not declared
+    /// - If a required parameter is missing
+    /// - If a parameter has the wrong type
+    pub fn check_params(&self, params: &HashMap<String, ParamValue>) -> Result<(), String> {
+        if let Some(name) = params.keys().find(|name| !self.params.contains_key(*name)) {
+            return Err(format!("unknown param `{name}`"));
+        }
+
+        for (name, definition) in &self.params {
+            match params.get(name) {
+                None if definition.required => return Err(format!("missing param `{name}`")),
+                Some(value) if !definition.r#type.matches(value) => {
+                    return Err(format!(
+                        "param `{name}` must be a {}",
+                        definition.r#type.as_str()
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get params to render the definition with when validating it
+    #[must_use]
+    pub fn sample_params(&self) -> HashMap<String, ParamValue> {
+        self.params
+            .iter()
+            .map(|(name, definition)| (name.clone(), definition.r#type.sample(name)))
+            .collect()
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ParamDefinition {
+    pub r#type: ParamType,
+    #[serde(default = "yes")]
+    pub required: bool,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum ParamType {
+    String,
+    Number,
+}
+
+impl ParamType {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::String => "string",
+            Self::Number => "number",
+        }
+    }
+
+    #[must_use]
+    pub const fn matches(self, value: &ParamValue) -> bool {
+        matches!(
+            (self, &value.param_value),
+            (Self::String, Some(ProtoParamValue::String(_)))
+                | (Self::Number, Some(ProtoParamValue::Number(_)))
+        )
+    }
+
+    #[must_use]
+    pub fn sample(self, name: &str) -> ParamValue {
+        match self {
+            Self::String => ProtoParamValue::String(format!("[{name}]")).into(),
+            Self::Number => ProtoParamValue::Number(42).into(),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub enum NotificationCategory {
     Announcements,
     Auth,
 }
 
+impl NotificationCategory {
+    pub const ALL: [Self; 2] = [Self::Announcements, Self::Auth];
+
+    /// Whether users can't opt out of the category (on any channel)
+    #[must_use]
+    pub const fn is_mandatory(self) -> bool {
+        matches!(self, Self::Auth)
+    }
+
+    /// Name of the category, as stored in the database
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Announcements => "announcements",
+            Self::Auth => "auth",
+        }
+    }
+
+    /// Convert a category sent over gRPC
+    #[must_use]
+    pub fn from_proto(value: i32) -> Option<Self> {
+        ProtoNotificationCategory::try_from(value)
+            .ok()
+            .map(Into::into)
+    }
+
+    /// Parse a category stored in the database
+    #[must_use]
+    pub fn from_db(value: &str) -> Option<Self> {
+        match value {
+            "announcements" => Some(Self::Announcements),
+            "auth" => Some(Self::Auth),
+            _ => None,
+        }
+    }
+}
+
+impl From<ProtoNotificationCategory> for NotificationCategory {
+    fn from(value: ProtoNotificationCategory) -> Self {
+        match value {
+            ProtoNotificationCategory::Announcements => Self::Announcements,
+            ProtoNotificationCategory::Auth => Self::Auth,
+        }
+    }
+}
+
+impl From<NotificationCategory> for ProtoNotificationCategory {
+    fn from(value: NotificationCategory) -> Self {
+        match value {
+            NotificationCategory::Announcements => Self::Announcements,
+            NotificationCategory::Auth => Self::Auth,
+        }
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct InAppDefinition {
     pub title: StringSet,
     pub body: StringSet,
 }
 
 #[derive(Clone, Serialize, Deserialize, JsonSchema)]
-#[serde(rename_all = "kebab-case")]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub struct EmailDefinition {
     pub subject: StringSet,
     pub body: StringSet,
@@ -63,8 +207,115 @@ impl From<StringSet> for Vec<ProtoConditionalString> {
 }
 
 #[derive(Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct ConditionalString {
     #[serde(rename = "if")]
     pub if_: Option<String>,
     pub value: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn definition() -> NotificationDefinition {
+        serde_yml::from_str(
+            r"
+            id: test
+            category: announcements
+            params:
+              client:
+                type: string
+              count:
+                type: number
+              note:
+                type: string
+                required: false
+            in-app:
+              title: title
+              body: body
+            ",
+        )
+        .unwrap()
+    }
+
+    fn string(value: &str) -> ParamValue {
+        ProtoParamValue::String(value.into()).into()
+    }
+
+    fn number(value: i64) -> ParamValue {
+        ProtoParamValue::Number(value).into()
+    }
+
+    #[test]
+    fn valid_params() {
+        let definition = definition();
+        let mut params = HashMap::from([
+            ("client".to_string(), string("Example")),
+            ("count".to_string(), number(3)),
+        ]);
+        assert_eq!(definition.check_params(&params), Ok(()));
+
+        params.insert("note".into(), string("optional"));
+        assert_eq!(definition.check_params(&params), Ok(()));
+    }
+
+    #[test]
+    fn sample_params_are_valid() {
+        let definition = definition();
+
+        assert_eq!(definition.check_params(&definition.sample_params()), Ok(()));
+    }
+
+    #[test]
+    fn invalid_params() {
+        let definition = definition();
+        let cases = [
+            (vec![("count", number(3))], "missing param `client`"),
+            (
+                vec![("client", string("Example")), ("count", string("3"))],
+                "param `count` must be a number",
+            ),
+            (
+                vec![("client", number(1)), ("count", number(3))],
+                "param `client` must be a string",
+            ),
+            (
+                vec![
+                    ("client", string("Example")),
+                    ("count", number(3)),
+                    ("extra", string("?")),
+                ],
+                "unknown param `extra`",
+            ),
+            (
+                vec![
+                    ("client", ParamValue { param_value: None }),
+                    ("count", number(3)),
+                ],
+                "param `client` must be a string",
+            ),
+        ];
+
+        for (params, error) in cases {
+            let params = params
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value))
+                .collect();
+            assert_eq!(definition.check_params(&params), Err(error.to_string()));
+        }
+    }
+
+    #[test]
+    fn no_declared_params() {
+        let mut definition = definition();
+        definition.params.clear();
+
+        assert_eq!(definition.check_params(&HashMap::new()), Ok(()));
+        assert!(
+            definition
+                .check_params(&HashMap::from([("client".to_string(), string("Example"))]))
+                .is_err()
+        );
+    }
+}
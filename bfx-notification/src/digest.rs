@@ -0,0 +1,323 @@
+use crate::NotificationService;
+use crate::models::notification::RawNotification;
+use bfx_core::status::StatusExt;
+use bfx_proto::auth::auth_core_client::AuthCoreClient;
+use bfx_proto::notification::email::SendEmailRequest;
+use bfx_proto::notification::email::notification_email_client::NotificationEmailClient;
+use bfx_proto::param_map;
+use bfx_proto::translation::ConditionalString;
+use bfx_proto::translation::translation_client::TranslationClient;
+use sqlx::Acquire;
+use std::time::Duration;
+use tokio::time::sleep;
+use tonic::Status;
+use tonic::transport::Channel;
+use tracing::{info, warn};
+
+const DIGEST_CHECK_INTERVAL: Duration = Duration::from_mins(5);
+
+const DIGEST_SUBJECT: &str = r#"{{ t("email-digest-subject", count=count) }}"#;
+// `digest` is already rendered, so it's passed as a param instead of being part of the template
+const DIGEST_BODY: &str = r#"<p>{{ t("email-digest-intro", count=count) }}</p>
+
+{{ digest }}"#;
+
+impl NotificationService {
+    /// Periodically send digest emails to users whose digest period is over
+    pub fn start_digest_scheduler(self) {
+        tokio::spawn(async move {
+            loop {
+                if let Err(err) = self.send_due_digests().await {
+                    warn!(err = %err, "failed to send digests");
+                }
+                sleep(DIGEST_CHECK_INTERVAL).await;
+            }
+        });
+    }
+
+    /// Send the digests of all users whose digest period is over
+    ///
+    /// The preferences of a user are locked while their digest is sent,
+    /// so other replicas skip them instead of sending the same digest.
+    async fn send_due_digests(&self) -> Result<(), Status> {
+        loop {
+            let mut tx = self.db.begin().await.map_err(Status::db)?;
+
+            // users that switched back to immediate delivery get their pending emails right away
+            let Some(user) = sqlx::query!(
+                "select user_id, lang_id from notification.preferences p
+                 where exists (
+                     select 1 from notification.notifications n
+                     where n.user_id = p.user_id and n.email_digest_pending
+                 )
+                 and coalesce(last_digest_at, created_at) + case digest_frequency
+                     when 'daily' then interval '1 day'
+                     when 'weekly' then interval '7 days'
+                     else interval '0'
+                 end <= now()
+                 limit 1
+                 for update skip locked"
+            )
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(Status::db)?
+            else {
+                return Ok(());
+            };
+
+            // pending emails stay pending if this fails and are retried with the next digest.
+            // the savepoint keeps the transaction usable after a database error, so
+            // last_digest_at is still updated and the loop moves on to the next user
+            let mut savepoint = tx.begin().await.map_err(Status::db)?;
+            match self
+                .send_digest(&mut savepoint, user.user_id, user.lang_id)
+                .await
+            {
+                Ok(()) => savepoint.commit().await.map_err(Status::db)?,
+                Err(err) => {
+                    warn!(user.user_id, err = %err, "failed to send digest");
+                    savepoint.rollback().await.map_err(Status::db)?;
+                }
+            }
+
+            sqlx::query!(
+                "update notification.preferences set last_digest_at = now() where user_id = $1",
+                user.user_id,
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(Status::db)?;
+
+            tx.commit().await.map_err(Status::db)?;
+        }
+    }
+
+    /// Render the pending emails of a user into one digest email and send it
+    ///
+    /// Emails that fail to render are left out and stay pending for the next digest.
+    ///
+    /// # Errors
+    ///
+    /// - If rendering the digest itself fails
+    /// - If sending the email fails
+    /// - Miscellaneous internal errors
+    async fn send_digest(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: i64,
+        lang_id: String,
+    ) -> Result<(), Status> {
+        let notifications = sqlx::query_as!(
+            RawNotification,
+            "select * from notification.notifications
+             where user_id = $1 and email_digest_pending
+             order by id",
+            user_id,
+        )
+        .fetch_all(&mut **tx)
+        .await
+        .map_err(Status::db)?;
+
+        let mut translation = TranslationClient::new(self.router.clone());
+
+        let mut digest = String::new();
+        let mut count = 0u32;
+        // emails that are either in the digest or have nothing to send
+        let mut done_ids = Vec::with_capacity(notifications.len());
+        for notification in &notifications {
+            match self
+                .render_digest_entry(&mut translation, notification, &lang_id)
+                .await
+            {
+                Ok(Some(entry)) => {
+                    digest.push_str(&entry);
+                    count += 1;
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    warn!(
+                        user_id,
+                        notification_id = notification.id,
+                        err = %err,
+                        "failed to render email for digest"
+                    );
+                    continue;
+                }
+            }
+            done_ids.push(notification.id);
+        }
+
+        let mut auth_core = AuthCoreClient::new(self.router.clone());
+        let to = auth_core
+            .get_user_by_id(user_id)
+            .await?
+            .and_then(|user| user.email);
+
+        // without an address, the emails can't be sent later either
+        if let Some(to) = to.filter(|_| count > 0) {
+            let subject = translation
+                .render_string_set_ext(
+                    lang_id.clone(),
+                    vec![ConditionalString {
+                        r#if: None,
+                        value: DIGEST_SUBJECT.to_string(),
+                    }],
+                    param_map! { "count" => count },
+                )
+                .await?;
+
+            let body = translation
+                .render_string_set_ext(
+                    lang_id,
+                    Self::wrap_string_set_in_template(vec![ConditionalString {
+                        r#if: None,
+                        value: DIGEST_BODY.to_string(),
+                    }]),
+                    param_map! { "count" => count, "digest" => digest },
+                )
+                .await?;
+
+            let mut notification_email = NotificationEmailClient::new(self.router.clone());
+            notification_email
+                .send_email(SendEmailRequest {
+                    to,
+                    to_name: None,
+                    subject,
+                    body_html: body,
+                })
+                .await?;
+
+            info!(user_id, count, "sent digest");
+        }
+
+        sqlx::query!(
+            "update notification.notifications
+             set email_digest_pending = false
+             where id = any($1)",
+            &done_ids,
+        )
+        .execute(&mut **tx)
+        .await
+        .map_err(Status::db)?;
+
+        Ok(())
+    }
+
+    /// Render a pending email as a section of the digest
+    ///
+    /// Returns `None` if the notification no longer has an email.
+    async fn render_digest_entry(
+        &self,
+        translation: &mut TranslationClient<Channel>,
+        notification: &RawNotification,
+        lang_id: &str,
+    ) -> Result<Option<String>, Status> {
+        let Some(email) = self.current_definition(notification)?.email else {
+            return Ok(None);
+        };
+        let params = notification.decode_params()?;
+
+        let subject = translation
+            .render_string_set_ext(lang_id.to_string(), email.subject.into(), params.clone())
+            .await?;
+        let body = translation
+            .render_string_set_ext(lang_id.to_string(), email.body.into(), params)
+            .await?;
+
+        Ok(Some(format!("<h3>{subject}</h3>\n\n{body}\n\n")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::definition::NotificationCategory;
+    use crate::models::preferences::{DigestFrequency, RawNotificationPreferences};
+    use crate::test_utils::email_definition;
+    use bfx_core::service::database::Db;
+    use bfx_proto::auth::User;
+    use sqlx::types::chrono::{DateTime, Utc};
+    use std::collections::HashMap;
+
+    async fn deliver(
+        service: &NotificationService,
+        category: NotificationCategory,
+        preferences: &RawNotificationPreferences,
+    ) -> i64 {
+        let mut conn = service.db.acquire().await.unwrap();
+        service
+            .deliver_notification(
+                &mut conn,
+                &email_definition(category),
+                1,
+                // without an address, immediate emails are skipped instead of failing
+                Some(User::default()),
+                HashMap::new(),
+                preferences,
+            )
+            .await
+            .unwrap()
+    }
+
+    async fn email_digest_pending(db: &Db, id: i64) -> bool {
+        sqlx::query_scalar(
+            "select email_digest_pending from notification.notifications where id = $1",
+        )
+        .bind(id)
+        .fetch_one(db)
+        .await
+        .unwrap()
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn leave_emails_for_the_digest(db: Db) {
+        let service = NotificationService::for_tests(db);
+        let daily = RawNotificationPreferences {
+            user_id: 1,
+            digest_frequency: DigestFrequency::Daily.as_str().to_string(),
+            ..Default::default()
+        };
+
+        let id = deliver(&service, NotificationCategory::Announcements, &daily).await;
+        assert!(email_digest_pending(&service.db, id).await);
+
+        let id = deliver(&service, NotificationCategory::Auth, &daily).await;
+        assert!(!email_digest_pending(&service.db, id).await);
+
+        let immediate = RawNotificationPreferences {
+            user_id: 1,
+            ..Default::default()
+        };
+        let id = deliver(&service, NotificationCategory::Announcements, &immediate).await;
+        assert!(!email_digest_pending(&service.db, id).await);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn failed_digests_stay_pending(db: Db) {
+        let service = NotificationService::for_tests(db);
+
+        sqlx::query(
+            "insert into notification.preferences (user_id, lang_id, digest_frequency, last_digest_at)
+             values (1, 'en', 'daily', now() - interval '2 days')",
+        )
+        .execute(&service.db)
+        .await
+        .unwrap();
+        let preferences = service.get_raw_notification_preferences(1).await.unwrap();
+        let id = deliver(&service, NotificationCategory::Announcements, &preferences).await;
+
+        // rendering fails without a router, which shouldn't stop the scheduler
+        service.send_due_digests().await.unwrap();
+
+        assert!(email_digest_pending(&service.db, id).await);
+
+        // retried with the next digest, not on every check
+        let last_digest_at: DateTime<Utc> = sqlx::query_scalar(
+            "select last_digest_at from notification.preferences where user_id = 1",
+        )
+        .fetch_one(&service.db)
+        .await
+        .unwrap();
+        assert!(last_digest_at > preferences.last_digest_at.unwrap());
+    }
+}
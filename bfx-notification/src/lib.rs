@@ -1,19 +1,52 @@
 pub mod definition;
+mod digest;
 mod methods;
 pub mod models;
+mod realtime;
+pub mod registry;
+mod scheduler;
+#[cfg(test)]
+mod test_utils;
 
+use crate::methods::subscribe_notifications::InAppNotificationStream;
+use crate::realtime::NotificationReceived;
+use crate::registry::DefinitionRegistry;
 use bfx_core::service::database::Db;
 use bfx_proto::notification::notification_server::Notification;
 use bfx_proto::notification::{
-    GetNotificationPreferencesReply, GetNotificationPreferencesRequest, SendNotificationReply,
-    SendNotificationRequest, SetNotificationPreferencesReply, SetNotificationPreferencesRequest,
+    GetNotificationPreferencesReply, GetNotificationPreferencesRequest, ListNotificationsReply,
+    ListNotificationsRequest, MarkAllReadReply, MarkAllReadRequest, MarkReadReply, MarkReadRequest,
+    SendNotificationReply, SendNotificationRequest, SetCategoryPreferencesReply,
+    SetCategoryPreferencesRequest, SetDigestFrequencyReply, SetDigestFrequencyRequest,
+    SetNotificationPreferencesReply, SetNotificationPreferencesRequest, SetQuietHoursReply,
+    SetQuietHoursRequest, SubscribeNotificationsRequest, UnreadCountReply, UnreadCountRequest,
 };
+use std::sync::Arc;
+use tokio::sync::broadcast;
 use tonic::transport::Channel;
 use tonic::{Request, Response, Status};
 
+// new notifications are rendered per subscriber, so this only has to buffer bursts
+const NOTIFICATION_RECEIVED_CAPACITY: usize = 1024;
+
+#[derive(Clone)]
 pub struct NotificationService {
     pub db: Db,
     pub router: Channel,
+    pub definitions: Arc<DefinitionRegistry>,
+    received: broadcast::Sender<NotificationReceived>,
+}
+
+impl NotificationService {
+    #[must_use]
+    pub fn new(db: Db, router: Channel, definitions: DefinitionRegistry) -> Self {
+        Self {
+            db,
+            router,
+            definitions: Arc::new(definitions),
+            received: broadcast::Sender::new(NOTIFICATION_RECEIVED_CAPACITY),
+        }
+    }
 }
 
 #[tonic::async_trait]
@@ -38,4 +71,62 @@ impl Notification for NotificationService {
     ) -> Result<Response<SetNotificationPreferencesReply>, Status> {
         self.set_notification_preferences(request).await
     }
+
+    async fn set_category_preferences(
+        &self,
+        request: Request<SetCategoryPreferencesRequest>,
+    ) -> Result<Response<SetCategoryPreferencesReply>, Status> {
+        self.set_category_preferences(request).await
+    }
+
+    async fn set_digest_frequency(
+        &self,
+        request: Request<SetDigestFrequencyRequest>,
+    ) -> Result<Response<SetDigestFrequencyReply>, Status> {
+        self.set_digest_frequency(request).await
+    }
+
+    async fn set_quiet_hours(
+        &self,
+        request: Request<SetQuietHoursRequest>,
+    ) -> Result<Response<SetQuietHoursReply>, Status> {
+        self.set_quiet_hours(request).await
+    }
+
+    async fn list_notifications(
+        &self,
+        request: Request<ListNotificationsRequest>,
+    ) -> Result<Response<ListNotificationsReply>, Status> {
+        self.list_notifications(request).await
+    }
+
+    async fn mark_read(
+        &self,
+        request: Request<MarkReadRequest>,
+    ) -> Result<Response<MarkReadReply>, Status> {
+        self.mark_read(request).await
+    }
+
+    async fn mark_all_read(
+        &self,
+        request: Request<MarkAllReadRequest>,
+    ) -> Result<Response<MarkAllReadReply>, Status> {
+        self.mark_all_read(request).await
+    }
+
+    async fn unread_count(
+        &self,
+        request: Request<UnreadCountRequest>,
+    ) -> Result<Response<UnreadCountReply>, Status> {
+        self.unread_count(request).await
+    }
+
+    type SubscribeNotificationsStream = InAppNotificationStream;
+
+    async fn subscribe_notifications(
+        &self,
+        request: Request<SubscribeNotificationsRequest>,
+    ) -> Result<Response<Self::SubscribeNotificationsStream>, Status> {
+        self.subscribe_notifications(request).await
+    }
 }
@@ -1,18 +1,26 @@
 use bfx_core::logging::setup_logging;
 use bfx_core::service::client::require_router;
 use bfx_core::service::database::require_db;
+use bfx_core::service::environment::require_env;
 use bfx_core::service::start_service;
 use bfx_notification::NotificationService;
+use bfx_notification::registry::DefinitionRegistry;
 use bfx_proto::notification::notification_server::NotificationServer;
+use std::path::Path;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     setup_logging();
 
-    let service = NotificationService {
-        db: require_db().await?,
-        router: require_router()?,
-    };
+    let definitions_dir = require_env("NOTIFICATION_DEFINITIONS_DIR")
+        .unwrap_or_else(|_| "./notifications".to_string());
+    let definitions = DefinitionRegistry::load(Path::new(&definitions_dir))?;
+
+    let service = NotificationService::new(require_db().await?, require_router()?, definitions);
+
+    service.clone().start_notification_listener();
+    service.clone().start_digest_scheduler();
+    service.clone().start_scheduler();
 
     start_service(NotificationServer::new(service)).await?;
 
@@ -1,7 +1,13 @@
 use crate::NotificationService;
-use crate::models::preferences::RawNotificationPreferences;
+use crate::definition::NotificationCategory;
+use crate::models::preferences::{
+    CategoryPreferences, RawCategoryPreferences, RawNotificationPreferences,
+};
 use bfx_core::status::StatusExt;
-use bfx_proto::notification::{GetNotificationPreferencesReply, GetNotificationPreferencesRequest};
+use bfx_proto::notification::DigestFrequency as ProtoDigestFrequency;
+use bfx_proto::notification::{
+    GetNotificationPreferencesReply, GetNotificationPreferencesRequest, NotificationPreferences,
+};
 use tonic::{Request, Response, Status};
 
 impl NotificationService {
@@ -17,14 +23,38 @@ impl NotificationService {
         let request = request.into_inner();
 
         let preferences = self
-            .get_raw_notification_preferences(request.user_id)
+            .get_full_notification_preferences(request.user_id)
             .await?;
 
         Ok(Response::new(GetNotificationPreferencesReply {
-            preferences: Some(preferences.into()),
+            preferences: Some(preferences),
         }))
     }
 
+    /// Get the user's notification preferences including every category
+    ///
+    /// # Errors
+    ///
+    /// - Miscellaneous internal errors
+    pub async fn get_full_notification_preferences(
+        &self,
+        user_id: i64,
+    ) -> Result<NotificationPreferences, Status> {
+        let preferences = self.get_raw_notification_preferences(user_id).await?;
+        let stored = self.get_raw_category_preferences(user_id).await?;
+
+        Ok(NotificationPreferences {
+            digest_frequency: ProtoDigestFrequency::from(preferences.digest_frequency()).into(),
+            quiet_hours: preferences.quiet_hours(),
+            timezone: preferences.timezone,
+            lang_id: preferences.lang_id,
+            categories: NotificationCategory::ALL
+                .into_iter()
+                .map(|category| CategoryPreferences::resolve(category, &stored).into())
+                .collect(),
+        })
+    }
+
     /// Get the user's notification preferences or the default ones
     ///
     /// # Errors
@@ -49,4 +79,51 @@ impl NotificationService {
 
         Ok(preferences)
     }
+
+    /// Get the category preferences the user has changed from the defaults
+    ///
+    /// # Errors
+    ///
+    /// - Miscellaneous internal errors
+    pub async fn get_raw_category_preferences(
+        &self,
+        user_id: i64,
+    ) -> Result<Vec<RawCategoryPreferences>, Status> {
+        sqlx::query_as!(
+            RawCategoryPreferences,
+            "select * from notification.category_preferences where user_id = $1",
+            user_id,
+        )
+        .fetch_all(&self.db)
+        .await
+        .map_err(Status::db)
+    }
+
+    /// Get the channels a category of notifications should be delivered on to the user
+    ///
+    /// # Errors
+    ///
+    /// - Miscellaneous internal errors
+    pub async fn get_category_preferences(
+        &self,
+        user_id: i64,
+        category: NotificationCategory,
+    ) -> Result<CategoryPreferences, Status> {
+        if category.is_mandatory() {
+            return Ok(CategoryPreferences::default_for(category));
+        }
+
+        let stored = sqlx::query_as!(
+            RawCategoryPreferences,
+            "select * from notification.category_preferences
+             where user_id = $1 and category = $2",
+            user_id,
+            category.as_str(),
+        )
+        .fetch_optional(&self.db)
+        .await
+        .map_err(Status::db)?;
+
+        Ok(CategoryPreferences::resolve(category, stored.as_slice()))
+    }
 }
@@ -0,0 +1,208 @@
+use crate::NotificationService;
+use crate::definition::{NotificationCategory, NotificationDefinition};
+use crate::models::notification::RawNotification;
+use bfx_core::status::{ErrorCode, StatusExt};
+use bfx_proto::notification::{
+    InAppNotification, ListNotificationsReply, ListNotificationsRequest,
+    NotificationCategory as ProtoNotificationCategory,
+};
+use bfx_proto::translation::RenderStringSetRequest;
+use bfx_proto::translation::translation_client::TranslationClient;
+use tonic::{Code, Request, Response, Status};
+
+const DEFAULT_PAGE_SIZE: u32 = 20;
+const MAX_PAGE_SIZE: u32 = 100;
+
+impl NotificationService {
+    /// List a user's in-app notifications, newest first
+    ///
+    /// Titles and bodies are rendered in the user's language when they're read.
+    ///
+    /// # Errors
+    ///
+    /// - If the category is unknown
+    /// - If rendering a notification fails
+    /// - Miscellaneous internal errors
+    pub async fn list_notifications(
+        &self,
+        request: Request<ListNotificationsRequest>,
+    ) -> Result<Response<ListNotificationsReply>, Status> {
+        let request = request.into_inner();
+
+        let limit = request
+            .limit
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE);
+        let category = request
+            .category
+            .map(|category| {
+                NotificationCategory::from_proto(category).ok_or_else(|| {
+                    Status::coded(Code::InvalidArgument, ErrorCode::InvalidParameter)
+                        .with_details("unknown notification category")
+                })
+            })
+            .transpose()?
+            .map(NotificationCategory::as_str);
+
+        // fetch one more to know if there's a next page
+        let mut notifications = sqlx::query_as!(
+            RawNotification,
+            "select *
+             from notification.notifications
+             where user_id = $1 and in_app
+                   and ($2::bigint is null or id < $2)
+                   and ($3::text is null or category = $3)
+                   and (not $4 or read_at is null)
+             order by id desc
+             limit $5",
+            request.user_id,
+            request.cursor,
+            category,
+            request.unread_only,
+            i64::from(limit) + 1,
+        )
+        .fetch_all(&self.db)
+        .await
+        .map_err(Status::db)?;
+
+        let next_cursor = if notifications.len() > limit as usize {
+            notifications.truncate(limit as usize);
+            notifications.last().map(|notification| notification.id)
+        } else {
+            None
+        };
+
+        let lang_id = self
+            .get_raw_notification_preferences(request.user_id)
+            .await?
+            .lang_id;
+
+        Ok(Response::new(ListNotificationsReply {
+            notifications: self.render_in_app(notifications, &lang_id).await?,
+            next_cursor,
+        }))
+    }
+
+    /// Get the current definition of a stored notification
+    ///
+    /// Notifications are rendered with the current definition, so changes apply to old
+    /// notifications too. The definition stored with the notification is only used if the
+    /// definition was removed since.
+    ///
+    /// # Errors
+    ///
+    /// - If the definition was removed and the stored one can't be decoded
+    pub fn current_definition(
+        &self,
+        notification: &RawNotification,
+    ) -> Result<NotificationDefinition, Status> {
+        match self.definitions.get(&notification.definition_id) {
+            Some(definition) => Ok(definition.as_ref().clone()),
+            None => Ok(notification.decode_data()?.definition),
+        }
+    }
+
+    /// Render the in-app content of stored notifications
+    ///
+    /// Everything is rendered in one request to bfx-translation, instead of one per notification.
+    ///
+    /// # Errors
+    ///
+    /// - If a stored notification has no in-app content or can't be decoded
+    /// - If rendering a template fails
+    pub async fn render_in_app(
+        &self,
+        notifications: Vec<RawNotification>,
+        lang_id: &str,
+    ) -> Result<Vec<InAppNotification>, Status> {
+        if notifications.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut categories = Vec::with_capacity(notifications.len());
+        // the title and body of every notification, in order
+        let mut string_sets = Vec::with_capacity(notifications.len() * 2);
+        for notification in &notifications {
+            let category = notification.category();
+            let in_app = self.current_definition(notification)?.in_app;
+            let params = notification.decode_params()?;
+
+            let (Some(category), Some(in_app)) = (category, in_app) else {
+                return Err(Status::coded(Code::Internal, ErrorCode::Internal)
+                    .with_details("stored notification has no in-app content"));
+            };
+
+            categories.push(category);
+            string_sets.push(RenderStringSetRequest {
+                lang_id: lang_id.to_string(),
+                conditionals: in_app.title.into(),
+                context: params.clone(),
+            });
+            string_sets.push(RenderStringSetRequest {
+                lang_id: lang_id.to_string(),
+                conditionals: in_app.body.into(),
+                context: params,
+            });
+        }
+
+        let mut translation = TranslationClient::new(self.router.clone());
+        let mut outputs = translation
+            .render_string_sets_ext(string_sets)
+            .await?
+            .into_iter();
+
+        notifications
+            .into_iter()
+            .zip(categories)
+            .map(|(notification, category)| {
+                let (Some(title), Some(body)) = (outputs.next(), outputs.next()) else {
+                    return Err(Status::coded(Code::Internal, ErrorCode::Internal)
+                        .with_details("missing rendered in-app content"));
+                };
+
+                Ok(InAppNotification {
+                    id: notification.id,
+                    definition_id: notification.definition_id,
+                    category: ProtoNotificationCategory::from(category).into(),
+                    title,
+                    body,
+                    read_at: notification.read_at.map(Into::into),
+                    created_at: Some(notification.created_at.into()),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bfx_core::service::database::Db;
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn unknown_category(db: Db) {
+        let service = NotificationService::for_tests(db);
+
+        let status = service
+            .list_notifications(Request::new(ListNotificationsRequest {
+                user_id: 1,
+                category: Some(42),
+                ..Default::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+
+        // an empty page doesn't need bfx-translation
+        let reply = service
+            .list_notifications(Request::new(ListNotificationsRequest {
+                user_id: 1,
+                category: Some(ProtoNotificationCategory::Auth.into()),
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(reply.notifications.is_empty());
+    }
+}
@@ -0,0 +1,38 @@
+use crate::NotificationService;
+use crate::definition::NotificationCategory;
+use bfx_core::status::StatusExt;
+use bfx_proto::notification::{MarkAllReadReply, MarkAllReadRequest};
+use tonic::{Request, Response, Status};
+
+impl NotificationService {
+    /// Mark all notifications of a user as read, optionally only in one category
+    ///
+    /// # Errors
+    ///
+    /// - Miscellaneous internal errors
+    pub async fn mark_all_read(
+        &self,
+        request: Request<MarkAllReadRequest>,
+    ) -> Result<Response<MarkAllReadReply>, Status> {
+        let request = request.into_inner();
+
+        let category = request
+            .category
+            .and_then(NotificationCategory::from_proto)
+            .map(NotificationCategory::as_str);
+
+        sqlx::query!(
+            "update notification.notifications
+             set read_at = now()
+             where user_id = $1 and read_at is null
+                   and ($2::text is null or category = $2)",
+            request.user_id,
+            category,
+        )
+        .execute(&self.db)
+        .await
+        .map_err(Status::db)?;
+
+        Ok(Response::new(MarkAllReadReply {}))
+    }
+}
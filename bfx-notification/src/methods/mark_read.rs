@@ -0,0 +1,33 @@
+use crate::NotificationService;
+use bfx_core::status::StatusExt;
+use bfx_proto::notification::{MarkReadReply, MarkReadRequest};
+use tonic::{Request, Response, Status};
+
+impl NotificationService {
+    /// Mark notifications of a user as read
+    ///
+    /// Notifications that are already read keep their original `read_at`.
+    ///
+    /// # Errors
+    ///
+    /// - Miscellaneous internal errors
+    pub async fn mark_read(
+        &self,
+        request: Request<MarkReadRequest>,
+    ) -> Result<Response<MarkReadReply>, Status> {
+        let request = request.into_inner();
+
+        sqlx::query!(
+            "update notification.notifications
+             set read_at = now()
+             where user_id = $1 and id = any($2) and read_at is null",
+            request.user_id,
+            &request.ids,
+        )
+        .execute(&self.db)
+        .await
+        .map_err(Status::db)?;
+
+        Ok(Response::new(MarkReadReply {}))
+    }
+}
@@ -1,3 +1,11 @@
 mod get_notification_preferences;
+mod list_notifications;
+mod mark_all_read;
+mod mark_read;
 mod send_notification;
+mod set_category_preferences;
+mod set_digest_frequency;
 mod set_notification_preferences;
+mod set_quiet_hours;
+pub mod subscribe_notifications;
+mod unread_count;
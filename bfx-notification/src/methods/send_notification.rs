@@ -1,6 +1,7 @@
 use crate::NotificationService;
-use crate::definition::{EmailDefinition, NotificationDefinition};
+use crate::definition::{EmailDefinition, NotificationCategory, NotificationDefinition};
 use crate::models::notification::NotificationData;
+use crate::models::preferences::{DigestFrequency, RawNotificationPreferences};
 use bfx_core::log_if_error::LogIfErrorExt;
 use bfx_core::status::{ErrorCode, StatusExt};
 use bfx_proto::ParamValue;
@@ -12,15 +13,21 @@ use bfx_proto::notification::{NotificationParams, SendNotificationReply, SendNot
 use bfx_proto::translation::ConditionalString;
 use bfx_proto::translation::translation_client::TranslationClient;
 use prost::Message;
+use sqlx::PgConnection;
+use sqlx::types::chrono::{DateTime, Utc};
 use std::collections::HashMap;
 use tonic::{Code, Request, Response, Status};
 
 impl NotificationService {
     /// Send a notification to a user
     ///
+    /// The notification is scheduled instead if `send_at` is in the future, or if the user has
+    /// quiet hours right now and the category isn't mandatory.
+    ///
     /// # Errors
     ///
-    /// - If the definition is invalid
+    /// - If the definition is not registered
+    /// - If the params don't match the ones declared by the definition
     /// - If rendering the template fails somewhere
     /// - If the user is not found and `user_override` is not provider and `email` is used
     /// - Miscellaneous internal errors
@@ -30,28 +37,161 @@ impl NotificationService {
     ) -> Result<Response<SendNotificationReply>, Status> {
         let request = request.into_inner();
 
-        let definition = request.definition;
-        let definition: NotificationDefinition =
-            serde_yml::from_str(&definition).map_err(|err| {
+        let definition = self
+            .definitions
+            .get(&request.definition_id)
+            .ok_or_else(|| {
                 Status::coded(
                     Code::InvalidArgument,
                     ErrorCode::InvalidNotificationDefinition,
                 )
-                .with_source(err)
+                .with_details(&format!("unknown definition `{}`", request.definition_id))
             })?;
 
+        definition.check_params(&request.params).map_err(|err| {
+            Status::coded(Code::InvalidArgument, ErrorCode::InvalidNotificationParams)
+                .with_details(&err)
+        })?;
+
+        let send_at = request.send_at.map(TryInto::try_into).transpose()?;
+
         let preferences = self
             .get_raw_notification_preferences(request.user_id)
             .await?;
 
-        if let Some(ref email_definition) = definition.email {
+        if let Some(deliver_at) = self
+            .delivery_delay(definition.category, &preferences, send_at)
+            .await?
+        {
+            let params = NotificationParams {
+                params: request.params,
+            }
+            .encode_to_vec();
+
+            let scheduled = sqlx::query!(
+                "insert into notification.scheduled_notifications
+                 (user_id, definition_id, params, user_override, deliver_at)
+                 values ($1, $2, $3, $4, $5)
+                 returning id",
+                request.user_id,
+                request.definition_id,
+                params,
+                request.user_override.map(|user| user.encode_to_vec()),
+                deliver_at,
+            )
+            .fetch_one(&self.db)
+            .await
+            .map_err(Status::db)?;
+
+            return Ok(Response::new(SendNotificationReply {
+                id: None,
+                scheduled_id: Some(scheduled.id),
+            }));
+        }
+
+        let mut conn = self.db.acquire().await.map_err(Status::db)?;
+
+        let id = self
+            .deliver_notification(
+                &mut conn,
+                &definition,
+                request.user_id,
+                request.user_override,
+                request.params,
+                &preferences,
+            )
+            .await?;
+
+        Ok(Response::new(SendNotificationReply {
+            id: Some(id),
+            scheduled_id: None,
+        }))
+    }
+
+    /// Get the time a notification has to wait for, if it can't be delivered right now
+    ///
+    /// Mandatory categories ignore quiet hours, but not `send_at`.
+    ///
+    /// # Errors
+    ///
+    /// - Miscellaneous internal errors
+    pub async fn delivery_delay(
+        &self,
+        category: NotificationCategory,
+        preferences: &RawNotificationPreferences,
+        send_at: Option<DateTime<Utc>>,
+    ) -> Result<Option<DateTime<Utc>>, Status> {
+        let now = Utc::now();
+        let at = send_at.filter(|send_at| *send_at > now);
+
+        let (Some(start), Some(end)) = (preferences.quiet_hours_start, preferences.quiet_hours_end)
+        else {
+            return Ok(at);
+        };
+
+        if category.is_mandatory() {
+            return Ok(at);
+        }
+
+        let quiet_hours_end = sqlx::query_scalar!(
+            "select notification.quiet_hours_end($1, $2, $3, $4)",
+            at.unwrap_or(now),
+            preferences.timezone,
+            start,
+            end,
+        )
+        .fetch_one(&self.db)
+        .await
+        .map_err(Status::db)?;
+
+        Ok(quiet_hours_end.or(at))
+    }
+
+    /// Deliver a notification right away, on the channels the user wants
+    ///
+    /// Channels the user opted out of for the category are skipped. The notification is still
+    /// stored, but not shown in-app if the user opted out of in-app notifications.
+    ///
+    /// Emails are left for the next digest if the user chose a digest frequency,
+    /// except for mandatory categories.
+    ///
+    /// # Errors
+    ///
+    /// - If rendering the template fails somewhere
+    /// - If the user is not found and `user_override` is not provider and `email` is used
+    /// - Miscellaneous internal errors
+    pub async fn deliver_notification(
+        &self,
+        conn: &mut PgConnection,
+        definition: &NotificationDefinition,
+        user_id: i64,
+        user_override: Option<User>,
+        params: HashMap<String, ParamValue>,
+        preferences: &RawNotificationPreferences,
+    ) -> Result<i64, Status> {
+        // mandatory categories ignore the preferences
+        let channels = self
+            .get_category_preferences(user_id, definition.category)
+            .await?;
+
+        let send_email = definition.email.is_some() && channels.email;
+        // mandatory categories are always sent immediately
+        let email_digest_pending = send_email
+            && !definition.category.is_mandatory()
+            && preferences.digest_frequency() != DigestFrequency::Immediate;
+
+        if let Some(email_definition) = definition
+            .email
+            .as_ref()
+            .filter(|_| send_email && !email_digest_pending)
+        {
             let mut auth_core = AuthCoreClient::new(self.router.clone());
 
-            let user = if let Some(user_override) = request.user_override {
+            let user = if let Some(user_override) = user_override {
                 user_override
             } else {
                 auth_core
-                    .get_user_by_id(request.user_id)
+                    .get_user_by_id(user_id)
                     .await?
                     .ok_or_else(|| Status::coded(Code::NotFound, ErrorCode::UserNotFound))?
             };
@@ -59,37 +199,38 @@ impl NotificationService {
             self.send_email_notification(
                 user,
                 preferences.lang_id.clone(),
-                request.params.clone(),
+                params.clone(),
                 email_definition.clone(),
             )
             .await?;
         }
 
-        let data = NotificationData { definition };
-        let params = NotificationParams {
-            params: request.params,
-        }
-        .encode_to_vec();
+        // a snapshot of the definition, in case it's removed later
+        let data = NotificationData {
+            definition: definition.clone(),
+        };
+        let params = NotificationParams { params }.encode_to_vec();
 
         let notification = sqlx::query!(
             "insert into notification.notifications
-             (user_id, definition_id, data, params)
-             values ($1, $2, $3, $4)
+             (user_id, definition_id, category, in_app, data, params, email_digest_pending)
+             values ($1, $2, $3, $4, $5, $6, $7)
              returning id",
-            request.user_id,
+            user_id,
             data.definition.id.clone(),
+            data.definition.category.as_str(),
+            data.definition.in_app.is_some() && channels.in_app,
             serde_json::to_value(data)
                 .map_err(From::from)
                 .map_err(Status::anyhow)?,
             params,
+            email_digest_pending,
         )
-        .fetch_one(&self.db)
+        .fetch_one(&mut *conn)
         .await
         .map_err(Status::db)?;
 
-        Ok(Response::new(SendNotificationReply {
-            id: notification.id,
-        }))
+        Ok(notification.id)
     }
 
     async fn send_email_notification(
@@ -134,7 +275,9 @@ impl NotificationService {
         Ok(())
     }
 
-    fn wrap_string_set_in_template(body: Vec<ConditionalString>) -> Vec<ConditionalString> {
+    /// Wrap email bodies in the greeting and footer of all emails
+    #[must_use]
+    pub fn wrap_string_set_in_template(body: Vec<ConditionalString>) -> Vec<ConditionalString> {
         body.into_iter()
             .map(|mut c| {
                 c.value = format!(
@@ -148,3 +291,175 @@ impl NotificationService {
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::preferences::CategoryPreferences;
+    use crate::test_utils::{email_definition, in_app_definition};
+    use bfx_core::service::database::Db;
+
+    async fn stored_notification(db: &Db, id: i64) -> (bool, bool) {
+        sqlx::query_as(
+            "select in_app, email_digest_pending from notification.notifications where id = $1",
+        )
+        .bind(id)
+        .fetch_one(db)
+        .await
+        .unwrap()
+    }
+
+    async fn quiet_hours_end(
+        db: &Db,
+        at: &str,
+        timezone: &str,
+        start: (i32, i32),
+        end: (i32, i32),
+    ) -> Option<DateTime<Utc>> {
+        sqlx::query_scalar("select notification.quiet_hours_end($1, $2, $3, $4)")
+            .bind(at.parse::<DateTime<Utc>>().unwrap())
+            .bind(timezone)
+            .bind(start.0 * 60 + start.1)
+            .bind(end.0 * 60 + end.1)
+            .fetch_one(db)
+            .await
+            .unwrap()
+    }
+
+    fn time(value: &str) -> DateTime<Utc> {
+        value.parse().unwrap()
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn quiet_hours_within_a_day(db: Db) {
+        let check = async |at| quiet_hours_end(&db, at, "UTC", (13, 0), (14, 30)).await;
+
+        assert_eq!(check("2025-01-15T12:59:00Z").await, None);
+        assert_eq!(
+            check("2025-01-15T13:00:00Z").await,
+            Some(time("2025-01-15T14:30:00Z"))
+        );
+        assert_eq!(
+            check("2025-01-15T14:29:00Z").await,
+            Some(time("2025-01-15T14:30:00Z"))
+        );
+        assert_eq!(check("2025-01-15T14:30:00Z").await, None);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn quiet_hours_wrapping_around_midnight(db: Db) {
+        let check = async |at| quiet_hours_end(&db, at, "UTC", (22, 0), (7, 0)).await;
+
+        assert_eq!(check("2025-01-15T21:59:00Z").await, None);
+        // before midnight, they end the next day
+        assert_eq!(
+            check("2025-01-15T22:00:00Z").await,
+            Some(time("2025-01-16T07:00:00Z"))
+        );
+        assert_eq!(
+            check("2025-01-15T23:59:00Z").await,
+            Some(time("2025-01-16T07:00:00Z"))
+        );
+        // after midnight, the same day
+        assert_eq!(
+            check("2025-01-16T00:00:00Z").await,
+            Some(time("2025-01-16T07:00:00Z"))
+        );
+        assert_eq!(
+            check("2025-01-16T06:59:00Z").await,
+            Some(time("2025-01-16T07:00:00Z"))
+        );
+        assert_eq!(check("2025-01-16T07:00:00Z").await, None);
+        assert_eq!(check("2025-01-16T12:00:00Z").await, None);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn quiet_hours_in_local_time(db: Db) {
+        let check = async |at| quiet_hours_end(&db, at, "Europe/Berlin", (22, 0), (7, 0)).await;
+
+        // 23:30 and 20:30 local time (CET, UTC+1)
+        assert_eq!(
+            check("2025-01-15T22:30:00Z").await,
+            Some(time("2025-01-16T06:00:00Z"))
+        );
+        assert_eq!(check("2025-01-15T19:30:00Z").await, None);
+        // the clocks go forward during the quiet hours, so they end at 07:00 CEST (UTC+2)
+        assert_eq!(
+            check("2025-03-29T23:00:00Z").await,
+            Some(time("2025-03-30T05:00:00Z"))
+        );
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn empty_quiet_hours(db: Db) {
+        let end = quiet_hours_end(&db, "2025-01-15T12:00:00Z", "UTC", (12, 0), (12, 0)).await;
+
+        assert_eq!(end, None);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn skip_opted_out_channels(db: Db) {
+        let service = NotificationService::for_tests(db);
+        let category = NotificationCategory::Announcements;
+
+        let mut tx = service.db.begin().await.unwrap();
+        NotificationService::store_category_preferences(
+            &mut tx,
+            1,
+            &[CategoryPreferences {
+                category,
+                in_app: false,
+                email: false,
+            }],
+        )
+        .await
+        .unwrap();
+        tx.commit().await.unwrap();
+
+        // sending the email would fail, there's no router
+        let mut conn = service.db.acquire().await.unwrap();
+        let id = service
+            .deliver_notification(
+                &mut conn,
+                &email_definition(category),
+                1,
+                None,
+                HashMap::new(),
+                &RawNotificationPreferences::default(),
+            )
+            .await
+            .unwrap();
+
+        // stored anyway, but not shown
+        assert_eq!(stored_notification(&service.db, id).await, (false, false));
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn mandatory_categories_ignore_preferences(db: Db) {
+        let service = NotificationService::for_tests(db);
+
+        // can't be set through `set_category_preferences`
+        sqlx::query(
+            "insert into notification.category_preferences (user_id, category, in_app, email)
+             values (1, 'auth', false, false)",
+        )
+        .execute(&service.db)
+        .await
+        .unwrap();
+
+        let mut conn = service.db.acquire().await.unwrap();
+        let id = service
+            .deliver_notification(
+                &mut conn,
+                &in_app_definition(NotificationCategory::Auth),
+                1,
+                None,
+                HashMap::new(),
+                &RawNotificationPreferences::default(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(stored_notification(&service.db, id).await, (true, false));
+    }
+}
@@ -0,0 +1,142 @@
+use crate::NotificationService;
+use crate::definition::NotificationCategory;
+use crate::models::preferences::CategoryPreferences;
+use bfx_core::status::{ErrorCode, StatusExt};
+use bfx_proto::notification::CategoryPreferences as ProtoCategoryPreferences;
+use bfx_proto::notification::{SetCategoryPreferencesReply, SetCategoryPreferencesRequest};
+use tonic::{Code, Request, Response, Status};
+
+impl NotificationService {
+    /// Set the channels the user wants to receive some categories of notifications on
+    ///
+    /// Categories that aren't given are left unchanged.
+    ///
+    /// # Errors
+    ///
+    /// - If a category is unknown
+    /// - If a mandatory category would be disabled on any channel
+    /// - Miscellaneous internal errors
+    pub async fn set_category_preferences(
+        &self,
+        request: Request<SetCategoryPreferencesRequest>,
+    ) -> Result<Response<SetCategoryPreferencesReply>, Status> {
+        let request = request.into_inner();
+
+        let categories = Self::parse_category_preferences(request.categories)?;
+
+        let mut tx = self.db.begin().await.map_err(Status::db)?;
+        Self::store_category_preferences(&mut tx, request.user_id, &categories).await?;
+        tx.commit().await.map_err(Status::db)?;
+
+        let preferences = self
+            .get_full_notification_preferences(request.user_id)
+            .await?;
+
+        Ok(Response::new(SetCategoryPreferencesReply {
+            preferences: Some(preferences),
+        }))
+    }
+
+    /// Validate category preferences sent over gRPC
+    ///
+    /// # Errors
+    ///
+    /// - If a category is unknown
+    /// - If a mandatory category would be disabled on any channel
+    pub fn parse_category_preferences(
+        categories: Vec<ProtoCategoryPreferences>,
+    ) -> Result<Vec<CategoryPreferences>, Status> {
+        categories
+            .into_iter()
+            .map(|preferences| {
+                let category =
+                    NotificationCategory::from_proto(preferences.category).ok_or_else(|| {
+                        Status::coded(Code::InvalidArgument, ErrorCode::InvalidParameter)
+                            .with_details("unknown notification category")
+                    })?;
+
+                if category.is_mandatory() && !(preferences.in_app && preferences.email) {
+                    return Err(Status::coded(
+                        Code::InvalidArgument,
+                        ErrorCode::MandatoryNotificationCategory,
+                    )
+                    .with_details(category.as_str()));
+                }
+
+                Ok(CategoryPreferences {
+                    category,
+                    in_app: preferences.in_app,
+                    email: preferences.email,
+                })
+            })
+            .collect()
+    }
+
+    /// Store category preferences, overwriting previous ones of the same categories
+    ///
+    /// # Errors
+    ///
+    /// - Miscellaneous internal errors
+    pub async fn store_category_preferences(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: i64,
+        categories: &[CategoryPreferences],
+    ) -> Result<(), Status> {
+        for preferences in categories {
+            sqlx::query!(
+                "insert into notification.category_preferences
+                 (user_id, category, in_app, email)
+                 values ($1, $2, $3, $4)
+                 on conflict (user_id, category) do update
+                 set in_app = $3, email = $4, updated_at = now()",
+                user_id,
+                preferences.category.as_str(),
+                preferences.in_app,
+                preferences.email,
+            )
+            .execute(&mut **tx)
+            .await
+            .map_err(Status::db)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bfx_proto::notification::NotificationCategory as ProtoNotificationCategory;
+
+    fn preferences(category: i32, in_app: bool, email: bool) -> ProtoCategoryPreferences {
+        ProtoCategoryPreferences {
+            category,
+            in_app,
+            email,
+            mandatory: false,
+        }
+    }
+
+    #[test]
+    fn parse() {
+        let announcements = ProtoNotificationCategory::Announcements.into();
+        let auth = ProtoNotificationCategory::Auth.into();
+
+        let parsed = NotificationService::parse_category_preferences(vec![
+            preferences(announcements, false, true),
+            preferences(auth, true, true),
+        ])
+        .unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert!(!parsed[0].in_app && parsed[0].email);
+
+        for invalid in [
+            preferences(auth, false, true),
+            preferences(auth, true, false),
+            preferences(42, true, true),
+        ] {
+            let result = NotificationService::parse_category_preferences(vec![invalid]);
+            assert!(result.is_err(), "{invalid:?}");
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use crate::NotificationService;
+use crate::models::preferences::{DigestFrequency, RawNotificationPreferences};
+use bfx_core::status::{ErrorCode, StatusExt};
+use bfx_proto::notification::{SetDigestFrequencyReply, SetDigestFrequencyRequest};
+use tonic::{Code, Request, Response, Status};
+
+impl NotificationService {
+    /// Set how often the user receives emails of non-mandatory categories
+    ///
+    /// Changing the frequency starts a new digest period, so the first digest is sent one period
+    /// after the change. Emails that are still pending when switching to immediate delivery are
+    /// sent in one last digest.
+    ///
+    /// # Errors
+    ///
+    /// - If the frequency is unknown
+    /// - Miscellaneous internal errors
+    pub async fn set_digest_frequency(
+        &self,
+        request: Request<SetDigestFrequencyRequest>,
+    ) -> Result<Response<SetDigestFrequencyReply>, Status> {
+        let request = request.into_inner();
+
+        let digest_frequency =
+            DigestFrequency::from_proto(request.digest_frequency).ok_or_else(|| {
+                Status::coded(Code::InvalidArgument, ErrorCode::InvalidParameter)
+                    .with_details("unknown digest frequency")
+            })?;
+
+        sqlx::query!(
+            "insert into notification.preferences
+             (user_id, lang_id, digest_frequency, last_digest_at)
+             values ($1, $2, $3, now())
+             on conflict (user_id) do update
+             set digest_frequency = $3,
+                 last_digest_at = case
+                     when preferences.digest_frequency = $3 then preferences.last_digest_at
+                     else now()
+                 end",
+            request.user_id,
+            RawNotificationPreferences::default().lang_id,
+            digest_frequency.as_str(),
+        )
+        .execute(&self.db)
+        .await
+        .map_err(Status::db)?;
+
+        let preferences = self
+            .get_full_notification_preferences(request.user_id)
+            .await?;
+
+        Ok(Response::new(SetDigestFrequencyReply {
+            preferences: Some(preferences),
+        }))
+    }
+}
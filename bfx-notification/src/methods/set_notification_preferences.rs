@@ -1,5 +1,4 @@
 use crate::NotificationService;
-use crate::models::preferences::RawNotificationPreferences;
 use bfx_core::status::{ErrorCode, StatusExt};
 use bfx_proto::notification::{SetNotificationPreferencesReply, SetNotificationPreferencesRequest};
 use tonic::{Code, Request, Response, Status};
@@ -7,8 +6,12 @@ use tonic::{Code, Request, Response, Status};
 impl NotificationService {
     /// Set the user's notification preferences
     ///
+    /// Categories that aren't given are left unchanged.
+    ///
     /// # Errors
     ///
+    /// - If a category is unknown
+    /// - If a mandatory category would be disabled on any channel
     /// - Miscellaneous internal errors
     pub async fn set_notification_preferences(
         &self,
@@ -20,23 +23,33 @@ impl NotificationService {
             .preferences
             .ok_or_else(|| Status::coded(Code::InvalidArgument, ErrorCode::Internal))?;
 
-        let preferences = sqlx::query_as!(
-            RawNotificationPreferences,
+        let categories = Self::parse_category_preferences(preferences.categories)?;
+
+        let mut tx = self.db.begin().await.map_err(Status::db)?;
+
+        sqlx::query!(
             "insert into notification.preferences
              (user_id, lang_id)
              values ($1, $2)
              on conflict (user_id) do update
-             set lang_id = $2
-             returning *",
+             set lang_id = $2",
             request.user_id,
             preferences.lang_id,
         )
-        .fetch_one(&self.db)
+        .execute(&mut *tx)
         .await
         .map_err(Status::db)?;
 
+        Self::store_category_preferences(&mut tx, request.user_id, &categories).await?;
+
+        tx.commit().await.map_err(Status::db)?;
+
+        let preferences = self
+            .get_full_notification_preferences(request.user_id)
+            .await?;
+
         Ok(Response::new(SetNotificationPreferencesReply {
-            preferences: Some(preferences.into()),
+            preferences: Some(preferences),
         }))
     }
 }
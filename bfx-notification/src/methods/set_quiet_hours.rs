@@ -0,0 +1,81 @@
+use crate::NotificationService;
+use crate::models::preferences::RawNotificationPreferences;
+use bfx_core::status::{ErrorCode, StatusExt};
+use bfx_proto::notification::{SetQuietHoursReply, SetQuietHoursRequest};
+use tonic::{Code, Request, Response, Status};
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+impl NotificationService {
+    /// Set the user's time zone and quiet hours
+    ///
+    /// Notifications of non-mandatory categories are held back until the quiet hours end.
+    ///
+    /// # Errors
+    ///
+    /// - If the time zone is unknown
+    /// - If the quiet hours aren't within a day
+    /// - Miscellaneous internal errors
+    pub async fn set_quiet_hours(
+        &self,
+        request: Request<SetQuietHoursRequest>,
+    ) -> Result<Response<SetQuietHoursReply>, Status> {
+        let request = request.into_inner();
+
+        let timezone_exists = sqlx::query_scalar!(
+            r#"select exists(select 1 from pg_timezone_names where name = $1) as "exists!""#,
+            request.timezone,
+        )
+        .fetch_one(&self.db)
+        .await
+        .map_err(Status::db)?;
+
+        if !timezone_exists {
+            return Err(Status::coded(
+                Code::InvalidArgument,
+                ErrorCode::InvalidTimezone,
+            ));
+        }
+
+        let quiet_hours = request
+            .quiet_hours
+            .map(|quiet_hours| {
+                let minutes = [quiet_hours.start_minute, quiet_hours.end_minute];
+                if minutes.iter().any(|minute| *minute >= MINUTES_PER_DAY) {
+                    return Err(
+                        Status::coded(Code::InvalidArgument, ErrorCode::InvalidParameter)
+                            .with_details("quiet hours must be minutes after midnight"),
+                    );
+                }
+
+                // both are less than MINUTES_PER_DAY
+                #[allow(clippy::cast_possible_wrap)]
+                Ok(minutes.map(|minute| minute as i32))
+            })
+            .transpose()?;
+
+        sqlx::query!(
+            "insert into notification.preferences
+             (user_id, lang_id, timezone, quiet_hours_start, quiet_hours_end)
+             values ($1, $2, $3, $4, $5)
+             on conflict (user_id) do update
+             set timezone = $3, quiet_hours_start = $4, quiet_hours_end = $5",
+            request.user_id,
+            RawNotificationPreferences::default().lang_id,
+            request.timezone,
+            quiet_hours.map(|[start, _]| start),
+            quiet_hours.map(|[_, end]| end),
+        )
+        .execute(&self.db)
+        .await
+        .map_err(Status::db)?;
+
+        let preferences = self
+            .get_full_notification_preferences(request.user_id)
+            .await?;
+
+        Ok(Response::new(SetQuietHoursReply {
+            preferences: Some(preferences),
+        }))
+    }
+}
@@ -0,0 +1,89 @@
+use crate::NotificationService;
+use crate::models::notification::RawNotification;
+use bfx_core::status::{ErrorCode, StatusExt};
+use bfx_proto::notification::{InAppNotification, SubscribeNotificationsRequest};
+use futures_util::Stream;
+use futures_util::stream::unfold;
+use std::pin::Pin;
+use tokio::sync::broadcast::error::RecvError;
+use tonic::{Code, Request, Response, Status};
+use tracing::warn;
+
+pub type InAppNotificationStream =
+    Pin<Box<dyn Stream<Item = Result<InAppNotification, Status>> + Send>>;
+
+impl NotificationService {
+    /// Stream new in-app notifications of a user
+    ///
+    /// Notifications that fail to render are logged and skipped,
+    /// so they don't end the subscription.
+    ///
+    /// # Errors
+    ///
+    /// - Never, the stream ends when the client disconnects
+    #[allow(clippy::unused_async)]
+    pub async fn subscribe_notifications(
+        &self,
+        request: Request<SubscribeNotificationsRequest>,
+    ) -> Result<Response<InAppNotificationStream>, Status> {
+        let user_id = request.into_inner().user_id;
+
+        let subscription = self.received.subscribe();
+
+        let stream = unfold(
+            (self.clone(), subscription),
+            move |(service, mut subscription)| async move {
+                loop {
+                    match subscription.recv().await {
+                        Ok(event) if event.user_id == user_id => {
+                            match service.get_in_app_notification(event.id).await {
+                                Ok(notification) => {
+                                    return Some((Ok(notification), (service, subscription)));
+                                }
+                                Err(err) => {
+                                    warn!(user_id, err = %err, "failed to render new notification");
+                                }
+                            }
+                        }
+                        Ok(_) => {}
+                        Err(RecvError::Lagged(skipped)) => {
+                            warn!(user_id, skipped, "notification subscriber lagged behind");
+                        }
+                        Err(RecvError::Closed) => return None,
+                    }
+                }
+            },
+        );
+
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    /// Get and render a single in-app notification
+    ///
+    /// # Errors
+    ///
+    /// - If the notification is not found
+    /// - If rendering the notification fails
+    /// - Miscellaneous internal errors
+    pub async fn get_in_app_notification(&self, id: i64) -> Result<InAppNotification, Status> {
+        let notification = sqlx::query_as!(
+            RawNotification,
+            "select * from notification.notifications where id = $1 and in_app",
+            id,
+        )
+        .fetch_optional(&self.db)
+        .await
+        .map_err(Status::db)?
+        .ok_or_else(|| Status::coded(Code::NotFound, ErrorCode::NotificationNotFound))?;
+
+        let lang_id = self
+            .get_raw_notification_preferences(notification.user_id)
+            .await?
+            .lang_id;
+
+        self.render_in_app(vec![notification], &lang_id)
+            .await?
+            .pop()
+            .ok_or_else(|| Status::coded(Code::Internal, ErrorCode::Internal))
+    }
+}
@@ -0,0 +1,38 @@
+use crate::NotificationService;
+use crate::definition::NotificationCategory;
+use bfx_core::status::StatusExt;
+use bfx_proto::notification::{UnreadCountReply, UnreadCountRequest};
+use tonic::{Request, Response, Status};
+
+impl NotificationService {
+    /// Count the unread in-app notifications of a user, optionally only in one category
+    ///
+    /// # Errors
+    ///
+    /// - Miscellaneous internal errors
+    pub async fn unread_count(
+        &self,
+        request: Request<UnreadCountRequest>,
+    ) -> Result<Response<UnreadCountReply>, Status> {
+        let request = request.into_inner();
+
+        let category = request
+            .category
+            .and_then(NotificationCategory::from_proto)
+            .map(NotificationCategory::as_str);
+
+        let count = sqlx::query_scalar!(
+            "select count(*) as \"count!\"
+             from notification.notifications
+             where user_id = $1 and in_app and read_at is null
+                   and ($2::text is null or category = $2)",
+            request.user_id,
+            category,
+        )
+        .fetch_one(&self.db)
+        .await
+        .map_err(Status::db)?;
+
+        Ok(Response::new(UnreadCountReply { count }))
+    }
+}
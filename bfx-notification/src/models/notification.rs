@@ -1,7 +1,68 @@
-use crate::definition::NotificationDefinition;
+use crate::definition::{NotificationCategory, NotificationDefinition};
+use bfx_core::status::{ErrorCode, StatusExt};
+use bfx_proto::ParamValue;
+use bfx_proto::notification::NotificationParams;
+use prost::Message;
 use serde::{Deserialize, Serialize};
+use sqlx::types::chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use tonic::{Code, Status};
 
 #[derive(Serialize, Deserialize)]
 pub struct NotificationData {
     pub definition: NotificationDefinition,
 }
+
+#[derive(Debug, Clone)]
+pub struct RawNotification {
+    pub id: i64,
+    pub user_id: i64,
+    pub definition_id: String,
+    pub category: String,
+    pub in_app: bool,
+    pub data: serde_json::Value,
+    pub params: Vec<u8>,
+    pub read_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub email_digest_pending: bool,
+}
+
+impl RawNotification {
+    #[must_use]
+    pub fn category(&self) -> Option<NotificationCategory> {
+        NotificationCategory::from_db(&self.category)
+    }
+
+    /// Decode the definition stored with the notification
+    ///
+    /// # Errors
+    ///
+    /// - If the stored data is invalid
+    pub fn decode_data(&self) -> Result<NotificationData, Status> {
+        serde_json::from_value(self.data.clone())
+            .map_err(|err| Status::coded(Code::Internal, ErrorCode::Internal).with_source(err))
+    }
+
+    /// Decode the params the notification was sent with
+    ///
+    /// # Errors
+    ///
+    /// - If the stored params are invalid
+    pub fn decode_params(&self) -> Result<HashMap<String, ParamValue>, Status> {
+        NotificationParams::decode(self.params.as_slice())
+            .map(|params| params.params)
+            .map_err(|err| Status::coded(Code::Internal, ErrorCode::Internal).with_source(err))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RawScheduledNotification {
+    pub id: i64,
+    pub user_id: i64,
+    pub definition_id: String,
+    pub params: Vec<u8>,
+    pub user_override: Option<Vec<u8>>,
+    pub deliver_at: DateTime<Utc>,
+    pub attempts: i32,
+    pub created_at: DateTime<Utc>,
+}
@@ -1,4 +1,8 @@
-use bfx_proto::notification::NotificationPreferences;
+use crate::definition::NotificationCategory;
+use bfx_proto::notification::CategoryPreferences as ProtoCategoryPreferences;
+use bfx_proto::notification::DigestFrequency as ProtoDigestFrequency;
+use bfx_proto::notification::NotificationCategory as ProtoNotificationCategory;
+use bfx_proto::notification::QuietHours;
 use sqlx::types::chrono::{DateTime, Utc};
 
 #[derive(Debug, Clone)]
@@ -6,6 +10,29 @@ pub struct RawNotificationPreferences {
     pub user_id: i64,
     pub lang_id: String,
     pub created_at: DateTime<Utc>,
+    pub digest_frequency: String,
+    pub last_digest_at: Option<DateTime<Utc>>,
+    pub timezone: String,
+    pub quiet_hours_start: Option<i32>,
+    pub quiet_hours_end: Option<i32>,
+}
+
+impl RawNotificationPreferences {
+    #[must_use]
+    pub fn quiet_hours(&self) -> Option<QuietHours> {
+        let (start, end) = (self.quiet_hours_start?, self.quiet_hours_end?);
+
+        Some(QuietHours {
+            start_minute: u32::try_from(start).ok()?,
+            end_minute: u32::try_from(end).ok()?,
+        })
+    }
+
+    /// Invalid values are treated as [`DigestFrequency::Immediate`]
+    #[must_use]
+    pub fn digest_frequency(&self) -> DigestFrequency {
+        DigestFrequency::from_db(&self.digest_frequency).unwrap_or(DigestFrequency::Immediate)
+    }
 }
 
 impl Default for RawNotificationPreferences {
@@ -14,14 +41,164 @@ impl Default for RawNotificationPreferences {
             user_id: 0,
             lang_id: "en".to_string(),
             created_at: Utc::now(),
+            digest_frequency: DigestFrequency::Immediate.as_str().to_string(),
+            last_digest_at: None,
+            timezone: "UTC".to_string(),
+            quiet_hours_start: None,
+            quiet_hours_end: None,
         }
     }
 }
 
-impl From<RawNotificationPreferences> for NotificationPreferences {
-    fn from(value: RawNotificationPreferences) -> Self {
+#[derive(Debug, Clone)]
+pub struct RawCategoryPreferences {
+    pub user_id: i64,
+    pub category: String,
+    pub in_app: bool,
+    pub email: bool,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Channels a category of notifications is delivered on
+#[derive(Debug, Clone, Copy)]
+pub struct CategoryPreferences {
+    pub category: NotificationCategory,
+    pub in_app: bool,
+    pub email: bool,
+}
+
+impl CategoryPreferences {
+    /// Every channel is enabled until the user opts out
+    #[must_use]
+    pub const fn default_for(category: NotificationCategory) -> Self {
         Self {
-            lang_id: value.lang_id,
+            category,
+            in_app: true,
+            email: true,
+        }
+    }
+
+    /// Get the effective preferences of a category from the stored ones
+    ///
+    /// Stored preferences of mandatory categories are ignored.
+    #[must_use]
+    pub fn resolve(category: NotificationCategory, stored: &[RawCategoryPreferences]) -> Self {
+        if category.is_mandatory() {
+            return Self::default_for(category);
+        }
+
+        stored
+            .iter()
+            .find(|stored| stored.category == category.as_str())
+            .map_or_else(
+                || Self::default_for(category),
+                |stored| Self {
+                    category,
+                    in_app: stored.in_app,
+                    email: stored.email,
+                },
+            )
+    }
+}
+
+impl From<CategoryPreferences> for ProtoCategoryPreferences {
+    fn from(value: CategoryPreferences) -> Self {
+        Self {
+            category: ProtoNotificationCategory::from(value.category).into(),
+            in_app: value.in_app,
+            email: value.email,
+            mandatory: value.category.is_mandatory(),
+        }
+    }
+}
+
+/// How often emails of non-mandatory categories are sent
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum DigestFrequency {
+    Immediate,
+    Daily,
+    Weekly,
+}
+
+impl DigestFrequency {
+    /// Name of the frequency, as stored in the database
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Immediate => "immediate",
+            Self::Daily => "daily",
+            Self::Weekly => "weekly",
         }
     }
+
+    /// Parse a frequency stored in the database
+    #[must_use]
+    pub fn from_db(value: &str) -> Option<Self> {
+        match value {
+            "immediate" => Some(Self::Immediate),
+            "daily" => Some(Self::Daily),
+            "weekly" => Some(Self::Weekly),
+            _ => None,
+        }
+    }
+
+    /// Convert a frequency sent over gRPC
+    #[must_use]
+    pub fn from_proto(value: i32) -> Option<Self> {
+        ProtoDigestFrequency::try_from(value).ok().map(Into::into)
+    }
+}
+
+impl From<ProtoDigestFrequency> for DigestFrequency {
+    fn from(value: ProtoDigestFrequency) -> Self {
+        match value {
+            ProtoDigestFrequency::Immediate => Self::Immediate,
+            ProtoDigestFrequency::Daily => Self::Daily,
+            ProtoDigestFrequency::Weekly => Self::Weekly,
+        }
+    }
+}
+
+impl From<DigestFrequency> for ProtoDigestFrequency {
+    fn from(value: DigestFrequency) -> Self {
+        match value {
+            DigestFrequency::Immediate => Self::Immediate,
+            DigestFrequency::Daily => Self::Daily,
+            DigestFrequency::Weekly => Self::Weekly,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stored(category: NotificationCategory, in_app: bool, email: bool) -> RawCategoryPreferences {
+        RawCategoryPreferences {
+            user_id: 1,
+            category: category.as_str().to_string(),
+            in_app,
+            email,
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn resolve_category_preferences() {
+        let announcements = NotificationCategory::Announcements;
+
+        let preferences = CategoryPreferences::resolve(announcements, &[]);
+        assert!(preferences.in_app && preferences.email);
+
+        let preferences =
+            CategoryPreferences::resolve(announcements, &[stored(announcements, true, false)]);
+        assert!(preferences.in_app && !preferences.email);
+
+        // stored preferences of mandatory categories shouldn't exist, but are ignored anyway
+        let preferences = CategoryPreferences::resolve(
+            NotificationCategory::Auth,
+            &[stored(NotificationCategory::Auth, false, false)],
+        );
+        assert!(preferences.in_app && preferences.email);
+    }
 }
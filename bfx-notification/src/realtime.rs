@@ -0,0 +1,91 @@
+use crate::NotificationService;
+use serde::Deserialize;
+use sqlx::postgres::PgListener;
+use std::time::Duration;
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+/// Postgres channel that new in-app notifications are announced on (by a trigger)
+const NOTIFICATION_RECEIVED_CHANNEL: &str = "notification_received";
+
+/// Payload of [`NOTIFICATION_RECEIVED_CHANNEL`]
+#[derive(Copy, Clone, Debug, Deserialize)]
+pub struct NotificationReceived {
+    pub id: i64,
+    pub user_id: i64,
+}
+
+impl NotificationService {
+    /// Forward new in-app notifications from Postgres to the subscribers of this replica
+    ///
+    /// Every replica listens, so it doesn't matter which one inserted the notification.
+    pub fn start_notification_listener(self) {
+        tokio::spawn(async move {
+            loop {
+                if let Err(err) = self.listen_for_notifications().await {
+                    warn!(err = %err, "notification listener failed");
+                }
+                sleep(Duration::from_secs(5)).await;
+            }
+        });
+    }
+
+    async fn listen_for_notifications(&self) -> anyhow::Result<()> {
+        let mut listener = PgListener::connect_with(&self.db).await?;
+        listener.listen(NOTIFICATION_RECEIVED_CHANNEL).await?;
+        info!("listening for new notifications");
+
+        loop {
+            let notification = listener.recv().await?;
+
+            let Ok(received) = serde_json::from_str(notification.payload()) else {
+                warn!(
+                    payload = notification.payload(),
+                    "invalid notification_received payload"
+                );
+                continue;
+            };
+
+            // there may be no subscribers at all
+            let _ = self.received.send(received);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bfx_core::service::database::Db;
+
+    async fn insert_notification(db: &Db, user_id: i64, in_app: bool) -> i64 {
+        sqlx::query_scalar(
+            "insert into notification.notifications
+             (user_id, definition_id, category, in_app, data, params)
+             values ($1, 'test', 'announcements', $2, '{}', '')
+             returning id",
+        )
+        .bind(user_id)
+        .bind(in_app)
+        .fetch_one(db)
+        .await
+        .unwrap()
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn announce_in_app_notifications(db: Db) {
+        let mut listener = PgListener::connect_with(&db).await.unwrap();
+        listener
+            .listen(NOTIFICATION_RECEIVED_CHANNEL)
+            .await
+            .unwrap();
+
+        // email-only notifications have nothing to show
+        insert_notification(&db, 1, false).await;
+        let id = insert_notification(&db, 2, true).await;
+
+        let notification = listener.recv().await.unwrap();
+        let received: NotificationReceived = serde_json::from_str(notification.payload()).unwrap();
+        assert_eq!(received.id, id);
+        assert_eq!(received.user_id, 2);
+    }
+}
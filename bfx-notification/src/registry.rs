@@ -0,0 +1,145 @@
+use crate::definition::NotificationDefinition;
+use anyhow::{Context, bail};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::info;
+
+/// Notification definitions by ID, loaded once at startup
+#[derive(Default)]
+pub struct DefinitionRegistry {
+    definitions: HashMap<String, Arc<NotificationDefinition>>,
+}
+
+impl DefinitionRegistry {
+    /// Load every `*.yml` definition in a directory
+    ///
+    /// Definitions are parsed strictly, so anything that `definition-schema.json` rejects
+    /// (unknown or missing properties, wrong types) fails to load.
+    ///
+    /// # Errors
+    ///
+    /// - If the directory can't be read
+    /// - If a definition is invalid
+    /// - If the file name doesn't match the ID of the definition
+    /// - If two files define the same ID (like `welcome.yml` and `welcome.yaml`)
+    pub fn load(dir: &Path) -> anyhow::Result<Self> {
+        let mut definitions = HashMap::new();
+        let mut paths = HashMap::<String, PathBuf>::new();
+
+        let entries = dir
+            .read_dir()
+            .with_context(|| format!("reading notification definitions in {}", dir.display()))?;
+
+        for entry in entries {
+            let path = entry?.path();
+            if !path
+                .extension()
+                .is_some_and(|ext| ext == "yml" || ext == "yaml")
+            {
+                continue;
+            }
+
+            let definition = Self::load_file(&path)
+                .with_context(|| format!("invalid notification definition {}", path.display()))?;
+
+            if let Some(previous) = paths.insert(definition.id.clone(), path.clone()) {
+                bail!(
+                    "notification definition `{}` is defined by both {} and {}",
+                    definition.id,
+                    previous.display(),
+                    path.display()
+                );
+            }
+
+            definitions.insert(definition.id.clone(), Arc::new(definition));
+        }
+
+        info!(count = definitions.len(), "loaded notification definitions");
+
+        Ok(Self { definitions })
+    }
+
+    fn load_file(path: &Path) -> anyhow::Result<NotificationDefinition> {
+        let definition: NotificationDefinition =
+            serde_yml::from_str(&std::fs::read_to_string(path)?)?;
+
+        // the file name is the ID, so IDs are unique and easy to find
+        if path.file_stem().and_then(|stem| stem.to_str()) != Some(definition.id.as_str()) {
+            bail!("file name doesn't match id `{}`", definition.id);
+        }
+
+        if definition.in_app.is_none() && definition.email.is_none() {
+            bail!("no channel (`in-app` or `email`) is defined");
+        }
+
+        Ok(definition)
+    }
+
+    /// Get a definition by ID
+    #[must_use]
+    pub fn get(&self, id: &str) -> Option<Arc<NotificationDefinition>> {
+        self.definitions.get(id).cloned()
+    }
+
+    /// Iterate over all definitions, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = &NotificationDefinition> {
+        self.definitions.values().map(AsRef::as_ref)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::definition::NotificationCategory;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures/definitions")
+            .join(name)
+    }
+
+    #[test]
+    fn load() {
+        let registry = DefinitionRegistry::load(&fixture("valid")).unwrap();
+
+        let welcome = registry.get("welcome").unwrap();
+        assert_eq!(welcome.category, NotificationCategory::Announcements);
+        assert!(welcome.in_app.is_some());
+        assert!(welcome.email.is_none());
+        assert!(registry.get("weekly_summary").unwrap().email.is_some());
+        assert!(registry.get("README").is_none());
+        assert_eq!(registry.iter().count(), 2);
+    }
+
+    #[test]
+    fn load_repo_definitions() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../notifications");
+        let registry = DefinitionRegistry::load(&dir).unwrap();
+
+        assert!(registry.get("password_reset").is_some());
+    }
+
+    #[test]
+    fn invalid_definitions() {
+        for name in [
+            "wrong_file_name",
+            "no_channel",
+            "unknown_property",
+            "missing",
+        ] {
+            assert!(DefinitionRegistry::load(&fixture(name)).is_err(), "{name}");
+        }
+    }
+
+    #[test]
+    fn duplicate_ids() {
+        let err = DefinitionRegistry::load(&fixture("duplicate"))
+            .err()
+            .unwrap()
+            .to_string();
+
+        assert!(err.contains("welcome.yml"), "{err}");
+        assert!(err.contains("welcome.yaml"), "{err}");
+    }
+}
@@ -0,0 +1,165 @@
+use crate::NotificationService;
+use crate::models::notification::RawScheduledNotification;
+use bfx_core::status::{ErrorCode, StatusExt};
+use bfx_proto::auth::User;
+use bfx_proto::notification::NotificationParams;
+use prost::Message;
+use std::time::Duration;
+use tokio::time::sleep;
+use tonic::{Code, Status};
+use tracing::{error, warn};
+
+const SCHEDULER_INTERVAL: Duration = Duration::from_secs(10);
+/// Failed deliveries are retried every 5 minutes, up to this many times
+const MAX_ATTEMPTS: i32 = 5;
+
+impl NotificationService {
+    /// Periodically deliver scheduled notifications that are due
+    pub fn start_scheduler(self) {
+        tokio::spawn(async move {
+            loop {
+                if let Err(err) = self.deliver_due_notifications().await {
+                    warn!(err = %err, "failed to deliver scheduled notifications");
+                }
+                sleep(SCHEDULER_INTERVAL).await;
+            }
+        });
+    }
+
+    /// Deliver all scheduled notifications that are due
+    ///
+    /// A scheduled notification is locked while it's delivered and deleted in the same
+    /// transaction that stores the notification, so it's delivered exactly once even with
+    /// multiple replicas. Only if a replica dies between sending the email and committing,
+    /// the email is sent again.
+    async fn deliver_due_notifications(&self) -> Result<(), Status> {
+        loop {
+            let mut tx = self.db.begin().await.map_err(Status::db)?;
+
+            let Some(scheduled) = sqlx::query_as!(
+                RawScheduledNotification,
+                "select * from notification.scheduled_notifications
+                 where deliver_at <= now()
+                 order by deliver_at
+                 limit 1
+                 for update skip locked"
+            )
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(Status::db)?
+            else {
+                return Ok(());
+            };
+
+            let (id, attempts) = (scheduled.id, scheduled.attempts);
+
+            let result = self.deliver_scheduled(&mut tx, scheduled).await;
+            let Err(err) = result else {
+                tx.commit().await.map_err(Status::db)?;
+                continue;
+            };
+
+            // start over, the transaction may be aborted
+            drop(tx);
+
+            if attempts + 1 >= MAX_ATTEMPTS {
+                error!(id, err = %err, "giving up on scheduled notification");
+                sqlx::query!(
+                    "delete from notification.scheduled_notifications where id = $1",
+                    id,
+                )
+                .execute(&self.db)
+                .await
+                .map_err(Status::db)?;
+            } else {
+                warn!(id, err = %err, "failed to deliver scheduled notification");
+                sqlx::query!(
+                    "update notification.scheduled_notifications
+                     set deliver_at = now() + interval '5 minutes', attempts = attempts + 1
+                     where id = $1",
+                    id,
+                )
+                .execute(&self.db)
+                .await
+                .map_err(Status::db)?;
+            }
+        }
+    }
+
+    /// Deliver a scheduled notification, unless the user has quiet hours now
+    ///
+    /// # Errors
+    ///
+    /// - If the stored params can't be decoded
+    /// - See [`NotificationService::deliver_notification`]
+    async fn deliver_scheduled(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        scheduled: RawScheduledNotification,
+    ) -> Result<(), Status> {
+        let Some(definition) = self.definitions.get(&scheduled.definition_id) else {
+            warn!(
+                scheduled.id,
+                scheduled.definition_id, "definition of scheduled notification was removed"
+            );
+            return Self::delete_scheduled(tx, scheduled.id).await;
+        };
+
+        let preferences = self
+            .get_raw_notification_preferences(scheduled.user_id)
+            .await?;
+
+        // the quiet hours may have changed since the notification was scheduled
+        if let Some(deliver_at) = self
+            .delivery_delay(definition.category, &preferences, None)
+            .await?
+        {
+            sqlx::query!(
+                "update notification.scheduled_notifications set deliver_at = $2 where id = $1",
+                scheduled.id,
+                deliver_at,
+            )
+            .execute(&mut **tx)
+            .await
+            .map_err(Status::db)?;
+
+            return Ok(());
+        }
+
+        let params = NotificationParams::decode(scheduled.params.as_slice())
+            .map_err(|err| Status::coded(Code::Internal, ErrorCode::Internal).with_source(err))?
+            .params;
+        let user_override = scheduled
+            .user_override
+            .map(|user| User::decode(user.as_slice()))
+            .transpose()
+            .map_err(|err| Status::coded(Code::Internal, ErrorCode::Internal).with_source(err))?;
+
+        self.deliver_notification(
+            tx,
+            &definition,
+            scheduled.user_id,
+            user_override,
+            params,
+            &preferences,
+        )
+        .await?;
+
+        Self::delete_scheduled(tx, scheduled.id).await
+    }
+
+    async fn delete_scheduled(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        id: i64,
+    ) -> Result<(), Status> {
+        sqlx::query!(
+            "delete from notification.scheduled_notifications where id = $1",
+            id,
+        )
+        .execute(&mut **tx)
+        .await
+        .map_err(Status::db)?;
+
+        Ok(())
+    }
+}
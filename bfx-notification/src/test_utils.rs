@@ -0,0 +1,52 @@
+use crate::NotificationService;
+use crate::definition::{NotificationCategory, NotificationDefinition};
+use crate::registry::DefinitionRegistry;
+use bfx_core::service::database::Db;
+use tonic::transport::Channel;
+
+impl NotificationService {
+    /// Make a service for tests, without definitions
+    ///
+    /// Nothing listens on the router, so anything that needs another service fails.
+    pub(crate) fn for_tests(db: Db) -> Self {
+        Self::new(
+            db,
+            Channel::from_static("http://localhost:1").connect_lazy(),
+            DefinitionRegistry::default(),
+        )
+    }
+}
+
+/// Get a definition with only in-app content
+pub fn in_app_definition(category: NotificationCategory) -> NotificationDefinition {
+    serde_yml::from_str(&format!(
+        "
+        id: test_in_app
+        category: {}
+        in-app:
+          title: title
+          body: body
+        ",
+        category.as_str()
+    ))
+    .unwrap()
+}
+
+/// Get a definition with in-app content and an email
+pub fn email_definition(category: NotificationCategory) -> NotificationDefinition {
+    serde_yml::from_str(&format!(
+        "
+        id: test_email
+        category: {}
+        in-app:
+          title: title
+          body: body
+        email:
+          subject: subject
+          body: body
+          is-list: false
+        ",
+        category.as_str()
+    ))
+    .unwrap()
+}
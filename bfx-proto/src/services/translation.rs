@@ -1,5 +1,7 @@
 use crate::translation::translation_client::TranslationClient;
-use crate::translation::{ConditionalString, RenderStringSetRequest, RenderTemplateRequest};
+use crate::translation::{
+    ConditionalString, RenderStringSetRequest, RenderStringSetsRequest, RenderTemplateRequest,
+};
 use crate::{ParamValue, ext_impl, param_value};
 use std::collections::HashMap;
 use tonic::Status;
@@ -40,6 +42,18 @@ ext_impl!(TranslationClient, {
 
         Ok(resp.output)
     }
+
+    pub async fn render_string_sets_ext(
+        &mut self,
+        string_sets: Vec<RenderStringSetRequest>,
+    ) -> Result<Vec<String>, Status> {
+        let resp = self
+            .render_string_sets(RenderStringSetsRequest { string_sets })
+            .await?
+            .into_inner();
+
+        Ok(resp.outputs)
+    }
 });
 
 // === convenience macro for creating map<string, ParamValue> ===
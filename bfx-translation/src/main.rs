@@ -8,8 +8,8 @@ use bfx_core::service::database::{Db, require_db};
 use bfx_core::service::start_service;
 use bfx_proto::translation::translation_server::{Translation, TranslationServer};
 use bfx_proto::translation::{
-    RenderStringSetRequest, RenderTemplateReply, RenderTemplateRequest, TranslateReply,
-    TranslateRequest, WriteFileReply, WriteFileRequest,
+    RenderStringSetRequest, RenderStringSetsReply, RenderStringSetsRequest, RenderTemplateReply,
+    RenderTemplateRequest, TranslateReply, TranslateRequest, WriteFileReply, WriteFileRequest,
 };
 use dashmap::DashMap;
 use std::sync::Arc;
@@ -83,4 +83,11 @@ impl Translation for TranslationService {
     ) -> Result<Response<RenderTemplateReply>, Status> {
         self.render_string_set(request)
     }
+
+    async fn render_string_sets(
+        &self,
+        request: Request<RenderStringSetsRequest>,
+    ) -> Result<Response<RenderStringSetsReply>, Status> {
+        self.render_string_sets(request)
+    }
 }
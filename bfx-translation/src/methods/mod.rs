@@ -1,4 +1,5 @@
 mod render_string_set;
+mod render_string_sets;
 mod render_template;
 mod translate;
 mod write_file;
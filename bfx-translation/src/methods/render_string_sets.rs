@@ -0,0 +1,27 @@
+use crate::TranslationService;
+use bfx_proto::translation::{RenderStringSetsReply, RenderStringSetsRequest};
+use tonic::{Request, Response, Status};
+
+impl TranslationService {
+    /// Render many sets of conditional strings, in one request instead of one per set
+    ///
+    /// # Errors
+    ///
+    /// - If rendering any of the sets fails, like [`Self::render_string_set`]
+    pub fn render_string_sets(
+        &self,
+        request: Request<RenderStringSetsRequest>,
+    ) -> Result<Response<RenderStringSetsReply>, Status> {
+        let outputs = request
+            .into_inner()
+            .string_sets
+            .into_iter()
+            .map(|string_set| {
+                self.render_string_set(Request::new(string_set))
+                    .map(|reply| reply.into_inner().output)
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Response::new(RenderStringSetsReply { outputs }))
+    }
+}
@@ -0,0 +1,9 @@
+delete from auth_oauth.flows where pkce_verifier is null;
+
+alter table auth_oauth.flows
+    alter column pkce_verifier set not null,
+    drop column saml_code,
+    drop column saml_subject,
+    drop column saml_email;
+
+drop table auth_oauth.saml_providers;
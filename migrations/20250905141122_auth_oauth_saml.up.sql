@@ -0,0 +1,14 @@
+create table auth_oauth.saml_providers (
+    id bigint not null generated always as identity primary key,
+    entity_id text not null unique,
+    metadata text not null,
+    email_attribute text not null default 'urn:oid:0.9.2342.19200300.100.1.3',
+    subject_attribute text null,
+    created_at timestamptz not null default now()
+);
+
+alter table auth_oauth.flows
+    alter column pkce_verifier drop not null,
+    add column saml_code text null,
+    add column saml_subject text null,
+    add column saml_email text null;
@@ -0,0 +1,9 @@
+drop table auth_oauth_provider.client_assertions;
+
+alter table auth_oauth_provider.clients
+    add column client_secret text not null default gen_random_uuid()::text,
+    drop column client_secret_hash,
+    drop column jwks;
+
+alter table auth_oauth_provider.clients
+    alter column client_secret drop default;
@@ -0,0 +1,19 @@
+alter table auth_oauth_provider.clients
+    add column client_secret_hash bytea null,
+    add column jwks text null;
+
+update auth_oauth_provider.clients
+set client_secret_hash = sha256(convert_to(client_secret, 'UTF8'));
+
+alter table auth_oauth_provider.clients
+    alter column client_secret_hash set not null,
+    drop column client_secret;
+
+create table auth_oauth_provider.client_assertions (
+    client_id bigint not null references auth_oauth_provider.clients (id) on delete cascade,
+    jti text not null,
+    expires_at timestamptz not null,
+    primary key (client_id, jti)
+);
+
+create index on auth_oauth_provider.client_assertions (expires_at);
@@ -0,0 +1,7 @@
+drop table auth_oauth_provider.device_authorizations;
+
+delete from auth_oauth_provider.flows
+where redirect_uri is null;
+
+alter table auth_oauth_provider.flows
+    alter column redirect_uri set not null;
@@ -0,0 +1,18 @@
+alter table auth_oauth_provider.flows
+    alter column redirect_uri drop not null;
+
+create table auth_oauth_provider.device_authorizations (
+    id bigint not null generated always as identity primary key,
+    client_id bigint not null references auth_oauth_provider.clients (id) on delete cascade,
+    scopes text[] not null,
+    device_code text not null unique,
+    user_code text not null unique,
+    poll_interval integer not null,
+    last_polled_at timestamptz null,
+    flow_id bigint null references auth_oauth_provider.flows (id) on delete cascade,
+    denied boolean not null default false,
+    created_at timestamptz not null default now(),
+    expires_at timestamptz not null
+);
+
+create index on auth_oauth_provider.device_authorizations (expires_at);
@@ -0,0 +1,5 @@
+delete from auth_oauth_provider.flows
+where user_id is null;
+
+alter table auth_oauth_provider.flows
+    alter column user_id set not null;
@@ -0,0 +1,2 @@
+alter table auth_oauth_provider.flows
+    alter column user_id drop not null;
@@ -0,0 +1,4 @@
+alter table auth_oauth_provider.clients
+    drop column id_token_signed_response_alg;
+
+drop table auth_oauth_provider.signing_keys;
@@ -0,0 +1,16 @@
+create table auth_oauth_provider.signing_keys (
+    id bigint not null generated always as identity primary key,
+    kid text not null unique,
+    alg text not null,
+    state text not null,
+    private_key text not null,
+    created_at timestamptz not null default now(),
+    activated_at timestamptz null,
+    retired_at timestamptz null
+);
+
+create unique index on auth_oauth_provider.signing_keys (alg) where state = 'active';
+create unique index on auth_oauth_provider.signing_keys (alg) where state = 'next';
+
+alter table auth_oauth_provider.clients
+    add column id_token_signed_response_alg text not null default 'RS256';
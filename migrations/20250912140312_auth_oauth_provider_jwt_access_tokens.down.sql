@@ -0,0 +1,2 @@
+alter table auth_oauth_provider.clients
+    drop column jwt_access_tokens;
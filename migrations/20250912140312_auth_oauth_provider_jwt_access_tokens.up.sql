@@ -0,0 +1,2 @@
+alter table auth_oauth_provider.clients
+    add column jwt_access_tokens boolean not null default false;
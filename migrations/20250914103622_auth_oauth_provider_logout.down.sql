@@ -0,0 +1,3 @@
+alter table auth_oauth_provider.clients
+    drop column post_logout_redirect_uris,
+    drop column backchannel_logout_uri;
@@ -0,0 +1,3 @@
+alter table auth_oauth_provider.clients
+    add column post_logout_redirect_uris text[] not null default '{}',
+    add column backchannel_logout_uri text null;
@@ -0,0 +1,3 @@
+alter table auth_oauth_provider.clients
+    drop column subject_type,
+    drop column sector_identifier;
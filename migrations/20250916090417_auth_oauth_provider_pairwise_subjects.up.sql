@@ -0,0 +1,3 @@
+alter table auth_oauth_provider.clients
+    add column subject_type text not null default 'public',
+    add column sector_identifier text null;
@@ -0,0 +1,4 @@
+drop table auth_oauth_provider.pushed_authorization_requests;
+
+alter table auth_oauth_provider.clients
+    drop column require_pushed_authorization_requests;
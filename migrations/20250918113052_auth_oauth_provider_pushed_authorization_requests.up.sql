@@ -0,0 +1,13 @@
+alter table auth_oauth_provider.clients
+    add column require_pushed_authorization_requests boolean not null default false;
+
+create table auth_oauth_provider.pushed_authorization_requests (
+    id bigint not null generated always as identity primary key,
+    client_id bigint not null references auth_oauth_provider.clients (id) on delete cascade,
+    request_uri text not null unique,
+    params jsonb not null,
+    created_at timestamptz not null default now(),
+    expires_at timestamptz not null
+);
+
+create index on auth_oauth_provider.pushed_authorization_requests (expires_at);
@@ -0,0 +1 @@
+drop table auth_oauth_provider.used_refresh_tokens;
@@ -0,0 +1,8 @@
+create table auth_oauth_provider.used_refresh_tokens (
+    id bigint not null generated always as identity primary key,
+    flow_id bigint not null references auth_oauth_provider.flows (id) on delete cascade,
+    refresh_token text not null unique,
+    used_at timestamptz not null default now()
+);
+
+create index on auth_oauth_provider.used_refresh_tokens (flow_id, used_at);
@@ -0,0 +1,4 @@
+drop table auth_oauth_provider.dpop_proofs;
+
+alter table auth_oauth_provider.flows
+    drop column dpop_jkt;
@@ -0,0 +1,11 @@
+alter table auth_oauth_provider.flows
+    add column dpop_jkt text null;
+
+create table auth_oauth_provider.dpop_proofs (
+    jkt text not null,
+    jti text not null,
+    expires_at timestamptz not null,
+    primary key (jkt, jti)
+);
+
+create index on auth_oauth_provider.dpop_proofs (expires_at);
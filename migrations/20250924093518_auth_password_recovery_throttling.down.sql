@@ -0,0 +1 @@
+drop table auth_password_recovery.recovery_attempts;
@@ -0,0 +1,10 @@
+create table auth_password_recovery.recovery_attempts (
+    id bigint not null generated always as identity primary key,
+    email text not null,
+    ip text not null,
+    email_sent bool not null default false,
+    created_at timestamptz not null default now()
+);
+
+create index on auth_password_recovery.recovery_attempts (email, created_at);
+create index on auth_password_recovery.recovery_attempts (ip, created_at);
@@ -0,0 +1,10 @@
+alter table auth_password_recovery.password_reset_requests
+    add column token text not null default gen_random_uuid()::text,
+    drop column token_hash,
+    drop column expires_at,
+    drop column invalidated_at;
+
+alter table auth_password_recovery.password_reset_requests
+    alter column token drop default;
+
+create unique index on auth_password_recovery.password_reset_requests (token);
@@ -0,0 +1,16 @@
+alter table auth_password_recovery.password_reset_requests
+    add column token_hash bytea null,
+    add column expires_at timestamptz null,
+    add column invalidated_at timestamptz null;
+
+update auth_password_recovery.password_reset_requests
+set token_hash = sha256(convert_to(token, 'UTF8')),
+    expires_at = created_at + interval '24 hours';
+
+alter table auth_password_recovery.password_reset_requests
+    alter column token_hash set not null,
+    alter column expires_at set not null,
+    drop column token;
+
+create unique index on auth_password_recovery.password_reset_requests (token_hash);
+create index on auth_password_recovery.password_reset_requests (user_id);
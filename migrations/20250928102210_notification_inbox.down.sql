@@ -0,0 +1,3 @@
+alter table notification.notifications
+    drop column category,
+    drop column in_app;
@@ -0,0 +1,13 @@
+alter table notification.notifications
+    add column category text null,
+    add column in_app bool null;
+
+update notification.notifications
+set category = data -> 'definition' ->> 'category',
+    in_app = jsonb_typeof(data -> 'definition' -> 'in-app') = 'object';
+
+alter table notification.notifications
+    alter column category set not null,
+    alter column in_app set not null;
+
+create index on notification.notifications (user_id, id) where in_app;
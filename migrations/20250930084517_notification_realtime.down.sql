@@ -0,0 +1,2 @@
+drop trigger notification_received on notification.notifications;
+drop function notification.notify_notification_received();
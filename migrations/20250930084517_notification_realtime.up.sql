@@ -0,0 +1,16 @@
+create or replace function notification.notify_notification_received() returns trigger as
+$$
+begin
+    perform pg_notify(
+        'notification_received',
+        json_build_object('id', new.id, 'user_id', new.user_id)::text
+    );
+    return null;
+end;
+$$ language plpgsql;
+
+create trigger notification_received
+    after insert on notification.notifications
+    for each row
+    when (new.in_app)
+execute function notification.notify_notification_received();
@@ -0,0 +1,8 @@
+create table notification.category_preferences (
+    user_id bigint not null,
+    category text not null,
+    in_app boolean not null,
+    email boolean not null,
+    updated_at timestamptz not null default now(),
+    primary key (user_id, category)
+);
@@ -0,0 +1,8 @@
+drop index notification.notifications_email_digest_pending_idx;
+
+alter table notification.notifications
+    drop column email_digest_pending;
+
+alter table notification.preferences
+    drop column digest_frequency,
+    drop column last_digest_at;
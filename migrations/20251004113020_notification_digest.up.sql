@@ -0,0 +1,11 @@
+alter table notification.preferences
+    add column digest_frequency text not null default 'immediate',
+    add column last_digest_at timestamptz null;
+
+-- emails that are waiting for the next digest of the user
+alter table notification.notifications
+    add column email_digest_pending boolean not null default false;
+
+create index notifications_email_digest_pending_idx
+    on notification.notifications (user_id)
+    where email_digest_pending;
@@ -0,0 +1,9 @@
+drop function notification.quiet_hours_end(timestamptz, text, int, int);
+
+drop table notification.scheduled_notifications;
+
+alter table notification.preferences
+    drop constraint preferences_quiet_hours_check,
+    drop column timezone,
+    drop column quiet_hours_start,
+    drop column quiet_hours_end;
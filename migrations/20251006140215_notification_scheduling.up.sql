@@ -0,0 +1,59 @@
+alter table notification.preferences
+    add column timezone text not null default 'UTC',
+    -- minutes after local midnight, the window may wrap around midnight
+    add column quiet_hours_start int null,
+    add column quiet_hours_end int null,
+    add constraint preferences_quiet_hours_check check (
+        (quiet_hours_start is null) = (quiet_hours_end is null)
+        and quiet_hours_start between 0 and 1439
+        and quiet_hours_end between 0 and 1439
+    );
+
+-- notifications that are delivered later, because of `send_at` or quiet hours
+create table notification.scheduled_notifications (
+    id bigint not null generated always as identity primary key,
+    user_id bigint not null,
+    definition_id text not null,
+    params bytea not null,
+    -- encoded bfx.auth.User
+    user_override bytea null,
+    deliver_at timestamptz not null,
+    -- failed delivery attempts
+    attempts int not null default 0,
+    created_at timestamptz not null default now()
+);
+
+create index scheduled_notifications_deliver_at_idx
+    on notification.scheduled_notifications (deliver_at);
+
+-- end of the quiet hours that `at` falls into, or null if it isn't in quiet hours
+create function notification.quiet_hours_end(
+    at timestamptz,
+    timezone text,
+    start_minute int,
+    end_minute int
+) returns timestamptz as
+$$
+declare
+    local_time timestamp := at at time zone timezone;
+    local_minute int := extract(hour from local_time) * 60 + extract(minute from local_time);
+    local_end timestamp;
+begin
+    if start_minute = end_minute then
+        return null;
+    elsif start_minute < end_minute then
+        if local_minute < start_minute or local_minute >= end_minute then
+            return null;
+        end if;
+    elsif local_minute < start_minute and local_minute >= end_minute then
+        return null;
+    end if;
+
+    local_end := date_trunc('day', local_time) + make_interval(mins => end_minute);
+    if local_end <= local_time then
+        local_end := local_end + interval '1 day';
+    end if;
+
+    return local_end at time zone timezone;
+end;
+$$ language plpgsql stable;
@@ -0,0 +1,5 @@
+alter table auth_oauth.flows
+    drop column saml_email_verified;
+
+alter table auth_oauth.saml_providers
+    drop column email_domains;
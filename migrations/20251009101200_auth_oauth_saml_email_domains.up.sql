@@ -0,0 +1,6 @@
+-- an IdP is only trusted to assert emails of these domains
+alter table auth_oauth.saml_providers
+    add column email_domains text[] not null default '{}';
+
+alter table auth_oauth.flows
+    add column saml_email_verified boolean not null default false;
@@ -0,0 +1,3 @@
+-- resource servers can introspect tokens of other clients
+alter table auth_oauth_provider.clients
+    add column resource_server boolean not null default false;
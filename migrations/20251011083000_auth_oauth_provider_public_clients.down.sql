@@ -0,0 +1,2 @@
+alter table auth_oauth_provider.clients
+    drop column token_endpoint_auth_method;
@@ -0,0 +1,3 @@
+-- `none` for public clients, which only send their client_id to the token endpoint
+alter table auth_oauth_provider.clients
+    add column token_endpoint_auth_method text not null default 'client_secret_basic';
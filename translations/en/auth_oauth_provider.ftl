@@ -0,0 +1,8 @@
+oauth-grant-revoked-subject = {$client} no longer has access to your Bonfire account
+oauth-grant-revoked-text = The access of {$client} to your Bonfire account has been revoked. The app will need to ask for your permission again to access your account.
+oauth-grant-revoked-title = Access for {$client} revoked
+oauth-grant-revoked-body = {$client} can no longer access your Bonfire account. Contact support if this was not you.
+oauth-refresh-token-reused-subject = We revoked the access of {$client} to your Bonfire account
+oauth-refresh-token-reused-text = A login of {$client} to your Bonfire account was used more than once, which can mean that it was stolen. To keep your account safe, we revoked the access of {$client}. The app will need to ask for your permission again to access your account.
+oauth-refresh-token-reused-title = Access for {$client} revoked
+oauth-refresh-token-reused-body = A login of {$client} may have been stolen, so we revoked its access to your Bonfire account. Consider changing your password if this keeps happening.
@@ -1,6 +1,10 @@
 email-reset-password-subject = Reset your password for Bonfire
 email-reset-password-1 = Someone has requested a password reset for your account. If this was you, please click the link below to reset your password.
-email-reset-password-2 = The link expires in 24 hours. If you did not request a password reset, please ignore this email.
+email-reset-password-2 = The link expires in { $hours } hours. If you did not request a password reset, please ignore this email.
 
 password-reset-notification-title = Someone requested a password reset for your account
 password-reset-notification-body = Heads up! Someone tried to send a password reset link to your email address.
+
+email-reset-password-unknown-subject = Password reset attempt for Bonfire
+email-reset-password-unknown-1 = Someone tried to reset a password for a Bonfire account with this email address, but there is no account with this address.
+email-reset-password-unknown-2 = If this was you, you may have signed up with a different email address. Otherwise, you can safely ignore this email.
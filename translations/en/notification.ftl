@@ -0,0 +1,5 @@
+email-digest-subject = { $count ->
+    [one] You have a new notification
+   *[other] You have { $count } new notifications
+}
+email-digest-intro = Here's what happened since your last update: